@@ -1,6 +1,9 @@
 // src/cli.rs
 use clap::{Parser, Subcommand};
 
+use crate::commands::{DEFAULT_MAX_SUBJECT_LEN, DEFAULT_PR_MAX_COMMITS, PREVIEW_COMMANDS};
+use crate::git::DEFAULT_DIFF_CONTEXT;
+
 #[derive(Parser)]
 #[command(
     name = "gitar",
@@ -16,6 +19,7 @@ use clap::{Parser, Subcommand};
 
     gitar explain v1.0.0            # Explain changes since tag
     gitar explain --staged          # Explain staged changes
+    gitar explain --audience qa     # Explain for QA (qa, security, support, developer)
 
     gitar pr develop                # PR description against develop
     gitar pr --staged               # PR from staged changes
@@ -26,9 +30,11 @@ use clap::{Parser, Subcommand};
     gitar version v1.0.0            # Version bump since tag
 
     gitar diff --compare            # Compare smart diff algorithms
+    gitar diff --algo-bench         # Time each algorithm on the diff
     gitar commit --alg 3            # Use hunk-level analysis for large refactors
 
 DIFF ALGORITHMS:
+    --alg 0    Auto: full if it fits, files if moderately over, semantic if much larger
     --alg 1    Full: complete git diff (ignores --max-chars)
     --alg 2    Files: selective files, ranked by priority
     --alg 3    Hunks: selective hunks, ranked by importance
@@ -37,6 +43,15 @@ DIFF ALGORITHMS:
 pub struct Cli {
     #[arg(long, global = true)]
     pub api_key: Option<String>,
+    /// Sampling seed for reproducible outputs (OpenAI-compatible providers only)
+    #[arg(long, global = true)]
+    pub seed: Option<u64>,
+    /// Reasoning effort for reasoning models (OpenAI o-series/gpt-5, Claude extended thinking, also on Bedrock)
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = ["low", "medium", "high"])]
+    pub reasoning_effort: Option<String>,
+    /// Read the API key from a file (whitespace trimmed)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub api_key_file: Option<String>,
     #[arg(long, global = true)]
     pub model: Option<String>,
     #[arg(long, global = true)]
@@ -50,14 +65,56 @@ pub struct Cli {
     #[arg(
         long,
         global = true,
-        value_parser = ["openai", "claude", "gemini", "google", "groq", "ollama", "local"]
+        value_parser = [
+            "openai", "claude", "gemini", "google", "groq", "openrouter", "ollama", "local", "bedrock", "aws",
+        ]
     )]
     pub provider: Option<String>,
 
+    /// Fail instead of making any network request (LLM calls, model lists)
+    #[arg(long, global = true, env = "GITAR_OFFLINE", value_parser = clap::builder::BoolishValueParser::new())]
+    pub offline: bool,
+
+    /// Extra instruction appended to the system prompt for this run, e.g. "focus on security"
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub instruct: Option<String>,
+
+    /// Print the tokens used (prompt and completion) to stderr when the command ends
+    #[arg(long, global = true)]
+    pub show_usage: bool,
+
     /// Stream responses to stdout (when supported by the provider).
     #[arg(long, global = true, default_value_t = false)]
     pub stream: bool,
 
+    /// Lines of context around each change in diffs (git diff --unified)
+    #[arg(long, global = true, default_value_t = DEFAULT_DIFF_CONTEXT)]
+    pub context: u32,
+
+    /// Semantic diffs (--alg 4): preview only changed lines, without context
+    #[arg(long, global = true, default_value_t = false)]
+    pub changed_only: bool,
+
+    /// Diff words instead of lines (git diff --word-diff=porcelain), less noise on prose
+    #[arg(long, global = true, default_value_t = false)]
+    pub word_diff: bool,
+
+    /// Files algorithm (--alg 2): include at most N files, by priority, even if more fit
+    #[arg(long, global = true, value_name = "N")]
+    pub max_files: Option<usize>,
+
+    /// Gitmoji-style commit messages (`:sparkles: feat: ...`); also `emoji = true` in the config
+    #[arg(long, global = true, default_value_t = false)]
+    pub emoji: bool,
+
+    /// Disable colored output (also honors NO_COLOR)
+    #[arg(long, global = true, default_value_t = false)]
+    pub no_color: bool,
+
+    /// Suppress progress lines and diff stats (results still go to stdout, errors to stderr)
+    #[arg(short = 'q', long, global = true, default_value_t = false)]
+    pub quiet: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -73,6 +130,11 @@ pub enum Commands {
         #[arg(short = 'p', long)]
         push: bool,
 
+        /// With --push, always push to origin and set it as upstream (`git push -u`).
+        /// Without it, this only happens when the branch has no upstream yet.
+        #[arg(long, requires = "push")]
+        set_upstream: bool,
+
         /// Stage all changes before committing (`git add -A`)
         #[arg(short = 'a', long)]
         all: bool,
@@ -85,6 +147,61 @@ pub enum Commands {
         #[arg(long = "no-tag")]
         no_tag: bool,
 
+        /// Add a Signed-off-by trailer (`git commit -s`)
+        #[arg(short = 's', long)]
+        signoff: bool,
+
+        /// GPG-sign the commit (`git commit -S`), optionally with a key id: --gpg-sign=KEYID
+        #[arg(
+            short = 'S',
+            long,
+            value_name = "KEYID",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = ""
+        )]
+        gpg_sign: Option<String>,
+
+        /// Do not sign, even if `sign_commits` is set in the config
+        #[arg(long, conflicts_with = "gpg_sign")]
+        no_gpg_sign: bool,
+
+        /// Describe and commit only staged changes, like `git commit` (errors if nothing is staged)
+        #[arg(long, conflicts_with_all = ["all", "interactive_split"])]
+        staged_only: bool,
+
+        /// Open the generated message in $EDITOR before committing
+        #[arg(long, conflicts_with = "write_to")]
+        edit: bool,
+
+        /// Show the review menu even when stdin is not a terminal (otherwise the first message is accepted)
+        #[arg(long, conflicts_with_all = ["write_to", "silent"])]
+        interactive: bool,
+
+        /// Commit in chunks: stage a subset, generate, commit, repeat until clean
+        #[arg(long, alias = "until-clean", conflicts_with_all = ["all", "write_to"])]
+        interactive_split: bool,
+
+        /// Force the subject scope, e.g. `feat(SCOPE): ...` (overrides the one guessed from paths)
+        #[arg(long, value_name = "SCOPE", conflicts_with = "interactive_split")]
+        scope: Option<String>,
+
+        /// Subject line only: ask for no body and drop any the model adds
+        #[arg(long, conflicts_with = "interactive_split")]
+        no_body: bool,
+
+        /// Put the ticket id from the branch name (`feature/PROJ-123-x`) in the subject,
+        /// before it (default) or after it; the pattern is `ticket_pattern` in the config
+        #[arg(
+            long,
+            value_name = "WHERE",
+            num_args = 0..=1,
+            default_missing_value = "prefix",
+            value_parser = ["prefix", "suffix"],
+            conflicts_with = "interactive_split"
+        )]
+        ticket_from_branch: Option<String>,
+
         /// Write commit message to file instead of committing (used by git hooks)
         #[arg(long, hide = true)]
         write_to: Option<String>,
@@ -98,27 +215,39 @@ pub enum Commands {
         #[arg(long, default_value = "false")]
         stream: bool,
 
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Use the last N commit subjects as style examples (gitar-tagged commits are skipped)
+        #[arg(long, value_name = "N")]
+        style_from_history: Option<usize>,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Generate an AI commit message for currently staged changes
     ///
     /// Prints the message to stdout (does not create a commit).
     Staged {
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Only describe changes matching this pathspec (repeatable)
+        #[arg(long = "path", value_name = "PATHSPEC")]
+        paths: Vec<String>,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Generate an AI commit message for unstaged working tree changes
     ///
     /// Prints the message to stdout (does not create a commit).
     Unstaged {
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Also describe untracked files, as new files (nothing is staged)
+        #[arg(long)]
+        include_untracked: bool,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Describe a range of commits in plain English (does not modify history)
@@ -150,9 +279,113 @@ pub enum Commands {
         #[arg(long, default_value = "500")]
         delay: u64,
 
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Send the full original message (subject and body) to the model, not just the subject
+        #[arg(long)]
+        with_body: bool,
+
+        /// Skip merge commits
+        #[arg(long)]
+        no_merges: bool,
+
+        /// Follow only the first parent of merges: the current branch's own commits
+        #[arg(long)]
+        first_parent: bool,
+
+        /// Remove `[AI:model]` tags from the original messages before prompting
+        #[arg(long)]
+        strip_ai_tag: bool,
+
+        /// Only commits whose author matches PATTERN (repeat for any of several)
+        #[arg(long = "author", value_name = "PATTERN")]
+        authors: Vec<String>,
+
+        /// Only print how many commits match, then exit (no LLM calls)
+        #[arg(long)]
+        count_only: bool,
+
+        /// Give up on a commit's request after SECS (default: the client timeout)
+        #[arg(long, value_name = "SECS")]
+        timeout_per_commit: Option<u64>,
+
+        /// Abort the run after K consecutive failed commits
+        #[arg(long, value_name = "K")]
+        max_failures: Option<usize>,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
+    },
+
+    /// Regenerate the last N commit messages and amend them (rebase; needs --force)
+    ///
+    /// Refuses on a dirty tree, merge commits, or commits already on a remote.
+    Rewrite {
+        /// Number of commits to rewrite, counting back from HEAD
+        #[arg(short = 'n', long, default_value = "1")]
+        count: usize,
+
+        /// Required: rewriting changes the commit hashes
+        #[arg(long)]
+        force: bool,
+
+        /// Do not add AI model/provider tag to the messages
+        #[arg(long = "no-tag")]
+        no_tag: bool,
+
+        /// GPG-sign the amended commits (`git commit -S`), optionally with a key id: --gpg-sign=KEYID
+        #[arg(
+            short = 'S',
+            long,
+            value_name = "KEYID",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = ""
+        )]
+        gpg_sign: Option<String>,
+
+        /// Do not sign, even if `sign_commits` is set in the config
+        #[arg(long, conflicts_with = "gpg_sign")]
+        no_gpg_sign: bool,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
+    },
+
+    /// Regenerate one commit's message (default HEAD) and amend it
+    ///
+    /// Commits older than HEAD are reworded with a rebase and need --force.
+    Reword {
+        /// Commit to reword
+        #[arg(value_name = "COMMIT")]
+        commit: Option<String>,
+
+        /// Required for commits before HEAD: the commits after it get new hashes
+        #[arg(long)]
+        force: bool,
+
+        /// Do not add AI model/provider tag to the message
+        #[arg(long = "no-tag")]
+        no_tag: bool,
+
+        /// GPG-sign the amended commit (`git commit -S`), optionally with a key id: --gpg-sign=KEYID
+        #[arg(
+            short = 'S',
+            long,
+            value_name = "KEYID",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = ""
+        )]
+        gpg_sign: Option<String>,
+
+        /// Do not sign, even if `sign_commits` is set in the config
+        #[arg(long, conflicts_with = "gpg_sign")]
+        no_gpg_sign: bool,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Generate a pull request description from branch changes
@@ -172,19 +405,47 @@ pub enum Commands {
         #[arg(long)]
         staged: bool,
 
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Maximum number of commit subjects to include in the prompt (alias: --commits)
+        #[arg(long, visible_alias = "commits", value_name = "N", default_value_t = DEFAULT_PR_MAX_COMMITS)]
+        max_commits_diff: usize,
+
+        /// PR template to fill in (default: the repo's .github/pull_request_template.md, if any)
+        #[arg(long, value_name = "FILE")]
+        template: Option<String>,
+
+        /// When the diff exceeds max_diff_chars, summarize file groups separately and combine them
+        #[arg(long)]
+        map_reduce: bool,
+
+        /// Leave the diff stats out of the prompt (skips the `git diff --stat` call)
+        #[arg(long)]
+        no_stats: bool,
+
+        /// Append a "## Commits" section listing the branch's commits (short hash and subject)
+        #[arg(long, conflicts_with = "staged")]
+        compare_commits: bool,
+
+        /// Add a file (design note, ticket) to the prompt as additional context
+        #[arg(long, value_name = "FILE")]
+        context_file: Option<String>,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Generate release notes (changelog) from a commit range
     ///
     /// Useful for GitHub Releases. Outputs markdown-ready text.
     Changelog {
-        /// Starting ref (tag, commit, branch)
+        /// Starting ref (tag, commit, branch), or a range `A..B`
         #[arg(value_name = "REF")]
         from: Option<String>,
 
+        /// More ranges (`A..B`) combined into the same changelog; commits are deduplicated
+        #[arg(value_name = "RANGE")]
+        ranges: Vec<String>,
+
         /// Ending ref (default: HEAD)
         #[arg(long)]
         to: Option<String>,
@@ -201,9 +462,34 @@ pub enum Commands {
         #[arg(short = 'n', long)]
         limit: Option<usize>,
 
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Include merge commits (skipped by default)
+        #[arg(long)]
+        include_merges: bool,
+
+        /// Remove `[AI:model]` tags from commit subjects before prompting
+        #[arg(long)]
+        strip_ai_tag: bool,
+
+        /// Only commits whose author matches PATTERN (repeat for any of several)
+        #[arg(long = "author", value_name = "PATTERN")]
+        authors: Vec<String>,
+
+        /// Group release notes by change type or by contributor
+        #[arg(long, default_value = "type", value_parser = ["type", "author"])]
+        group_by: String,
+
+        /// Output: the model's markdown, JSON sections ({"features": [...], ...}),
+        /// or a Keep a Changelog release entry
+        #[arg(long, default_value = "markdown", value_parser = ["markdown", "json", "keepachangelog"])]
+        format: String,
+
+        /// Only print how many commits match, then exit (no LLM calls)
+        #[arg(long)]
+        count_only: bool,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Explain changes in plain English for non-technical stakeholders
@@ -230,9 +516,44 @@ pub enum Commands {
         #[arg(long)]
         staged: bool,
 
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Who the explanation is for: stakeholder (default), qa, security, support,
+        /// developer, or any free-form description
+        #[arg(long)]
+        audience: Option<String>,
+
+        /// Also generate a commit message for the same staged diff (requires --staged)
+        #[arg(long, requires = "staged")]
+        commit_ready: bool,
+
+        /// Send only the diff, without the diff stats section (skips a git call)
+        #[arg(long, alias = "no-stats")]
+        diff_only: bool,
+
+        /// Only print the risk level (Low/Medium/High) with a one-line reason;
+        /// exits 0 for Low, 3 for Medium, 4 for High
+        #[arg(long, conflicts_with_all = ["commit_ready", "audience", "format"])]
+        risk_only: bool,
+
+        /// Explain the difference between two files (`git diff --no-index`; works outside a repo)
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["OLD", "NEW"],
+            conflicts_with_all = ["from", "to", "since", "until", "staged"]
+        )]
+        files: Option<Vec<String>>,
+
+        /// Output format: markdown as the model writes it, plain text, or basic HTML
+        #[arg(long, default_value = "markdown", value_parser = ["text", "markdown", "html"])]
+        format: String,
+
+        /// Add a file (design note, ticket) to the prompt as additional context
+        #[arg(long, value_name = "FILE")]
+        context_file: Option<String>,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Suggest a semantic version bump (major/minor/patch) from changes
@@ -251,9 +572,13 @@ pub enum Commands {
         #[arg(long)]
         current: Option<String>,
 
-        /// Diff algorithm: 1=full, 2=files, 3=hunks, 4=semantic (default)
-        #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(1..=4))]
-        alg: u8,
+        /// Output the recommendation as JSON (bump, next_version, breaking, reasoning)
+        #[arg(long)]
+        json: bool,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
     },
 
     /// Manage git hooks for automatic commit message generation
@@ -262,14 +587,85 @@ pub enum Commands {
         command: HookCommands,
     },
 
-    /// Create or update `~/.gitar.toml` with provider/model defaults
-    Init,
+    /// Create or update the config file (`~/.gitar.toml` or `$GITAR_CONFIG`) with provider/model defaults
+    ///
+    /// Without flags, walks through provider, API key and model interactively.
+    Init {
+        /// Run the interactive setup even when flags are given
+        #[arg(long)]
+        interactive: bool,
+    },
 
     /// Show the resolved configuration and where each value comes from
-    Config,
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
+    },
 
     /// List available models (when the provider exposes a models endpoint)
-    Models,
+    ///
+    /// The list is cached per base URL next to the config file for a day.
+    Models {
+        /// Fetch the list even if the cached one is still fresh
+        #[arg(long)]
+        refresh: bool,
+
+        /// Print the cached names only, one per line, without a request (for scripts)
+        #[arg(long, conflicts_with = "refresh")]
+        cached: bool,
+    },
+
+    /// Check git, the config file, the API key and the base URL, with hints to fix problems
+    Doctor,
+
+    /// Print a shell completion script to stdout
+    ///
+    /// e.g. `gitar completions bash > ~/.local/share/bash-completion/completions/gitar`.
+    /// `--model` completes the names cached by `gitar models`; regenerate after a refresh.
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Check commit subjects against the Type(scope) format (no API call)
+    ///
+    /// Exits non-zero if any commit violates the format. Merge commits are skipped.
+    Lint {
+        /// Starting ref (tag, commit, branch). If omitted, checks the last 50 commits.
+        #[arg(value_name = "REF")]
+        from: Option<String>,
+
+        /// Ending ref (default: HEAD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Maximum number of commits to check
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+
+        /// Maximum subject length
+        #[arg(long, default_value_t = DEFAULT_MAX_SUBJECT_LEN)]
+        max_length: usize,
+    },
+
+    /// Debug: Print the exact system and user prompts a command would send (no API call)
+    PromptPreview {
+        /// Command to preview
+        #[arg(value_parser = clap::builder::PossibleValuesParser::new(PREVIEW_COMMANDS))]
+        command: String,
+
+        /// Ref passed to the command (pr/version base, history/changelog/explain start)
+        #[arg(value_name = "REF")]
+        reference: Option<String>,
+
+        /// Use staged changes (pr, explain)
+        #[arg(long)]
+        staged: bool,
+
+        /// Diff algorithm: 0=auto, 1=full, 2=files, 3=hunks, 4=semantic (default: [diff] config or 4)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+        alg: Option<u8>,
+    },
 
     /// Debug: Preview what would be sent to the LLM
     Diff {
@@ -284,8 +680,8 @@ pub enum Commands {
         #[arg(long, default_value = "15000")]
         max_chars: usize,
 
-        /// Diff algorithm: 1=naive, 2=standard, 3=think, 4=ir
-        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        /// Diff algorithm: 0=auto, 1=naive, 2=standard, 3=think, 4=ir
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
         alg: Option<u8>,
 
         /// Include git diff --stat header
@@ -299,6 +695,57 @@ pub enum Commands {
         /// Compare all algorithms side-by-side
         #[arg(long)]
         compare: bool,
+
+        /// Time all algorithms on the diff and print a size/token table
+        #[arg(long, conflicts_with = "compare")]
+        algo_bench: bool,
+    },
+}
+
+impl Commands {
+    /// Subcommand name, as used for `[diff]` and `[models]` config entries
+    pub fn name(&self) -> &'static str {
+        match self {
+            Commands::Commit { .. } => "commit",
+            Commands::Staged { .. } => "staged",
+            Commands::Unstaged { .. } => "unstaged",
+            Commands::History { .. } => "history",
+            Commands::Rewrite { .. } => "rewrite",
+            Commands::Reword { .. } => "reword",
+            Commands::Pr { .. } => "pr",
+            Commands::Changelog { .. } => "changelog",
+            Commands::Explain { .. } => "explain",
+            Commands::Version { .. } => "version",
+            Commands::Hook { .. } => "hook",
+            Commands::Init { .. } => "init",
+            Commands::Config { .. } => "config",
+            Commands::Models { .. } => "models",
+            Commands::Doctor => "doctor",
+            Commands::Completions { .. } => "completions",
+            Commands::Lint { .. } => "lint",
+            Commands::PromptPreview { .. } => "prompt-preview",
+            Commands::Diff { .. } => "diff",
+        }
+    }
+
+    /// Output other tools parse (`changelog --format json`, `explain --risk-only`) or embed
+    /// (`explain --format html`): only the result goes to stdout, as with `--quiet`.
+    pub fn machine_output(&self) -> bool {
+        match self {
+            Commands::Changelog { format, .. } => format == "json",
+            Commands::Explain { format, risk_only, .. } => format == "html" || *risk_only,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Subcommand, Clone)]
+pub enum ConfigCommands {
+    /// Check the resolved config: API key, model availability and (with --ping) the base URL
+    Validate {
+        /// Also check that the base URL is reachable
+        #[arg(long)]
+        ping: bool,
     },
 }
 
@@ -342,11 +789,130 @@ mod tests {
         assert!(matches!(cli.command, Commands::Commit { .. }));
     }
 
+    #[test]
+    fn cli_parses_commit_gpg_sign() {
+        let sign_of = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Commands::Commit { gpg_sign, .. } => gpg_sign,
+            _ => panic!("Expected Commit command"),
+        };
+        assert_eq!(sign_of(&["gitar", "commit"]), None);
+        assert_eq!(sign_of(&["gitar", "commit", "-S"]), Some(String::new()));
+        assert_eq!(sign_of(&["gitar", "commit", "--gpg-sign"]), Some(String::new()));
+        assert_eq!(sign_of(&["gitar", "commit", "--gpg-sign=ABCD1234"]), Some("ABCD1234".into()));
+        assert_eq!(sign_of(&["gitar", "commit", "-S", "-a"]), Some(String::new()));
+        assert!(Cli::try_parse_from(["gitar", "commit", "-S", "--no-gpg-sign"]).is_err());
+    }
+
+    #[test]
+    fn cli_commit_staged_only_conflicts_with_all() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--staged-only"]).unwrap();
+        assert!(matches!(cli.command, Commands::Commit { staged_only: true, .. }));
+        assert!(Cli::try_parse_from(["gitar", "commit", "--staged-only", "-a"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_global_quiet() {
+        let cli = Cli::try_parse_from(["gitar", "staged", "-q"]).unwrap();
+        assert!(cli.quiet);
+        let cli = Cli::try_parse_from(["gitar", "--quiet", "pr"]).unwrap();
+        assert!(cli.quiet);
+        assert!(!Cli::try_parse_from(["gitar", "staged"]).unwrap().quiet);
+    }
+
+    #[test]
+    fn machine_output_formats() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap().command;
+        assert!(parse(&["gitar", "changelog", "--format", "json"]).machine_output());
+        assert!(!parse(&["gitar", "changelog", "--format", "keepachangelog"]).machine_output());
+        assert!(!parse(&["gitar", "changelog"]).machine_output());
+        assert!(parse(&["gitar", "explain", "--format", "html"]).machine_output());
+        assert!(!parse(&["gitar", "explain", "--format", "text"]).machine_output());
+        assert!(parse(&["gitar", "explain", "--risk-only"]).machine_output());
+    }
+
+    #[test]
+    fn cli_parses_commit_style_from_history() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--style-from-history", "10"]).unwrap();
+        if let Commands::Commit { style_from_history, .. } = cli.command {
+            assert_eq!(style_from_history, Some(10));
+        } else {
+            panic!("Expected Commit command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_commit_signoff() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "-s", "--no-tag"]).unwrap();
+        if let Commands::Commit { signoff, no_tag, .. } = cli.command {
+            assert!(signoff);
+            assert!(no_tag);
+        } else {
+            panic!("Expected Commit command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_commit_interactive_split() {
+        for flag in ["--interactive-split", "--until-clean"] {
+            let cli = Cli::try_parse_from(["gitar", "commit", flag]).unwrap();
+            if let Commands::Commit { interactive_split, .. } = cli.command {
+                assert!(interactive_split);
+            } else {
+                panic!("Expected Commit command");
+            }
+        }
+        assert!(Cli::try_parse_from(["gitar", "commit", "--interactive-split", "-a"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_commit_scope() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--scope", "api"]).unwrap();
+        if let Commands::Commit { scope, .. } = cli.command {
+            assert_eq!(scope.as_deref(), Some("api"));
+        } else {
+            panic!("Expected Commit command");
+        }
+        assert!(Cli::try_parse_from(["gitar", "commit", "--scope", "api", "--interactive-split"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_commit_no_body() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--no-body"]).unwrap();
+        if let Commands::Commit { no_body, .. } = cli.command {
+            assert!(no_body);
+        } else {
+            panic!("Expected Commit command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_commit_ticket_from_branch() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--ticket-from-branch"]).unwrap();
+        if let Commands::Commit { ticket_from_branch, .. } = cli.command {
+            assert_eq!(ticket_from_branch.as_deref(), Some("prefix"));
+        } else {
+            panic!("Expected Commit command");
+        }
+        let cli = Cli::try_parse_from(["gitar", "commit", "--ticket-from-branch=suffix"]).unwrap();
+        assert!(matches!(cli.command, Commands::Commit { ticket_from_branch: Some(ref w), .. } if w == "suffix"));
+        assert!(Cli::try_parse_from(["gitar", "commit", "--ticket-from-branch=middle"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_commit_edit() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--edit"]).unwrap();
+        if let Commands::Commit { edit, .. } = cli.command {
+            assert!(edit);
+        } else {
+            panic!("Expected Commit command");
+        }
+    }
+
     #[test]
     fn cli_parses_commit_with_alg() {
         let cli = Cli::try_parse_from(["gitar", "commit", "--alg", "3"]).unwrap();
         if let Commands::Commit { alg, .. } = cli.command {
-            assert_eq!(alg, 3);
+            assert_eq!(alg, Some(3));
         } else {
             panic!("Expected Commit command");
         }
@@ -356,7 +922,8 @@ mod tests {
     fn cli_parses_commit_default_alg() {
         let cli = Cli::try_parse_from(["gitar", "commit"]).unwrap();
         if let Commands::Commit { alg, .. } = cli.command {
-            assert_eq!(alg, 4);
+            // Left unset so the [diff] config can supply a per-command default
+            assert_eq!(alg, None);
         } else {
             panic!("Expected Commit command");
         }
@@ -365,8 +932,8 @@ mod tests {
     #[test]
     fn cli_parses_staged_with_alg() {
         let cli = Cli::try_parse_from(["gitar", "staged", "--alg", "4"]).unwrap();
-        if let Commands::Staged { alg } = cli.command {
-            assert_eq!(alg, 4);
+        if let Commands::Staged { alg, .. } = cli.command {
+            assert_eq!(alg, Some(4));
         } else {
             panic!("Expected Staged command");
         }
@@ -377,10 +944,228 @@ mod tests {
         let cli = Cli::try_parse_from(["gitar", "pr", "main", "--alg", "3"]).unwrap();
         if let Commands::Pr { base, alg, .. } = cli.command {
             assert_eq!(base, Some("main".into()));
-            assert_eq!(alg, 3);
+            assert_eq!(alg, Some(3));
+        } else {
+            panic!("Expected Pr command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_pr_max_commits_diff() {
+        let cli = Cli::try_parse_from(["gitar", "pr"]).unwrap();
+        if let Commands::Pr { max_commits_diff, .. } = cli.command {
+            assert_eq!(max_commits_diff, 20);
         } else {
             panic!("Expected Pr command");
         }
+        let cli = Cli::try_parse_from(["gitar", "pr", "--max-commits-diff", "50"]).unwrap();
+        if let Commands::Pr { max_commits_diff, .. } = cli.command {
+            assert_eq!(max_commits_diff, 50);
+        } else {
+            panic!("Expected Pr command");
+        }
+        let cli = Cli::try_parse_from(["gitar", "pr", "main", "--commits", "5"]).unwrap();
+        assert!(matches!(cli.command, Commands::Pr { max_commits_diff: 5, .. }));
+    }
+
+    #[test]
+    fn cli_parses_no_stats() {
+        let cli = Cli::try_parse_from(["gitar", "pr", "--no-stats"]).unwrap();
+        assert!(matches!(cli.command, Commands::Pr { no_stats: true, .. }));
+
+        let cli = Cli::try_parse_from(["gitar", "explain", "--no-stats"]).unwrap();
+        assert!(matches!(cli.command, Commands::Explain { diff_only: true, .. }));
+    }
+
+    #[test]
+    fn cli_parses_explain_audience() {
+        let cli = Cli::try_parse_from(["gitar", "explain", "--audience", "security"]).unwrap();
+        if let Commands::Explain { audience, .. } = cli.command {
+            assert_eq!(audience, Some("security".into()));
+        } else {
+            panic!("Expected Explain command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_explain_files() {
+        let cli = Cli::try_parse_from(["gitar", "explain", "--files", "old.txt", "new.txt"]).unwrap();
+        if let Commands::Explain { files, from, .. } = cli.command {
+            assert_eq!(files, Some(vec!["old.txt".to_string(), "new.txt".to_string()]));
+            assert_eq!(from, None);
+        } else {
+            panic!("Expected Explain command");
+        }
+    }
+
+    #[test]
+    fn cli_rejects_explain_files_with_wrong_arity_or_ref() {
+        assert!(Cli::try_parse_from(["gitar", "explain", "--files", "old.txt"]).is_err());
+        assert!(Cli::try_parse_from(["gitar", "explain", "--files", "a", "b", "c"]).is_err());
+        assert!(Cli::try_parse_from(["gitar", "explain", "v1.0", "--files", "a", "b"]).is_err());
+        assert!(Cli::try_parse_from(["gitar", "explain", "--staged", "--files", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_history_with_body() {
+        let cli = Cli::try_parse_from(["gitar", "history", "--with-body"]).unwrap();
+        if let Commands::History { with_body, .. } = cli.command {
+            assert!(with_body);
+        } else {
+            panic!("Expected History command");
+        }
+    }
+
+    #[test]
+    fn cli_merges_defaults_per_command() {
+        let cli = Cli::try_parse_from(["gitar", "history", "--no-merges"]).unwrap();
+        if let Commands::History { no_merges, .. } = cli.command {
+            assert!(no_merges);
+        } else {
+            panic!("Expected History command");
+        }
+
+        let cli = Cli::try_parse_from(["gitar", "changelog"]).unwrap();
+        if let Commands::Changelog { include_merges, .. } = cli.command {
+            assert!(!include_merges);
+        } else {
+            panic!("Expected Changelog command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_changelog_group_by() {
+        let cli = Cli::try_parse_from(["gitar", "changelog", "--group-by", "author"]).unwrap();
+        if let Commands::Changelog { group_by, .. } = cli.command {
+            assert_eq!(group_by, "author");
+        } else {
+            panic!("Expected Changelog command");
+        }
+        assert!(Cli::try_parse_from(["gitar", "changelog", "--group-by", "date"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_changelog_multiple_ranges() {
+        let cli = Cli::try_parse_from(["gitar", "changelog", "v1.0.0..v1.1.0", "v1.2.0..v1.3.0"]).unwrap();
+        if let Commands::Changelog { from, ranges, .. } = cli.command {
+            assert_eq!(from.as_deref(), Some("v1.0.0..v1.1.0"));
+            assert_eq!(ranges, ["v1.2.0..v1.3.0"]);
+        } else {
+            panic!("Expected Changelog command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_repeated_author() {
+        let cli = Cli::try_parse_from(["gitar", "history", "--author", "Ana", "--author", "ben@x.io"]).unwrap();
+        if let Commands::History { authors, .. } = cli.command {
+            assert_eq!(authors, ["Ana", "ben@x.io"]);
+        } else {
+            panic!("Expected History command");
+        }
+        let cli = Cli::try_parse_from(["gitar", "changelog", "v1.0.0"]).unwrap();
+        assert!(matches!(cli.command, Commands::Changelog { ref authors, .. } if authors.is_empty()));
+    }
+
+    #[test]
+    fn cli_parses_global_reasoning_effort() {
+        let cli = Cli::try_parse_from(["gitar", "staged", "--reasoning-effort", "high"]).unwrap();
+        assert_eq!(cli.reasoning_effort.as_deref(), Some("high"));
+        assert!(Cli::try_parse_from(["gitar", "--reasoning-effort", "max", "staged"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_global_seed() {
+        let cli = Cli::try_parse_from(["gitar", "staged"]).unwrap();
+        assert_eq!(cli.seed, None);
+        let cli = Cli::try_parse_from(["gitar", "--seed", "42", "staged"]).unwrap();
+        assert_eq!(cli.seed, Some(42));
+    }
+
+    #[test]
+    fn cli_parses_version_json() {
+        let cli = Cli::try_parse_from(["gitar", "version", "--json"]).unwrap();
+        if let Commands::Version { json, .. } = cli.command {
+            assert!(json);
+        } else {
+            panic!("Expected Version command");
+        }
+    }
+
+    #[test]
+    fn cli_parses_lint() {
+        let cli = Cli::try_parse_from(["gitar", "lint", "v1.0.0", "--max-length", "60"]).unwrap();
+        if let Commands::Lint { from, max_length, .. } = cli.command {
+            assert_eq!(from.as_deref(), Some("v1.0.0"));
+            assert_eq!(max_length, 60);
+        } else {
+            panic!("Expected Lint command");
+        }
+    }
+
+    #[test]
+    fn command_names_match_subcommands() {
+        let cli = Cli::try_parse_from(["gitar", "history", "--limit", "5"]).unwrap();
+        assert_eq!(cli.command.name(), "history");
+        let cli = Cli::try_parse_from(["gitar", "prompt-preview", "pr"]).unwrap();
+        assert_eq!(cli.command.name(), "prompt-preview");
+    }
+
+    #[test]
+    fn cli_parses_global_max_files() {
+        assert_eq!(Cli::try_parse_from(["gitar", "pr"]).unwrap().max_files, None);
+        let cli = Cli::try_parse_from(["gitar", "commit", "--max-files", "3"]).unwrap();
+        assert_eq!(cli.max_files, Some(3));
+    }
+
+    #[test]
+    fn cli_parses_global_changed_only() {
+        assert!(!Cli::try_parse_from(["gitar", "pr"]).unwrap().changed_only);
+        assert!(Cli::try_parse_from(["gitar", "pr", "--alg", "4", "--changed-only"]).unwrap().changed_only);
+    }
+
+    #[test]
+    fn cli_parses_global_context() {
+        let cli = Cli::try_parse_from(["gitar", "staged"]).unwrap();
+        assert_eq!(cli.context, 3);
+        let cli = Cli::try_parse_from(["gitar", "pr", "--context", "8"]).unwrap();
+        assert_eq!(cli.context, 8);
+    }
+
+    #[test]
+    fn cli_parses_prompt_preview() {
+        let cli = Cli::try_parse_from(["gitar", "prompt-preview", "pr", "main", "--staged"]).unwrap();
+        if let Commands::PromptPreview { command, reference, staged, .. } = cli.command {
+            assert_eq!(command, "pr");
+            assert_eq!(reference.as_deref(), Some("main"));
+            assert!(staged);
+        } else {
+            panic!("Expected PromptPreview command");
+        }
+
+        assert!(Cli::try_parse_from(["gitar", "prompt-preview", "models"]).is_err());
+    }
+
+    #[test]
+    fn cli_explain_commit_ready_requires_staged() {
+        let cli = Cli::try_parse_from(["gitar", "explain", "--staged", "--commit-ready"]).unwrap();
+        if let Commands::Explain { commit_ready, .. } = cli.command {
+            assert!(commit_ready);
+        } else {
+            panic!("Expected Explain command");
+        }
+        assert!(Cli::try_parse_from(["gitar", "explain", "--commit-ready"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_diff_algo_bench() {
+        let cli = Cli::try_parse_from(["gitar", "diff", "--algo-bench", "--staged"]).unwrap();
+        if let Commands::Diff { algo_bench, staged, .. } = cli.command {
+            assert!(algo_bench && staged);
+        } else {
+            panic!("Expected Diff command");
+        }
+        assert!(Cli::try_parse_from(["gitar", "diff", "--algo-bench", "--compare"]).is_err());
     }
 
     #[test]
@@ -410,9 +1195,13 @@ mod tests {
     }
 
     #[test]
-    fn cli_rejects_alg_zero() {
-        let result = Cli::try_parse_from(["gitar", "commit", "--alg", "0"]);
-        assert!(result.is_err());
+    fn cli_accepts_alg_zero_as_auto() {
+        let cli = Cli::try_parse_from(["gitar", "commit", "--alg", "0"]).unwrap();
+        if let Commands::Commit { alg, .. } = cli.command {
+            assert_eq!(alg, Some(0));
+        } else {
+            panic!("Expected Commit command");
+        }
     }
 
     #[test]
@@ -426,6 +1215,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_set_upstream_requires_push() {
+        assert!(Cli::try_parse_from(["gitar", "commit", "--set-upstream"]).is_err());
+        let cli = Cli::try_parse_from(["gitar", "commit", "--push", "--set-upstream"]).unwrap();
+        assert!(matches!(cli.command, Commands::Commit { set_upstream: true, .. }));
+    }
+
     #[test]
     fn cli_parses_global_stream_flag() {
         let cli = Cli::try_parse_from(["gitar", "--stream", "staged"]).unwrap();
@@ -433,6 +1229,72 @@ mod tests {
         assert!(matches!(cli.command, Commands::Staged { .. }));
     }
 
+    #[test]
+    fn cli_parses_staged_paths() {
+        let cli = Cli::try_parse_from(["gitar", "staged", "--path", "src/api", "--path", "docs"]).unwrap();
+        if let Commands::Staged { paths, .. } = cli.command {
+            assert_eq!(paths, vec!["src/api", "docs"]);
+        } else {
+            panic!("Expected Staged command");
+        }
+    }
+
+    #[test]
+    fn cli_global_stream_applies_to_pr_changelog_explain() {
+        for cmd in ["pr", "changelog", "explain", "version", "history"] {
+            let before = Cli::try_parse_from(["gitar", "--stream", cmd]).unwrap();
+            assert!(before.stream, "--stream before {}", cmd);
+            let after = Cli::try_parse_from(["gitar", cmd, "--stream"]).unwrap();
+            assert!(after.stream, "--stream after {}", cmd);
+            let off = Cli::try_parse_from(["gitar", cmd]).unwrap();
+            assert!(!off.stream, "{} without --stream", cmd);
+        }
+    }
+
+    #[test]
+    fn cli_parses_rewrite() {
+        let cli = Cli::try_parse_from(["gitar", "rewrite", "-n", "3", "--force"]).unwrap();
+        if let Commands::Rewrite { count, force, no_tag, .. } = cli.command {
+            assert_eq!(count, 3);
+            assert!(force && !no_tag);
+        } else {
+            panic!("Expected Rewrite command");
+        }
+        let cli = Cli::try_parse_from(["gitar", "rewrite"]).unwrap();
+        assert!(matches!(cli.command, Commands::Rewrite { count: 1, force: false, .. }));
+        let cli = Cli::try_parse_from(["gitar", "rewrite", "-n", "2", "--gpg-sign=ABCD"]).unwrap();
+        assert!(matches!(cli.command, Commands::Rewrite { gpg_sign: Some(ref k), .. } if k == "ABCD"));
+    }
+
+    #[test]
+    fn cli_parses_reword() {
+        let cli = Cli::try_parse_from(["gitar", "reword"]).unwrap();
+        assert!(matches!(cli.command, Commands::Reword { commit: None, force: false, .. }));
+        let cli = Cli::try_parse_from(["gitar", "reword", "abc123", "--force"]).unwrap();
+        if let Commands::Reword { commit, force, .. } = cli.command {
+            assert_eq!(commit.as_deref(), Some("abc123"));
+            assert!(force);
+        } else {
+            panic!("Expected Reword command");
+        }
+        let cli = Cli::try_parse_from(["gitar", "reword", "-S"]).unwrap();
+        assert!(matches!(cli.command, Commands::Reword { gpg_sign: Some(ref k), .. } if k.is_empty()));
+        assert!(Cli::try_parse_from(["gitar", "reword", "-S", "--no-gpg-sign"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_explain_format() {
+        let cli = Cli::try_parse_from(["gitar", "explain", "--format", "html"]).unwrap();
+        if let Commands::Explain { format, .. } = cli.command {
+            assert_eq!(format, "html");
+        } else {
+            panic!("Expected Explain command");
+        }
+        let cli = Cli::try_parse_from(["gitar", "explain"]).unwrap();
+        assert!(matches!(cli.command, Commands::Explain { ref format, .. } if format == "markdown"));
+        assert!(Cli::try_parse_from(["gitar", "explain", "--format", "pdf"]).is_err());
+    }
+
     #[test]
     fn cli_parses_staged_command() {
         let cli = Cli::try_parse_from(["gitar", "staged"]).unwrap();
@@ -486,21 +1348,63 @@ mod tests {
             "init",
         ])
         .unwrap();
-        assert!(matches!(cli.command, Commands::Init));
+        assert!(matches!(cli.command, Commands::Init { interactive: false }));
         assert_eq!(cli.model, Some("claude-3".into()));
         assert_eq!(cli.base_branch, Some("develop".into()));
     }
 
+    #[test]
+    fn cli_parses_init_interactive() {
+        let cli = Cli::try_parse_from(["gitar", "--provider", "claude", "init", "--interactive"]).unwrap();
+        assert!(matches!(cli.command, Commands::Init { interactive: true }));
+    }
+
     #[test]
     fn cli_parses_config_command() {
         let cli = Cli::try_parse_from(["gitar", "config"]).unwrap();
-        assert!(matches!(cli.command, Commands::Config));
+        assert!(matches!(cli.command, Commands::Config { command: None }));
+    }
+
+    #[test]
+    fn cli_parses_config_validate() {
+        let cli = Cli::try_parse_from(["gitar", "config", "validate"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: Some(ConfigCommands::Validate { ping: false })
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["gitar", "config", "validate", "--ping"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: Some(ConfigCommands::Validate { ping: true })
+            }
+        ));
+    }
+
+    #[test]
+    fn cli_parses_doctor_command() {
+        let cli = Cli::try_parse_from(["gitar", "doctor"]).unwrap();
+        assert!(matches!(cli.command, Commands::Doctor));
+        assert_eq!(cli.command.name(), "doctor");
+    }
+
+    #[test]
+    fn cli_parses_completions_command() {
+        let cli = Cli::try_parse_from(["gitar", "completions", "zsh"]).unwrap();
+        assert!(matches!(cli.command, Commands::Completions { shell: clap_complete::Shell::Zsh }));
+        assert!(Cli::try_parse_from(["gitar", "completions", "tcsh"]).is_err());
     }
 
     #[test]
     fn cli_parses_models_command() {
         let cli = Cli::try_parse_from(["gitar", "models"]).unwrap();
-        assert!(matches!(cli.command, Commands::Models));
+        assert!(matches!(cli.command, Commands::Models { refresh: false, cached: false }));
+        let cli = Cli::try_parse_from(["gitar", "models", "--refresh"]).unwrap();
+        assert!(matches!(cli.command, Commands::Models { refresh: true, .. }));
+        assert!(Cli::try_parse_from(["gitar", "models", "--refresh", "--cached"]).is_err());
     }
 
     #[test]
@@ -571,7 +1475,7 @@ mod tests {
             let cli =
                 Cli::try_parse_from(["gitar", "commit", "--alg", &alg_val.to_string()]).unwrap();
             if let Commands::Commit { alg, .. } = cli.command {
-                assert_eq!(alg, alg_val);
+                assert_eq!(alg, Some(alg_val));
             }
         }
     }
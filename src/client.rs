@@ -1,113 +1,209 @@
 // src/client.rs
-use anyhow::Result;
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Proxy};
+use std::time::Duration;
 
 use crate::config::ResolvedConfig;
-use crate::providers::{claude, gemini, openai};
+use crate::prompt::add_instruction;
+use crate::providers::{self, ChatRequest, Endpoint, Provider, ReasoningEffort, Usage};
+use crate::types::ChatMessage;
+
+/// Settings for the one HTTP client each run shares across requests
+#[derive(Debug, PartialEq)]
+struct HttpOptions {
+    timeout: Duration,
+    pool_idle_timeout: Duration,
+    pool_max_idle_per_host: usize,
+    tcp_keepalive: Duration,
+    proxy: Option<String>,
+    /// From the config's `[headers]`, sent with every request
+    headers: Vec<(String, String)>,
+}
+
+impl HttpOptions {
+    fn from_config(config: &ResolvedConfig, all_proxy: Option<String>) -> Self {
+        Self {
+            timeout: Duration::from_secs(120),
+            pool_idle_timeout: Duration::from_secs(config.pool_idle_timeout_secs),
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+            tcp_keepalive: Duration::from_secs(60),
+            proxy: all_proxy.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()),
+            headers: config.headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        }
+    }
+
+    fn build(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .timeout(self.timeout)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .tcp_keepalive(self.tcp_keepalive)
+            .default_headers(header_map(&self.headers)?);
+
+        if let Some(proxy_url) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy_url)?);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Extra headers as a `HeaderMap`. Errors name the header but never print its value.
+fn header_map(headers: &[(String, String)]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let key = HeaderName::from_bytes(name.trim().as_bytes())
+            .with_context(|| format!("Invalid header name in [headers]: '{}'", name))?;
+        let mut val = HeaderValue::from_str(value.trim())
+            .with_context(|| format!("Invalid value for header '{}' in [headers]", name))?;
+        val.set_sensitive(crate::config::is_secret_header(name));
+        map.insert(key, val);
+    }
+    Ok(map)
+}
 
+/// One per run: commands take `&LlmClient`, so loops like `history` reuse its
+/// connection pool instead of reconnecting per request.
 pub struct LlmClient {
     http: Client,
-    provider: String,
     base_url: String,
-    api_key: Option<String>,
+    provider: Box<dyn Provider>,
     model: String,
-    max_tokens: u32,
-    temperature: f32,
+    seed: Option<u64>,
+    reasoning_effort: Option<ReasoningEffort>,
+    offline: bool,
+    instruct: Option<String>,
+    usage: Usage,
 }
 
 impl LlmClient {
     pub fn new(config: &ResolvedConfig) -> Result<Self> {
-        let mut builder = Client::builder()
-            .danger_accept_invalid_certs(true)
-            .timeout(std::time::Duration::from_secs(120));
+        let http = HttpOptions::from_config(config, std::env::var("ALL_PROXY").ok()).build()?;
+        let base_url = config.base_url.trim_end_matches('/').to_string();
 
-        if let Ok(proxy_url) = std::env::var("ALL_PROXY") {
-            let proxy_url = proxy_url.trim();
-            if !proxy_url.is_empty() {
-                builder = builder.proxy(Proxy::all(proxy_url)?);
-            }
-        }
-
-        let http = builder.build()?;
-
-        Ok(Self {
-            http,
-            provider: config.provider.clone(),
-            base_url: config.base_url.trim_end_matches('/').to_string(),
+        let endpoint = Endpoint {
+            http: http.clone(),
+            base_url: base_url.clone(),
             api_key: config.api_key.clone(),
             model: config.model.clone(),
             max_tokens: config.max_tokens,
             temperature: config.temperature,
+        };
+
+        Ok(Self {
+            http,
+            base_url,
+            provider: providers::for_endpoint(&config.provider, endpoint),
+            model: config.model.clone(),
+            seed: None,
+            reasoning_effort: None,
+            offline: false,
+            instruct: None,
+            usage: Usage::default(),
         })
     }
 
+    /// Seed for reproducible sampling; only OpenAI-compatible providers use it.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Reasoning effort; providers drop it for models that don't reason.
+    pub fn with_reasoning_effort(mut self, effort: Option<ReasoningEffort>) -> Self {
+        self.reasoning_effort = effort;
+        self
+    }
+
+    /// `--offline`: every network call fails right away instead of sending a request.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// `--instruct`: extra text appended to every system prompt this client sends.
+    pub fn with_instruct(mut self, instruct: Option<String>) -> Self {
+        self.instruct = instruct;
+        self
+    }
+
+    fn check_online(&self) -> Result<()> {
+        if self.offline {
+            anyhow::bail!("Offline mode (--offline / GITAR_OFFLINE): no requests to {}", self.base_url);
+        }
+        Ok(())
+    }
+
     pub fn model(&self) -> &str {
         &self.model
     }
 
-    fn is_claude_api(&self) -> bool {
-        self.provider == "claude" || self.base_url.contains("anthropic.com")
+    pub fn base_url(&self) -> &str {
+        &self.base_url
     }
 
-    fn is_gemini_api(&self) -> bool {
-        self.provider == "gemini" || self.base_url.contains("generativelanguage.googleapis.com")
+    /// `--show-usage` line: tokens summed over this client's requests
+    pub fn usage_line(&self) -> String {
+        self.usage.line()
+    }
+
+    /// Which API the requests speak (`openai`, `claude`, `gemini` or `bedrock`)
+    pub fn api(&self) -> &'static str {
+        self.provider.name()
     }
 
     pub async fn chat(&self, system: &str, user: &str, stream: bool) -> Result<String> {
-        if self.is_claude_api() {
-            return claude::chat(
-                &self.http,
-                &self.base_url,
-                self.api_key.as_deref(),
-                &self.model,
-                self.max_tokens,
-                self.temperature,
-                system,
-                user,
-                stream,
-            )
-            .await;
-        }
+        self.send(system, &[ChatMessage::user(user)], stream, false).await
+    }
 
-        if self.is_gemini_api() {
-            return gemini::chat(
-                &self.http,
-                &self.base_url,
-                self.api_key.as_deref(),
-                &self.model,
-                self.max_tokens,
-                self.temperature,
-                system,
-                user,
-                stream,
-            )
-            .await;
-        }
+    /// Multi-turn chat: alternating user/assistant `turns`, ending with a user turn.
+    pub async fn chat_turns(&self, system: &str, turns: &[ChatMessage], stream: bool) -> Result<String> {
+        self.send(system, turns, stream, false).await
+    }
 
-        openai::chat(
-            &self.http,
-            &self.base_url,
-            self.api_key.as_deref(),
-            &self.model,
-            self.max_tokens,
-            self.temperature,
-            system,
-            user,
-            stream,
-        )
-        .await
+    /// Ask for a JSON object. OpenAI-compatible providers get native JSON mode; others rely
+    /// on the prompt.
+    pub async fn chat_json(&self, system: &str, user: &str) -> Result<String> {
+        self.send(system, &[ChatMessage::user(user)], false, true).await
     }
 
-    pub async fn list_models(&self) -> Result<Vec<String>> {
-        if self.is_gemini_api() {
-            return gemini::list_models(&self.http, &self.base_url, self.api_key.as_deref()).await;
-        }
+    async fn send(
+        &self,
+        system: &str,
+        turns: &[ChatMessage],
+        stream: bool,
+        json_mode: bool,
+    ) -> Result<String> {
+        self.check_online()?;
+        let system = add_instruction(system, self.instruct.as_deref());
+        let req = ChatRequest {
+            system: &system,
+            turns,
+            stream,
+            json_mode,
+            seed: self.seed,
+            reasoning_effort: self.reasoning_effort,
+            usage: &self.usage,
+        };
+        self.provider.chat(req).await
+    }
 
-        if self.is_claude_api() {
-            return claude::list_models(&self.http, &self.base_url, self.api_key.as_deref()).await;
-        }
+    /// Check that the base URL answers at all (any HTTP status counts).
+    pub async fn ping(&self) -> Result<()> {
+        self.check_online()?;
+        self.http
+            .get(&self.base_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?;
+        Ok(())
+    }
 
-        openai::list_models(&self.http, &self.base_url, self.api_key.as_deref()).await
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        self.check_online()?;
+        self.provider.list_models().await
     }
 }
 
@@ -117,7 +213,10 @@ impl LlmClient {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::ResolvedConfig;
+    use crate::config::{ResolvedConfig, DEFAULT_POOL_IDLE_TIMEOUT_SECS, DEFAULT_POOL_MAX_IDLE_PER_HOST};
+    use futures_util::future::BoxFuture;
+    use futures_util::FutureExt;
+    use std::sync::{Arc, Mutex};
 
     // Stable, explicit URLs (avoid depending on config constants that might be
     // provider names rather than URLs).
@@ -160,109 +259,244 @@ mod tests {
             base_branch: "main".into(),
             stream: false,
             max_diff_chars: 10_000,
+            pool_idle_timeout_secs: DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            headers: Default::default(),
         }
     }
 
+    fn provider_name(provider: &str, base_url: &str) -> &'static str {
+        let client = LlmClient::new(&make_config(provider, base_url)).unwrap();
+        client.api()
+    }
+
     #[test]
-    fn is_claude_api_detects_provider() {
+    fn claude_selected_by_provider_or_url() {
         let _env = EnvGuard::remove("ALL_PROXY");
 
-        let config = make_config("claude", URL_OPENAI);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(client.is_claude_api());
-        assert!(!client.is_gemini_api());
+        assert_eq!(provider_name("claude", URL_OPENAI), "claude");
+        assert_eq!(provider_name("openai", URL_CLAUDE), "claude");
     }
 
     #[test]
-    fn is_claude_api_detects_url() {
+    fn gemini_selected_by_provider_or_url() {
         let _env = EnvGuard::remove("ALL_PROXY");
 
-        let config = make_config("openai", URL_CLAUDE);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(client.is_claude_api());
-        assert!(!client.is_gemini_api());
+        assert_eq!(provider_name("gemini", URL_OPENAI), "gemini");
+        assert_eq!(provider_name("openai", URL_GEMINI), "gemini");
     }
 
     #[test]
-    fn is_gemini_api_detects_provider() {
+    fn bedrock_selected_by_provider_or_url() {
         let _env = EnvGuard::remove("ALL_PROXY");
 
-        let config = make_config("gemini", URL_OPENAI);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(client.is_gemini_api());
-        assert!(!client.is_claude_api());
+        assert_eq!(provider_name("bedrock", crate::config::PROVIDER_BEDROCK), "bedrock");
+        assert_eq!(provider_name("openai", "https://bedrock-runtime.eu-west-1.amazonaws.com"), "bedrock");
     }
 
     #[test]
-    fn is_gemini_api_detects_url() {
+    fn openai_compatible_providers_use_openai() {
         let _env = EnvGuard::remove("ALL_PROXY");
 
-        let config = make_config("openai", URL_GEMINI);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(client.is_gemini_api());
-        assert!(!client.is_claude_api());
+        let cases = [
+            ("openai", URL_OPENAI),
+            ("groq", URL_GROQ),
+            ("openrouter", crate::config::PROVIDER_OPENROUTER),
+            ("ollama", URL_OLLAMA),
+        ];
+        for (provider, url) in cases {
+            assert_eq!(provider_name(provider, url), "openai", "{} ({})", provider, url);
+        }
     }
 
-    #[test]
-    fn openai_provider_uses_openai_path() {
-        let _env = EnvGuard::remove("ALL_PROXY");
+    type Call = (String, Vec<ChatMessage>, bool, bool, Option<u64>);
 
-        let config = make_config("openai", URL_OPENAI);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(!client.is_claude_api());
-        assert!(!client.is_gemini_api());
+    /// Records what `LlmClient` hands to the provider.
+    #[derive(Default)]
+    struct MockProvider {
+        calls: Arc<Mutex<Vec<Call>>>,
     }
 
-    #[test]
-    fn groq_uses_openai_path() {
-        let _env = EnvGuard::remove("ALL_PROXY");
+    impl Provider for MockProvider {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
 
-        let config = make_config("groq", URL_GROQ);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(!client.is_claude_api());
-        assert!(!client.is_gemini_api());
+        fn chat<'a>(&'a self, req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>> {
+            self.calls.lock().unwrap().push((
+                req.system.to_string(),
+                req.turns.to_vec(),
+                req.stream,
+                req.json_mode,
+                req.seed,
+            ));
+            req.usage.record(10, 2);
+            async { Ok("reply".to_string()) }.boxed()
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+            async { Ok(vec!["m1".to_string()]) }.boxed()
+        }
     }
 
-    #[test]
-    fn ollama_uses_openai_path() {
+    /// A client wired to a mock provider, plus the mock's call log.
+    fn mock_client() -> (LlmClient, Arc<Mutex<Vec<Call>>>) {
         let _env = EnvGuard::remove("ALL_PROXY");
+        let mock = MockProvider::default();
+        let calls = mock.calls.clone();
+        let mut client = LlmClient::new(&make_config("openai", URL_OPENAI)).unwrap();
+        client.provider = Box::new(mock);
+        (client, calls)
+    }
 
-        let config = make_config("ollama", URL_OLLAMA);
-        let client = LlmClient::new(&config).unwrap();
-        assert!(!client.is_claude_api());
-        assert!(!client.is_gemini_api());
+    #[tokio::test]
+    async fn instruct_appended_to_system_prompt() {
+        let (client, calls) = mock_client();
+        let client = client.with_instruct(Some("focus on the security implications".into()));
+        client.chat("You write commit messages.\n", "diff", false).await.unwrap();
+        client.chat_json("Reply in JSON.", "diff").await.unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(
+            calls[0].0,
+            "You write commit messages.\n\nAdditional instructions for this request:\n\
+             focus on the security implications"
+        );
+        assert!(calls[1].0.starts_with("Reply in JSON."));
+        assert!(calls[1].0.ends_with("focus on the security implications"));
     }
 
-    #[test]
-    fn provider_detection_mutually_exclusive() {
-        let _env = EnvGuard::remove("ALL_PROXY");
+    #[tokio::test]
+    async fn offline_fails_without_calling_provider() {
+        let (client, calls) = mock_client();
+        let client = client.with_offline(true);
+
+        let err = client.chat("sys", "diff", false).await.unwrap_err();
+        assert!(err.to_string().contains("Offline mode"));
+        assert!(client.chat_json("sys", "diff").await.is_err());
+        assert!(client.list_models().await.is_err());
+        assert!(client.ping().await.is_err());
+        assert!(calls.lock().unwrap().is_empty());
+    }
 
-        let cases = [
-            ("openai", URL_OPENAI, false, false),
-            ("claude", URL_CLAUDE, true, false),
-            ("gemini", URL_GEMINI, false, true),
-            ("groq", URL_GROQ, false, false),
-            ("ollama", URL_OLLAMA, false, false),
+    #[tokio::test]
+    async fn chat_dispatches_to_provider() {
+        let (client, calls) = mock_client();
+        let client = client.with_seed(Some(3));
+        assert_eq!(client.api(), "mock");
+
+        assert_eq!(client.chat("sys", "hi", true).await.unwrap(), "reply");
+        assert_eq!(client.chat_json("sys", "json").await.unwrap(), "reply");
+        assert_eq!(client.list_models().await.unwrap(), vec!["m1"]);
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], ("sys".into(), vec![ChatMessage::user("hi")], true, false, Some(3)));
+        assert_eq!(calls[1].1, vec![ChatMessage::user("json")]);
+        assert!(calls[1].3);
+    }
+
+    #[tokio::test]
+    async fn chat_turns_passes_conversation() {
+        let (client, calls) = mock_client();
+        let turns = [
+            ChatMessage::user("diff"),
+            ChatMessage::assistant("Fix bug"),
+            ChatMessage::user("shorter"),
         ];
 
-        for (provider, url, expected_claude, expected_gemini) in cases {
-            let config = make_config(provider, url);
-            let client = LlmClient::new(&config).unwrap();
-            assert_eq!(
-                client.is_claude_api(),
-                expected_claude,
-                "Claude detection failed for {} ({})",
-                provider,
-                url
-            );
-            assert_eq!(
-                client.is_gemini_api(),
-                expected_gemini,
-                "Gemini detection failed for {} ({})",
-                provider,
-                url
-            );
+        client.chat_turns("sys", &turns, false).await.unwrap();
+        assert_eq!(calls.lock().unwrap()[0].1, turns.to_vec());
+    }
+
+    #[tokio::test]
+    async fn sequential_chats_reuse_one_client() {
+        // What cmd_history does: one client, many requests
+        let (client, calls) = mock_client();
+        for i in 0..5 {
+            client.chat("sys", &format!("commit {}", i), false).await.unwrap();
         }
+        assert_eq!(calls.lock().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn usage_summed_per_client() {
+        let (client, _) = mock_client();
+        client.chat("sys", "a", false).await.unwrap();
+        client.chat_json("sys", "b").await.unwrap();
+        assert_eq!(client.usage_line(), "tokens: prompt=20 completion=4");
+
+        let (other, _) = mock_client();
+        assert!(other.usage_line().starts_with("tokens: not reported"));
+    }
+
+    #[test]
+    fn http_options_from_config() {
+        let mut config = make_config("openai", URL_OPENAI);
+        let opts = HttpOptions::from_config(&config, None);
+        assert_eq!(opts.pool_idle_timeout, Duration::from_secs(DEFAULT_POOL_IDLE_TIMEOUT_SECS));
+        assert_eq!(opts.pool_max_idle_per_host, DEFAULT_POOL_MAX_IDLE_PER_HOST);
+        assert_eq!(opts.timeout, Duration::from_secs(120));
+        assert_eq!(opts.proxy, None);
+        assert!(opts.build().is_ok());
+
+        config.pool_idle_timeout_secs = 5;
+        config.pool_max_idle_per_host = 0;
+        let opts = HttpOptions::from_config(&config, Some(" socks5://127.0.0.1:1080 ".into()));
+        assert_eq!(opts.pool_idle_timeout, Duration::from_secs(5));
+        assert_eq!(opts.pool_max_idle_per_host, 0);
+        assert_eq!(opts.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+        assert!(opts.build().is_ok());
+
+        assert_eq!(HttpOptions::from_config(&config, Some("  ".into())).proxy, None);
+    }
+
+    #[test]
+    fn header_map_from_config_headers() {
+        let headers = vec![
+            ("X-Org-Id".to_string(), " 42 ".to_string()),
+            ("Authorization".to_string(), "Bearer gw-secret".to_string()),
+        ];
+        let map = header_map(&headers).unwrap();
+        assert_eq!(map.get("x-org-id").unwrap(), "42");
+        assert!(map.get("authorization").unwrap().is_sensitive());
+        assert!(!map.get("x-org-id").unwrap().is_sensitive());
+
+        let err = header_map(&[("Bad Name".into(), "1".into())]).unwrap_err();
+        assert!(err.to_string().contains("'Bad Name'"));
+        let err = header_map(&[("X-Token".into(), "line\nbreak".into())]).unwrap_err();
+        assert!(err.to_string().contains("'X-Token'"));
+        assert!(!err.to_string().contains("break"));
+    }
+
+    #[tokio::test]
+    async fn config_headers_sent_with_requests() {
+        use std::io::{Read, Write};
+        let _env = EnvGuard::remove("ALL_PROXY");
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+
+        let mut config = make_config("openai", &format!("http://{}", addr));
+        config.headers.insert("X-Org-Id".into(), "42".into());
+        LlmClient::new(&config).unwrap().ping().await.unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.contains("x-org-id: 42\r\n"), "{}", request);
     }
 
     #[test]
@@ -279,12 +513,29 @@ mod tests {
             base_branch: "main".into(),
             stream: false,
             max_diff_chars: 10_000,
+            pool_idle_timeout_secs: DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            headers: Default::default(),
         };
         let client = LlmClient::new(&config).unwrap();
         assert!(!client.base_url.ends_with('/'));
         assert_eq!(client.base_url, "https://api.openai.com/v1");
     }
 
+    #[test]
+    fn with_seed_sets_seed() {
+        let _env = EnvGuard::remove("ALL_PROXY");
+
+        let config = make_config("openai", URL_OPENAI);
+        let client = LlmClient::new(&config).unwrap();
+        assert_eq!(client.seed, None);
+        let client = client.with_seed(Some(7));
+        assert_eq!(client.seed, Some(7));
+        assert_eq!(client.reasoning_effort, None);
+        let client = client.with_reasoning_effort(Some(ReasoningEffort::High));
+        assert_eq!(client.reasoning_effort, Some(ReasoningEffort::High));
+    }
+
     #[test]
     fn model_getter_works() {
         let _env = EnvGuard::remove("ALL_PROXY");
@@ -0,0 +1,106 @@
+// src/color.rs
+//! Minimal ANSI coloring for interactive output.
+//!
+//! Disabled by `--no-color`, a non-empty `NO_COLOR`, or when stdout/stderr is not a TTY.
+
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Decide whether to emit color (see https://no-color.org).
+pub fn should_color(no_color_flag: bool, no_color_env: Option<&str>, is_tty: bool) -> bool {
+    if no_color_flag {
+        return false;
+    }
+    if no_color_env.is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    is_tty
+}
+
+/// Resolve the color decision once at startup.
+pub fn init(no_color_flag: bool) {
+    let env = std::env::var("NO_COLOR").ok();
+    let tty = std::io::stdout().is_terminal() && std::io::stderr().is_terminal();
+    ENABLED.store(should_color(no_color_flag, env.as_deref(), tty), Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn paint(code: &str, s: &str) -> String {
+    if enabled() {
+        format!("\x1b[{}m{}\x1b[0m", code, s)
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn dim(s: &str) -> String {
+    paint("2", s)
+}
+
+pub fn green(s: &str) -> String {
+    paint("32", s)
+}
+
+pub fn yellow(s: &str) -> String {
+    paint("33", s)
+}
+
+pub fn cyan(s: &str) -> String {
+    paint("36", s)
+}
+
+/// Color a `a | b | c` menu: default choice green, alternatives yellow, cancel dimmed.
+pub fn menu(text: &str) -> String {
+    let items: Vec<&str> = text.split(" | ").collect();
+    let last = items.len() - 1;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| match i {
+            0 => green(item),
+            i if i == last => dim(item),
+            _ => yellow(item),
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// The `=====` rule framing interactive prompts.
+pub fn rule() -> String {
+    dim(&"=".repeat(50))
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_enabled_on_tty_by_default() {
+        assert!(should_color(false, None, true));
+    }
+
+    #[test]
+    fn color_disabled_when_not_tty() {
+        assert!(!should_color(false, None, false));
+    }
+
+    #[test]
+    fn color_disabled_by_no_color_env() {
+        assert!(!should_color(false, Some("1"), true));
+        // An empty NO_COLOR does not count, per the spec
+        assert!(should_color(false, Some(""), true));
+    }
+
+    #[test]
+    fn color_disabled_by_flag() {
+        assert!(!should_color(true, None, true));
+    }
+}
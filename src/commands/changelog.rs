@@ -1,23 +1,248 @@
 // src/commands/changelog.rs
 use anyhow::Result;
+use std::collections::HashSet;
 
 use crate::client::LlmClient;
-use crate::git::{get_commit_logs, get_diff};
-use crate::prompt::{CHANGELOG_SYSTEM_PROMPT, CHANGELOG_USER_PROMPT};
+use crate::git::{count_commits, get_commit_logs, get_diff, get_ref_date, CommitInfo, LogQuery};
+use crate::prompt::{changelog_prompt, Prompt};
 
-use super::apply_smart_diff;
+use super::commit::strip_ai_tag;
+use super::{apply_smart_diff, no_request_line, omitted_commits_note, CommandOptions};
 
+/// `changelog --format`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangelogFormat {
+    Markdown,
+    Json,
+    KeepAChangelog,
+}
+
+impl ChangelogFormat {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "json" => Self::Json,
+            "keepachangelog" => Self::KeepAChangelog,
+            _ => Self::Markdown,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_changelog(
     client: &LlmClient,
     from: Option<String>,
+    ranges: Vec<String>,
     to: Option<String>,
     since: Option<String>,
     until: Option<String>,
     limit: Option<usize>,
+    include_merges: bool,
+    strip_tags: bool,
+    authors: Vec<String>,
+    by_author: bool,
+    count_only: bool,
+    format: ChangelogFormat,
     stream: bool,
     alg: u8,
-    max_diff_chars: usize,
+    opts: &CommandOptions,
 ) -> Result<()> {
+    // Reshaped output needs the whole response
+    let stream = stream && format == ChangelogFormat::Markdown;
+    let version = to.clone();
+
+    let prompt = match prepare_changelog(
+        from,
+        &ranges,
+        to,
+        since,
+        until,
+        limit,
+        include_merges,
+        strip_tags,
+        &authors,
+        by_author,
+        count_only,
+        alg,
+        opts,
+    )? {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    let r = client.chat(&prompt.system, &prompt.user, stream).await?;
+    match format {
+        ChangelogFormat::Markdown if stream => println!(),
+        ChangelogFormat::Markdown => println!("{}", r),
+        ChangelogFormat::Json => println!("{}", changelog_json(&parse_changelog_sections(&r))),
+        ChangelogFormat::KeepAChangelog => {
+            let date = version.as_deref().and_then(get_ref_date);
+            let sections = parse_changelog_sections(&r);
+            println!("{}", keep_a_changelog(&sections, version.as_deref(), date.as_deref()));
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// OUTPUT FORMATS
+// =============================================================================
+/// `## Heading` sections of a model response with their `-`/`*` items, in order.
+/// Text outside sections (the `# Release Notes` title) is dropped; indented
+/// continuation lines join the previous item.
+pub(crate) fn parse_changelog_sections(text: &str) -> Vec<(String, Vec<String>)> {
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix("## ") {
+            sections.push((heading.trim().to_string(), Vec::new()));
+            continue;
+        }
+        let Some((_, items)) = sections.last_mut() else {
+            continue;
+        };
+        if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            items.push(item.trim().to_string());
+        } else if !trimmed.is_empty() && line.starts_with([' ', '\t']) {
+            if let Some(last) = items.last_mut() {
+                last.push(' ');
+                last.push_str(trimmed);
+            }
+        }
+    }
+
+    sections.retain(|(_, items)| !items.is_empty());
+    sections
+}
+
+/// `Breaking Changes` -> `breaking_changes`
+fn section_key(heading: &str) -> String {
+    let key: String = heading
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    key.split('_').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("_")
+}
+
+/// `{"features": [...], "fixes": [...], ...}`; repeated headings are merged.
+pub(crate) fn changelog_json(sections: &[(String, Vec<String>)]) -> String {
+    let mut map = serde_json::Map::new();
+    for (heading, items) in sections {
+        let entry = map
+            .entry(section_key(heading))
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+        if let serde_json::Value::Array(list) = entry {
+            list.extend(items.iter().cloned().map(serde_json::Value::String));
+        }
+    }
+    serde_json::to_string_pretty(&serde_json::Value::Object(map)).unwrap_or_default()
+}
+
+/// Keep a Changelog categories, in the order the convention lists them
+const KEEP_A_CHANGELOG_ORDER: &[&str] = &["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security"];
+
+/// Category for one of our section headings; everything unknown counts as Changed.
+fn keep_a_changelog_category(heading: &str) -> &'static str {
+    match section_key(heading).as_str() {
+        "features" | "feature" | "added" | "new" => "Added",
+        "fixes" | "fix" | "fixed" | "bug_fixes" => "Fixed",
+        "deprecated" | "deprecations" => "Deprecated",
+        "removed" | "removals" => "Removed",
+        "security" => "Security",
+        _ => "Changed",
+    }
+}
+
+/// One release in Keep a Changelog form: `## [1.2.0] - 2024-05-01` (or `[Unreleased]`)
+/// with `### Added` / `### Changed` / ... subsections. Breaking changes are kept
+/// under Changed with a `**BREAKING:**` prefix.
+pub(crate) fn keep_a_changelog(
+    sections: &[(String, Vec<String>)],
+    version: Option<&str>,
+    date: Option<&str>,
+) -> String {
+    let title = match (version, date) {
+        (Some(v), Some(d)) => format!("## [{}] - {}", strip_version_prefix(v), d),
+        (Some(v), None) => format!("## [{}]", strip_version_prefix(v)),
+        (None, _) => "## [Unreleased]".to_string(),
+    };
+
+    let mut out = title;
+    for category in KEEP_A_CHANGELOG_ORDER {
+        let items: Vec<String> = sections
+            .iter()
+            .filter(|(h, _)| keep_a_changelog_category(h) == *category)
+            .flat_map(|(h, items)| {
+                let breaking = section_key(h).starts_with("breaking");
+                items.iter().map(move |i| {
+                    if breaking {
+                        format!("**BREAKING:** {}", i)
+                    } else {
+                        i.clone()
+                    }
+                })
+            })
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n\n### {}\n", category));
+        out.push_str(&items.iter().map(|i| format!("- {}", i)).collect::<Vec<_>>().join("\n"));
+    }
+    out
+}
+
+/// `v1.2.0` -> `1.2.0`; other refs are kept as they are.
+fn strip_version_prefix(version: &str) -> &str {
+    match version.strip_prefix('v') {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+        _ => version,
+    }
+}
+
+/// Ranges to log: `from` (`from..to` unless it is already a range), then the extra ones.
+pub(crate) fn changelog_ranges(from: Option<&str>, more: &[String], end: &str) -> Vec<String> {
+    from.into_iter()
+        .chain(more.iter().map(String::as_str))
+        .map(|r| if r.contains("..") { r.to_string() } else { format!("{}..{}", r, end) })
+        .collect()
+}
+
+/// Commits of every range, in range order, each commit once (ranges may overlap).
+pub(crate) fn gather_commits(
+    ranges: &[String],
+    mut fetch: impl FnMut(&str) -> Result<Vec<CommitInfo>>,
+) -> Result<Vec<CommitInfo>> {
+    let mut seen = HashSet::new();
+    let mut commits = Vec::new();
+    for range in ranges {
+        for c in fetch(range)? {
+            if seen.insert(c.hash.clone()) {
+                commits.push(c);
+            }
+        }
+    }
+    Ok(commits)
+}
+
+/// Build the `changelog` prompt (None if there are no commits, or with `count_only`).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_changelog(
+    from: Option<String>,
+    more_ranges: &[String],
+    to: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+    include_merges: bool,
+    strip_tags: bool,
+    authors: &[String],
+    by_author: bool,
+    count_only: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<Prompt>> {
     let limit = match (&from, limit) {
         (Some(_), None) => None,
         (None, None) => Some(50),
@@ -25,11 +250,11 @@ pub async fn cmd_changelog(
     };
 
     let end = to.as_deref().unwrap_or("HEAD");
-    let range = from.as_ref().map(|r| format!("{}..{}", r, end));
+    let ranges = changelog_ranges(from.as_deref(), more_ranges, end);
+    let range = ranges.first().cloned();
 
     let display = match (&from, &to, &since, &until) {
-        (Some(r), Some(t), _, _) => format!("{}..{}", r, t),
-        (Some(r), None, _, _) => format!("{}..HEAD", r),
+        (Some(_), _, _, _) => ranges.join(", "),
         (None, Some(t), _, _) => format!("..{}", t),
         (None, None, Some(s), Some(u)) => format!("--since {} --until {}", s, u),
         (None, None, Some(s), None) => format!("--since {}", s),
@@ -37,30 +262,66 @@ pub async fn cmd_changelog(
         (None, None, None, None) => "recent (last 50 commits)".into(),
     };
 
-    println!("Changelog for {}...\n", display);
-    let commits = get_commit_logs(limit, since.as_deref(), until.as_deref(), range.as_deref())?;
+    if !opts.quiet {
+        println!("Changelog for {}...\n", display);
+    }
+    let no_merges = !include_merges;
+    let fetch = |range: Option<&str>| {
+        get_commit_logs(&LogQuery {
+            limit,
+            since: since.as_deref(),
+            until: until.as_deref(),
+            range,
+            no_merges,
+            authors,
+            ..Default::default()
+        })
+    };
+    let mut commits = if ranges.len() > 1 {
+        let mut commits = gather_commits(&ranges, |r| fetch(Some(r)))?;
+        commits.truncate(limit.unwrap_or(usize::MAX));
+        commits
+    } else {
+        fetch(range.as_deref())?
+    };
+    if strip_tags {
+        strip_ai_tag(&mut commits);
+    }
 
-    if commits.is_empty() {
-        println!("No commits found.");
-        return Ok(());
+    if let Some(line) = no_request_line(&commits, count_only) {
+        println!("{}", line);
+        return Ok(None);
     }
 
-    println!("Found {} commits.\n", commits.len());
+    if !opts.quiet {
+        println!("Found {} commits.\n", commits.len());
+    }
 
     // Build commit list with messages
-    let ct = commits
-        .iter()
-        .map(|c| format!("- [{}] {}", &c.hash[..8.min(c.hash.len())], c.message))
-        .collect::<Vec<_>>()
-        .join("\n");
+    let mut ct = format_commit_list(&commits, by_author);
+
+    if ranges.len() <= 1 && limit == Some(commits.len()) {
+        let total = count_commits(since.as_deref(), until.as_deref(), range.as_deref(), no_merges, authors)
+            .unwrap_or(commits.len());
+        if let Some(note) = omitted_commits_note(commits.len(), total) {
+            if !opts.quiet {
+                println!("{}\n", note);
+            }
+            ct.push_str(&format!("\n{}", note));
+        }
+    }
 
-    // Get combined diff for the range
-    let diff = if let Some(ref base) = from {
-        let raw_diff = get_diff(Some(&format!("{}..{}", base, end)), false, usize::MAX)?;
+    // Get combined diff for the range(s)
+    let diff = if from.is_some() {
+        let raw_diff = ranges
+            .iter()
+            .map(|r| get_diff(Some(r), false, usize::MAX, &opts.diff, &[]))
+            .collect::<Result<Vec<_>>>()?
+            .join("\n");
         if raw_diff.trim().is_empty() {
             String::new()
         } else {
-            apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?
+            apply_smart_diff(&raw_diff, None, false, alg, opts)?
         }
     } else if let Some(first_commit) = commits.last() {
         // Use oldest commit's parent as base
@@ -68,28 +329,199 @@ pub async fn cmd_changelog(
             Some(&format!("{}^..{}", first_commit.hash, end)),
             false,
             usize::MAX,
+            &opts.diff,
+            &[],
         )
         .unwrap_or_default();
         if raw_diff.trim().is_empty() {
             String::new()
         } else {
-            apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?
+            apply_smart_diff(&raw_diff, None, false, alg, opts)?
         }
     } else {
         String::new()
     };
 
-    let prompt = CHANGELOG_USER_PROMPT
-        .replace("{range}", &display)
-        .replace("{count}", &commits.len().to_string())
-        .replace("{commits}", &ct)
-        .replace("{diff}", &diff);
+    Ok(Some(changelog_prompt(&display, commits.len(), &ct, &diff, by_author)))
+}
 
-    let r = client.chat(CHANGELOG_SYSTEM_PROMPT, &prompt, stream).await?;
-    if stream {
-        println!();
-    } else {
-        println!("{}", r);
+fn format_commit_line(c: &CommitInfo) -> String {
+    format!("- [{}] {}", &c.hash[..8.min(c.hash.len())], c.message)
+}
+
+/// Commit list for the prompt; with `by_author`, one `### Author` block per contributor
+/// in order of first appearance.
+pub(crate) fn format_commit_list(commits: &[CommitInfo], by_author: bool) -> String {
+    if !by_author {
+        return commits.iter().map(format_commit_line).collect::<Vec<_>>().join("\n");
     }
-    Ok(())
-}
\ No newline at end of file
+
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+    for c in commits {
+        let line = format_commit_line(c);
+        match groups.iter_mut().find(|(a, _)| *a == c.author) {
+            Some((_, lines)) => lines.push(line),
+            None => groups.push((&c.author, vec![line])),
+        }
+    }
+
+    groups
+        .iter()
+        .map(|(author, lines)| format!("### {}\n{}", author, lines.join("\n")))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, author: &str, message: &str) -> CommitInfo {
+        CommitInfo {
+            hash: hash.into(),
+            author: author.into(),
+            date: "2024-01-01".into(),
+            message: message.into(),
+            body: String::new(),
+        }
+    }
+
+    fn sample() -> Vec<CommitInfo> {
+        vec![
+            commit("aaaaaaaaaa", "Ana", "Add login"),
+            commit("bbbbbbbbbb", "Ben", "Fix crash"),
+            commit("cccccccccc", "Ana", "Add logout"),
+        ]
+    }
+
+    #[test]
+    fn changelog_ranges_from_refs_and_ranges() {
+        assert_eq!(changelog_ranges(Some("v1.0.0"), &[], "HEAD"), ["v1.0.0..HEAD"]);
+        assert_eq!(changelog_ranges(Some("v1.0.0"), &[], "v1.1.0"), ["v1.0.0..v1.1.0"]);
+        assert_eq!(
+            changelog_ranges(Some("v1.0.0..v1.1.0"), &["v1.2.0..v1.3.0".into()], "HEAD"),
+            ["v1.0.0..v1.1.0", "v1.2.0..v1.3.0"]
+        );
+        assert!(changelog_ranges(None, &[], "HEAD").is_empty());
+    }
+
+    #[test]
+    fn gather_commits_dedupes_across_ranges() {
+        let mut asked = Vec::new();
+        let commits = gather_commits(&["a..b".into(), "c..d".into()], |r| {
+            asked.push(r.to_string());
+            Ok(match r {
+                "a..b" => vec![commit("bbbbbbbbbb", "Ben", "Fix crash"), commit("aaaaaaaaaa", "Ana", "Add login")],
+                _ => vec![commit("cccccccccc", "Ana", "Add logout"), commit("bbbbbbbbbb", "Ben", "Fix crash")],
+            })
+        })
+        .unwrap();
+        assert_eq!(asked, ["a..b", "c..d"]);
+        let hashes: Vec<&str> = commits.iter().map(|c| c.hash.as_str()).collect();
+        assert_eq!(hashes, ["bbbbbbbbbb", "aaaaaaaaaa", "cccccccccc"]);
+    }
+
+    #[test]
+    fn gather_commits_stops_on_bad_range() {
+        let result = gather_commits(&["a..b".into(), "nope..x".into()], |r| match r {
+            "a..b" => Ok(sample()),
+            _ => Err(anyhow::anyhow!("bad revision '{}'", r)),
+        });
+        assert!(result.unwrap_err().to_string().contains("nope..x"));
+    }
+
+    #[test]
+    fn commit_list_flat_by_default() {
+        assert_eq!(
+            format_commit_list(&sample(), false),
+            "- [aaaaaaaa] Add login\n- [bbbbbbbb] Fix crash\n- [cccccccc] Add logout"
+        );
+    }
+
+    #[test]
+    fn commit_list_grouped_by_author() {
+        assert_eq!(
+            format_commit_list(&sample(), true),
+            "### Ana\n- [aaaaaaaa] Add login\n- [cccccccc] Add logout\n\n### Ben\n- [bbbbbbbb] Fix crash"
+        );
+    }
+
+    #[test]
+    fn commit_list_grouped_empty() {
+        assert_eq!(format_commit_list(&[], true), "");
+    }
+
+    const SAMPLE_RESPONSE: &str = r#"# Release Notes
+
+## Features
+- Add login page
+- Add OAuth providers,
+  including GitHub
+
+## Fixes
+* Fix crash on empty diff
+
+## Breaking Changes
+- Drop the --legacy flag
+
+## Infrastructure
+- Cache cargo in CI
+
+## Improvements
+"#;
+
+    #[test]
+    fn parse_sections_from_response() {
+        let sections = parse_changelog_sections(SAMPLE_RESPONSE);
+        let headings: Vec<&str> = sections.iter().map(|(h, _)| h.as_str()).collect();
+        // empty sections are dropped
+        assert_eq!(headings, ["Features", "Fixes", "Breaking Changes", "Infrastructure"]);
+        assert_eq!(
+            sections[0].1,
+            ["Add login page", "Add OAuth providers, including GitHub"]
+        );
+        assert_eq!(sections[1].1, ["Fix crash on empty diff"]);
+    }
+
+    #[test]
+    fn parse_sections_ignores_text_outside_sections() {
+        assert!(parse_changelog_sections("# Release Notes\n- stray item\n").is_empty());
+        assert!(parse_changelog_sections("").is_empty());
+    }
+
+    #[test]
+    fn json_format_uses_section_keys() {
+        let json = changelog_json(&parse_changelog_sections(SAMPLE_RESPONSE));
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(v["features"][1], "Add OAuth providers, including GitHub");
+        assert_eq!(v["fixes"][0], "Fix crash on empty diff");
+        assert_eq!(v["breaking_changes"][0], "Drop the --legacy flag");
+        assert_eq!(v["infrastructure"].as_array().unwrap().len(), 1);
+        assert!(v.get("improvements").is_none());
+    }
+
+    #[test]
+    fn keep_a_changelog_maps_categories() {
+        let sections = parse_changelog_sections(SAMPLE_RESPONSE);
+        let out = keep_a_changelog(&sections, Some("v1.4.0"), Some("2024-05-01"));
+        assert_eq!(
+            out,
+            "## [1.4.0] - 2024-05-01\n\n\
+             ### Added\n- Add login page\n- Add OAuth providers, including GitHub\n\n\
+             ### Changed\n- **BREAKING:** Drop the --legacy flag\n- Cache cargo in CI\n\n\
+             ### Fixed\n- Fix crash on empty diff"
+        );
+        assert!(keep_a_changelog(&sections, None, None).starts_with("## [Unreleased]\n"));
+        assert!(keep_a_changelog(&sections, Some("main"), None).starts_with("## [main]\n"));
+    }
+
+    #[test]
+    fn changelog_format_names() {
+        assert_eq!(ChangelogFormat::from_name("json"), ChangelogFormat::Json);
+        assert_eq!(ChangelogFormat::from_name("keepachangelog"), ChangelogFormat::KeepAChangelog);
+        assert_eq!(ChangelogFormat::from_name("markdown"), ChangelogFormat::Markdown);
+    }
+}
@@ -1,179 +1,1286 @@
 // src/commands/commit.rs
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use std::fs;
-use std::io::{self, Write};
+use std::future::Future;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
 
 use crate::client::LlmClient;
-use crate::git::{get_diff, run_git, run_git_status};
-use crate::prompt::{COMMIT_SYSTEM_PROMPT, COMMIT_USER_PROMPT};
+use crate::color;
+use crate::diff::{dominant_scope, split_diff_by_file};
+use crate::git::{
+    get_comment_char, get_commit_logs, get_current_branch, get_diff, get_git_path, get_numstat,
+    get_signoff_line, get_unmerged_paths, get_untracked_diff, merge_commit_message, run_git, run_git_status,
+    CommitInfo, LogQuery,
+};
+use crate::prompt::{commit_prompt, commit_style_block, CommitScope, Prompt, COMMIT_NO_BODY_PROMPT};
+use crate::types::ChatMessage;
 
-use super::apply_smart_diff;
+use super::{apply_smart_diff, CommandOptions};
+use super::split::{run_split_loop, GitSplitSession};
+use super::ticket::{add_ticket, ticket_from_branch, ticket_regex, ticket_width, TicketPosition};
 
+pub(crate) const REVIEW_MENU: &str =
+    "[Enter] Accept | [g] Regenerate | [f] Feedback | [e] Edit | [other] Cancel";
+
+/// What the user chose after seeing a generated message.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Review {
+    Accept,
+    Regenerate,
+    Feedback,
+    Edit,
+    Cancel,
+}
+
+/// Record a feedback round: the rejected message, then the user's request.
+/// `turns` are the conversation after the original user prompt.
+pub(crate) fn push_feedback(turns: &mut Vec<ChatMessage>, previous: &str, feedback: &str) {
+    turns.push(ChatMessage::assistant(previous));
+    turns.push(ChatMessage::user(feedback.trim()));
+}
+
+/// Full conversation for a follow-up call: original prompt plus feedback rounds.
+pub(crate) fn feedback_conversation(user: &str, turns: &[ChatMessage]) -> Vec<ChatMessage> {
+    let mut all = vec![ChatMessage::user(user)];
+    all.extend_from_slice(turns);
+    all
+}
+
+pub(crate) fn parse_review(input: &str) -> Review {
+    match input.trim().to_lowercase().as_str() {
+        "" => Review::Accept,
+        "g" => Review::Regenerate,
+        "f" => Review::Feedback,
+        "e" => Review::Edit,
+        _ => Review::Cancel,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_commit(
     client: &LlmClient,
     push: bool,
+    set_upstream: bool,
     all: bool,
     tag: bool,
     write_to: Option<String>,
     silent: bool,
+    signoff: bool,
+    sign: Option<String>,
+    staged_only: bool,
+    interactive_split: bool,
+    scope: Option<String>,
+    no_body: bool,
+    ticket: Option<(TicketPosition, &str)>,
+    edit: bool,
+    interactive: bool,
     stream: bool,
+    style_from_history: Option<usize>,
     alg: u8,
-    max_diff_chars: usize,
+    opts: &CommandOptions,
 ) -> Result<()> {
-    let staged = run_git(&["diff", "--cached"]).unwrap_or_default();
-    let unstaged = run_git(&["diff"]).unwrap_or_default();
+    let style = load_style_block(style_from_history)?;
+    let quiet = opts.quiet;
 
-    let mut raw_diff = String::new();
-    if !staged.trim().is_empty() {
-        raw_diff.push_str(&staged);
-    }
-    if !unstaged.trim().is_empty() {
-        if !raw_diff.is_empty() {
-            raw_diff.push('\n');
+    if interactive_split {
+        let mut session = GitSplitSession {
+            client,
+            tag,
+            signoff,
+            sign,
+            style,
+            alg,
+            opts,
+        };
+        let commits = run_split_loop(&mut session).await?;
+        if push && commits > 0 {
+            if !quiet {
+                println!("Pushing...");
+            }
+            let (o, e, ok) = push_branch(set_upstream, &get_current_branch(), run_git_status);
+            println!("{}{}", o, e);
+            return git_step("Push", ok);
         }
-        raw_diff.push_str(&unstaged);
+        return Ok(());
     }
 
-    if raw_diff.trim().is_empty() {
-        if !silent {
-            println!("Nothing to commit.");
+    let ticket = match ticket {
+        Some((position, pattern)) => {
+            let branch = get_current_branch();
+            let found = ticket_from_branch(&branch, &ticket_regex(pattern)?);
+            if found.is_none() && !silent && !quiet {
+                println!("No ticket matching {} in branch '{}'.", pattern, branch);
+            }
+            found.map(|t| (t, position))
         }
-        return Ok(());
-    }
+        None => None,
+    };
+    let ticket = ticket.as_ref().map(|(t, p)| (t.as_str(), *p));
+
+    let prepared = prepare_commit(silent, staged_only, scope.as_deref(), alg, opts)?;
+    let mut prompt = match prepared {
+        Some(p) => p,
+        None => {
+            if !silent {
+                println!("Nothing to commit.");
+            }
+            return Ok(());
+        }
+    };
 
-    let diff = apply_smart_diff(&raw_diff, max_diff_chars, silent, alg)?;
+    prompt.system.push_str(&style);
+    if no_body {
+        prompt.system.push_str(COMMIT_NO_BODY_PROMPT);
+    }
 
     // Hook mode: never stream (hooks expect file output only)
     if let Some(ref output_file) = write_to {
-        let prompt = COMMIT_USER_PROMPT.replace("{diff}", &diff);
-        let msg = client.chat(COMMIT_SYSTEM_PROMPT, &prompt, false).await?;
-        fs::write(output_file, format!("{}\n", msg.trim()))?;
+        let msg = client.chat(&prompt.system, &prompt.user, false).await?;
+        let mut msg = shape_message(&msg, scope.as_deref(), no_body, ticket, opts.subject_max_len);
+        if signoff {
+            if let Some(line) = get_signoff_line() {
+                msg = append_signoff(&msg, &line);
+            }
+        }
+        let existing = fs::read_to_string(output_file).unwrap_or_default();
+        fs::write(output_file, merge_commit_message(&msg, &existing, get_comment_char()))?;
         return Ok(());
     }
 
-    // Interactive mode
-    let commit_message = loop {
-        let prompt = COMMIT_USER_PROMPT.replace("{diff}", &diff);
+    // Interactive mode, unless nobody is there to answer the menu
+    let auto_accept = accept_without_review(interactive, io::stdin().is_terminal());
+    let review = async {
+        let mut feedback: Vec<ChatMessage> = Vec::new();
+        loop {
+            let do_stream = stream && !silent;
+            let conversation = feedback_conversation(&prompt.user, &feedback);
+            let raw = client.chat_turns(&prompt.system, &conversation, do_stream).await?;
+            let msg = shape_message(&raw, scope.as_deref(), no_body, ticket, opts.subject_max_len);
 
-        let do_stream = stream && !silent;
-        let msg = client.chat(COMMIT_SYSTEM_PROMPT, &prompt, do_stream).await?;
+            if silent {
+                return Ok(Some(msg));
+            }
 
-        if silent {
-            break msg;
-        }
+            if edit {
+                return edit_in_editor(&msg);
+            }
 
-        if do_stream {
-            println!();
-        } else {
-            println!("\n{}\n", msg);
-        }
+            if do_stream && msg == raw {
+                println!();
+            } else {
+                println!("\n{}\n", msg);
+            }
+
+            if auto_accept {
+                if !quiet {
+                    println!("stdin is not a terminal: accepting (use --interactive to review).");
+                }
+                return Ok(Some(msg));
+            }
 
-        println!("{}", "=".repeat(50));
-        println!("  [Enter] Accept | [g] Regenerate | [e] Edit | [other] Cancel");
-        println!("{}", "=".repeat(50));
-        print!("> ");
-        io::stdout().flush()?;
+            println!("{}", color::rule());
+            println!("  {}", color::menu(REVIEW_MENU));
+            println!("{}", color::rule());
+            print!("> ");
+            io::stdout().flush()?;
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
 
-        match input.trim().to_lowercase().as_str() {
-            "" => break msg,
-            "g" => {
-                println!("Regenerating...\n");
-                continue;
+            match parse_review(&input) {
+                Review::Accept => return Ok(Some(msg)),
+                Review::Regenerate => {
+                    println!("Regenerating...\n");
+                }
+                Review::Feedback => {
+                    print!("Feedback: ");
+                    io::stdout().flush()?;
+                    let mut fb = String::new();
+                    io::stdin().read_line(&mut fb)?;
+                    if !fb.trim().is_empty() {
+                        push_feedback(&mut feedback, &msg, &fb);
+                    }
+                    println!("Regenerating...\n");
+                }
+                Review::Edit => {
+                    print!("New message: ");
+                    io::stdout().flush()?;
+                    let mut ed = String::new();
+                    io::stdin().read_line(&mut ed)?;
+                    return Ok(Some(if ed.trim().is_empty() { msg } else { ed.trim().into() }));
+                }
+                Review::Cancel => return Ok(None),
             }
-            "e" => {
-                print!("New message: ");
-                io::stdout().flush()?;
-                let mut ed = String::new();
-                io::stdin().read_line(&mut ed)?;
-                break if ed.trim().is_empty() { msg } else { ed.trim().into() };
+        }
+    };
+
+    let committed = accept_then_commit(
+        review,
+        all,
+        || {
+            if !silent && !quiet {
+                println!("Staging all...");
+            }
+            run_git(&["add", "-A"]).map(|_| ())
+        },
+        |message| {
+            if !silent && !quiet {
+                println!("Committing...");
             }
-            _ => {
-                println!("Canceled.");
-                return Ok(());
+            let full_msg = tag_message(message, client.model(), tag, opts.subject_max_len);
+            let (out, err, ok) = git_commit(&full_msg, all, signoff, sign.as_deref(), run_git_status);
+            if !silent {
+                println!("{}{}", out, err);
             }
+            Ok(ok)
+        },
+    )
+    .await?;
+
+    match committed {
+        None => {
+            println!("Canceled.");
+            return Ok(());
+        }
+        Some(ok) => git_step("Commit", ok)?,
+    }
+
+    if push {
+        if !silent && !quiet {
+            println!("Pushing...");
         }
+        let (o, e, ok) = push_branch(set_upstream, &get_current_branch(), run_git_status);
+        if !silent {
+            println!("{}{}", o, e);
+        }
+        git_step("Push", ok)?;
+    }
+
+    Ok(())
+}
+
+/// Skip the review menu when stdin is not a terminal (CI, scripts), where reading an
+/// answer would block forever. `--interactive` keeps the menu anyway.
+pub(crate) fn accept_without_review(interactive: bool, stdin_is_tty: bool) -> bool {
+    !interactive && !stdin_is_tty
+}
+
+/// A failed git step fails the command, so scripts and CI can rely on the exit code.
+pub(crate) fn git_step(step: &str, ok: bool) -> Result<()> {
+    if !ok {
+        bail!("{} failed.", step);
+    }
+    Ok(())
+}
+
+/// Wait for an accepted message before touching the index, so a failed or canceled
+/// generation leaves staging as it was. Returns None if canceled, else whether
+/// the commit succeeded.
+pub(crate) async fn accept_then_commit<Fut>(
+    review: Fut,
+    all: bool,
+    stage_all: impl FnOnce() -> Result<()>,
+    commit: impl FnOnce(&str) -> Result<bool>,
+) -> Result<Option<bool>>
+where
+    Fut: Future<Output = Result<Option<String>>>,
+{
+    let message = match review.await? {
+        Some(m) => m,
+        None => return Ok(None),
     };
+    if all {
+        stage_all()?;
+    }
+    commit(&message).map(Some)
+}
+
+/// Editor command from $EDITOR, falling back to notepad on Windows and vi elsewhere.
+pub(crate) fn resolve_editor(editor_env: Option<String>, windows: bool) -> String {
+    match editor_env {
+        Some(e) if !e.trim().is_empty() => e.trim().to_string(),
+        _ if windows => "notepad".into(),
+        _ => "vi".into(),
+    }
+}
 
+/// Drop comment lines and surrounding blank lines from an edited message.
+pub(crate) fn clean_edited_message(content: &str, comment_char: char) -> String {
+    content
+        .lines()
+        .filter(|l| !l.starts_with(comment_char))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Open `msg` in the user's editor. None (cancel) if the editor fails or leaves it empty.
+fn edit_in_editor(msg: &str) -> Result<Option<String>> {
+    let comment_char = get_comment_char();
+    let path = get_git_path("GITAR_EDITMSG").context("Could not locate the git directory")?;
+    fs::write(
+        &path,
+        format!(
+            "{}\n\n{} Edit the commit message. Lines starting with '{}' are ignored;\n{} an empty message cancels the commit.\n",
+            msg, comment_char, comment_char, comment_char
+        ),
+    )?;
+
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let editor = resolve_editor(std::env::var("EDITOR").ok(), cfg!(windows));
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(&path).status();
+
+    let edited = fs::read_to_string(&path).unwrap_or_default();
+    let _ = fs::remove_file(&path);
+
+    match status {
+        Ok(s) if s.success() => {}
+        Ok(s) => {
+            eprintln!("Editor exited with {}", s);
+            return Ok(None);
+        }
+        Err(e) => bail!("Failed to start editor '{}': {}", program, e),
+    }
+
+    let cleaned = clean_edited_message(&edited, comment_char);
+    Ok(if cleaned.is_empty() { None } else { Some(cleaned) })
+}
+
+/// Arguments for `git commit`; `-s` lets git add the Signed-off-by trailer after the tag.
+/// `sign` is a GPG key id, or empty for the default key. With `message_file` the message
+/// is read from it (`-F`); otherwise it is bound to `--message=` so a leading `-` stays text.
+pub(crate) fn build_commit_args(
+    message: &str,
+    all: bool,
+    signoff: bool,
+    sign: Option<&str>,
+    message_file: Option<&Path>,
+) -> Vec<String> {
+    let mut args = vec!["commit".to_string()];
     if all {
-        if !silent {
-            println!("Staging all...");
+        args.push("-a".into());
+    }
+    if signoff {
+        args.push("-s".into());
+    }
+    if let Some(key) = sign {
+        args.push(format!("-S{}", key));
+    }
+    match message_file {
+        Some(path) => args.extend(["-F".to_string(), path.display().to_string()]),
+        None => args.push(format!("--message={}", message)),
+    }
+    args
+}
+
+/// Run `git commit`. Multi-line messages go through `.git/GITAR_COMMIT_MSG` and `-F`,
+/// removed afterwards.
+pub(crate) fn git_commit(
+    message: &str,
+    all: bool,
+    signoff: bool,
+    sign: Option<&str>,
+    run: impl FnOnce(&[&str]) -> (String, String, bool),
+) -> (String, String, bool) {
+    let message_file = match message.contains('\n') {
+        false => None,
+        true => match get_git_path("GITAR_COMMIT_MSG") {
+            Some(path) => Some(path),
+            None => return (String::new(), "Could not locate the git directory".to_string(), false),
+        },
+    };
+    if let Some(path) = &message_file {
+        if let Err(e) = fs::write(path, message) {
+            return (String::new(), format!("Could not write {}: {}", path.display(), e), false);
         }
-        run_git(&["add", "-A"])?;
     }
+    let args_vec = build_commit_args(message, all, signoff, sign, message_file.as_deref());
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    let result = run(&args);
+    if let Some(path) = &message_file {
+        fs::remove_file(path).ok();
+    }
+    result
+}
 
-    if !silent {
-        println!("Committing...");
+/// Arguments for `git push`; with `upstream`, push that branch to origin and track it.
+pub(crate) fn build_push_args(upstream: Option<&str>) -> Vec<String> {
+    let mut args = vec!["push".to_string()];
+    if let Some(branch) = upstream {
+        args.extend(["-u".to_string(), "origin".to_string(), branch.to_string()]);
     }
+    args
+}
 
-    let full_msg = if tag {
-        format!("{} [AI:{}]", commit_message, client.model())
-    } else {
-        commit_message
+/// Whether `git push` failed because the branch has no upstream yet.
+pub(crate) fn missing_upstream(stderr: &str) -> bool {
+    stderr.contains("has no upstream branch")
+}
+
+/// Push, setting the upstream when asked or when git says there is none.
+/// Returns (stdout, stderr, success) of the last push.
+pub(crate) fn push_branch(
+    set_upstream: bool,
+    branch: &str,
+    mut run: impl FnMut(&[&str]) -> (String, String, bool),
+) -> (String, String, bool) {
+    let mut run_push = |upstream: Option<&str>| {
+        let args = build_push_args(upstream);
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        run(&args)
+    };
+
+    let (o, e, ok) = run_push(set_upstream.then_some(branch));
+    if ok || set_upstream || !missing_upstream(&e) {
+        return (o, e, ok);
+    }
+    run_push(Some(branch))
+}
+
+/// Append a Signed-off-by trailer (hook mode, where git writes the message file).
+pub(crate) fn append_signoff(msg: &str, line: &str) -> String {
+    let msg = msg.trim_end();
+    if msg.lines().any(|l| l.trim() == line) {
+        return msg.to_string();
+    }
+    format!("{}\n\n{}", msg, line)
+}
+
+/// `commit --scope`, `--no-body`, `--ticket-from-branch` and `subject_max_len` applied to a
+/// generated message.
+fn shape_message(
+    msg: &str,
+    scope: Option<&str>,
+    no_body: bool,
+    ticket: Option<(&str, TicketPosition)>,
+    subject_max_len: usize,
+) -> String {
+    let msg = if no_body { subject_only(msg) } else { msg.to_string() };
+    let msg = match scope {
+        Some(s) => set_subject_scope(&msg, s),
+        None => msg,
+    };
+    match ticket {
+        Some((t, position)) => {
+            // Cut the subject to leave room for the ticket, so the ticket is never cut off
+            let room = match subject_max_len {
+                0 => 0,
+                max => max.saturating_sub(ticket_width(&msg, t, position)).max(1),
+            };
+            add_ticket(&truncate_subject(&msg, room), t, position)
+        }
+        None => truncate_subject(&msg, subject_max_len),
+    }
+}
+
+/// `--no-body`: the first non-empty line, whatever the model added after it.
+pub(crate) fn subject_only(msg: &str) -> String {
+    msg.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Put `scope` in the subject's `type(scope):` prefix (`Feat(x):`, `feat:`, `:bug: fix(x)!:`).
+/// Subjects without a type prefix are left alone.
+pub(crate) fn set_subject_scope(msg: &str, scope: &str) -> String {
+    let (subject, body) = match msg.split_once('\n') {
+        Some((s, b)) => (s, Some(b)),
+        None => (msg, None),
+    };
+    // Optional gitmoji shortcode in front of the type
+    let (emoji, rest) = match subject.strip_prefix(':').and_then(|r| r.split_once(": ")) {
+        Some((code, rest)) if !code.contains(' ') => (&subject[..code.len() + 3], rest),
+        _ => ("", subject),
+    };
+    let Some((head, description)) = rest.split_once(':') else {
+        return msg.to_string();
+    };
+    let (head, bang) = match head.strip_suffix('!') {
+        Some(h) => (h, "!"),
+        None => (head, ""),
     };
+    let ty = head.split_once('(').map_or(head, |(t, _)| t);
+    let scope_ok = head.len() == ty.len() || head.ends_with(')');
+    if ty.is_empty() || !ty.chars().all(|c| c.is_ascii_alphabetic()) || !scope_ok {
+        return msg.to_string();
+    }
+    let subject = format!("{}{}({}){}:{}", emoji, ty, scope, bang, description);
+    match body {
+        Some(b) => format!("{}\n{}", subject, b),
+        None => subject,
+    }
+}
 
-    let (out, err, ok) = if all {
-        run_git_status(&["commit", "-am", &full_msg])
+/// Cut the subject (first line) to `max` characters, ending in `...`. 0 = no limit.
+/// Limits too small for the `...` cut without one.
+pub(crate) fn truncate_subject(msg: &str, max: usize) -> String {
+    let (subject, body) = msg.split_once('\n').unwrap_or((msg, ""));
+    if max == 0 || subject.chars().count() <= max {
+        return msg.to_string();
+    }
+    let cut = if max <= 3 {
+        subject.chars().take(max).collect()
     } else {
-        run_git_status(&["commit", "-m", &full_msg])
+        let keep: String = subject.chars().take(max - 3).collect();
+        format!("{}...", keep.trim_end())
     };
-    if !silent {
-        println!("{}{}", out, err);
+    if body.is_empty() {
+        cut
+    } else {
+        format!("{}\n{}", cut, body)
     }
+}
 
-    if !ok {
-        if !silent {
-            println!("Commit failed.");
+/// Strip any `[AI:...]` the model echoed, then append the canonical tag if requested.
+/// On a one-line message the tag ends the subject, so it counts toward `subject_max_len`.
+pub(crate) fn tag_message(msg: &str, model: &str, tag: bool, subject_max_len: usize) -> String {
+    let clean = strip_ai_tags(msg);
+    if !tag {
+        return clean;
+    }
+    let tag = format!(" [AI:{}]", model);
+    // Cut the subject to leave room for the tag, as shape_message does for tickets
+    let clean = match subject_max_len {
+        0 => clean,
+        _ if clean.contains('\n') => clean,
+        max => truncate_subject(&clean, max.saturating_sub(tag.chars().count()).max(1)),
+    };
+    format!("{}{}", clean, tag)
+}
+
+fn strip_ai_tags(msg: &str) -> String {
+    let mut out = String::with_capacity(msg.len());
+    let mut rest = msg;
+    while let Some(start) = rest.find("[AI:") {
+        match rest[start..].find(']') {
+            Some(end) => {
+                out.push_str(&rest[..start]);
+                rest = &rest[start + end + 1..];
+                // Drop the space the tag leaves behind
+                if rest.starts_with(' ') && (out.is_empty() || out.ends_with([' ', '\n'])) {
+                    rest = &rest[1..];
+                }
+            }
+            None => break,
         }
-        return Ok(());
     }
+    out.push_str(rest);
 
-    if push {
-        if !silent {
-            println!("Pushing...");
+    out.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// `--strip-ai-tag`: drop `[AI:...]` tags from fetched commits so the model doesn't echo them.
+pub(crate) fn strip_ai_tag(commits: &mut [CommitInfo]) {
+    for c in commits {
+        c.message = strip_ai_tags(&c.message);
+        c.body = strip_ai_tags(&c.body);
+    }
+}
+
+/// Up to `n` recent subjects as style examples, skipping gitar-tagged (`[AI:...]`)
+/// commits so generated messages don't feed back into the prompt.
+pub(crate) fn style_subjects(commits: &[CommitInfo], n: usize) -> Vec<String> {
+    commits
+        .iter()
+        .map(|c| c.message.trim())
+        .filter(|m| !m.is_empty() && !m.contains("[AI:"))
+        .take(n)
+        .map(String::from)
+        .collect()
+}
+
+/// Few-shot block for `--style-from-history <n>` (empty when not requested).
+fn load_style_block(n: Option<usize>) -> Result<String> {
+    match n {
+        None | Some(0) => Ok(String::new()),
+        Some(n) => {
+            // Over-fetch so skipped tagged commits still leave n examples
+            let commits = get_commit_logs(&LogQuery {
+                limit: Some(n * 3),
+                no_merges: true,
+                ..Default::default()
+            })?;
+            Ok(commit_style_block(&style_subjects(&commits, n)))
         }
-        let (o, e, _) = run_git_status(&["push"]);
-        if !silent {
-            println!("{}{}", o, e);
+    }
+}
+
+/// Diff the commit message describes: staged plus unstaged changes, or with
+/// `staged_only` just the index (like `git commit`). Errors if nothing is staged.
+pub(crate) fn select_commit_diff(staged: &str, unstaged: &str, staged_only: bool) -> Result<String> {
+    if staged_only {
+        if staged.trim().is_empty() {
+            bail!("Nothing staged (use `git add`, or drop --staged-only).");
         }
+        return Ok(staged.to_string());
     }
 
-    Ok(())
+    let mut raw_diff = String::new();
+    if !staged.trim().is_empty() {
+        raw_diff.push_str(staged);
+    }
+    if !unstaged.trim().is_empty() {
+        if !raw_diff.is_empty() {
+            raw_diff.push('\n');
+        }
+        raw_diff.push_str(unstaged);
+    }
+    Ok(raw_diff)
 }
 
-pub async fn cmd_staged(client: &LlmClient, stream: bool, alg: u8, max_diff_chars: usize) -> Result<()> {
-    let raw_diff = get_diff(None, true, usize::MAX)?;
+/// Build the `commit` prompt from staged plus unstaged changes (None if there are none).
+/// `forced_scope` replaces the scope guessed from the changed paths.
+pub(crate) fn prepare_commit(
+    silent: bool,
+    staged_only: bool,
+    forced_scope: Option<&str>,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<Prompt>> {
+    // The whole repo, even from a subdirectory: `git commit -a` / `git add -A` take it all
+    let root = [":/".to_string()];
+    let staged = get_diff(None, true, usize::MAX, &opts.diff, &root)?;
+    let unstaged = match staged_only {
+        true => String::new(),
+        false => get_diff(None, false, usize::MAX, &opts.diff, &root)?,
+    };
+    // Same order as the combined diff, so repeated paths pair up
+    let numstat = [
+        get_numstat(None, true, &root)?,
+        if staged_only { String::new() } else { get_numstat(None, false, &root)? },
+    ]
+    .concat();
+
+    let raw_diff = select_commit_diff(&staged, &unstaged, staged_only)?;
+    check_conflicts(&get_unmerged_paths()?, &raw_diff)?;
     if raw_diff.trim().is_empty() {
-        bail!("No staged changes.");
+        return Ok(None);
     }
 
-    let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
+    let detected = dominant_scope(&split_diff_by_file(&raw_diff, opts.diff.max_line_chars));
+    let scope = match forced_scope {
+        Some(s) => Some(CommitScope::Forced(s)),
+        None => detected.as_deref().map(CommitScope::Detected),
+    };
+    let diff = apply_smart_diff(&raw_diff, Some(&numstat), silent, alg, opts)?;
+    Ok(Some(commit_prompt(&diff, scope, opts.emoji, opts.subject_max_len)))
+}
 
-    let prompt = COMMIT_USER_PROMPT.replace("{diff}", &diff);
-    let msg = client.chat(COMMIT_SYSTEM_PROMPT, &prompt, stream).await?;
-    if stream {
-        println!();
-    } else {
-        println!("{}", msg);
+/// Files whose diff adds a conflict marker line (`<<<<<<< ` / `>>>>>>> `). Handles plain
+/// diffs and the combined `diff --cc` git shows for unmerged files.
+pub(crate) fn conflict_marker_files(raw_diff: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    let mut current = "";
+    let mut prefix_len = 1;
+
+    for line in raw_diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            current = rest.split(" b/").last().unwrap_or(rest);
+            prefix_len = 1;
+        } else if let Some(path) = line.strip_prefix("diff --cc ") {
+            current = path;
+            prefix_len = 2;
+        } else if line.len() > prefix_len && !line.starts_with("+++") {
+            let (prefix, content) = line.split_at(prefix_len);
+            let added = prefix.contains('+') && !prefix.contains('-');
+            let marker = ["<<<<<<<", ">>>>>>>"]
+                .iter()
+                .any(|m| content == *m || content.starts_with(&format!("{} ", m)));
+            if added && marker && files.last().map(String::as_str) != Some(current) {
+                files.push(current.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// Refuse to describe (and later stage) unresolved conflicts: paths git reports as
+/// unmerged, or a diff that adds conflict markers.
+pub(crate) fn check_conflicts(unmerged: &[String], raw_diff: &str) -> Result<()> {
+    if !unmerged.is_empty() {
+        bail!(
+            "Unresolved merge conflicts in: {}. Resolve them and `git add` the files first.",
+            unmerged.join(", ")
+        );
+    }
+    let marked = conflict_marker_files(raw_diff);
+    if !marked.is_empty() {
+        bail!("Conflict markers in: {}. Resolve them before committing.", marked.join(", "));
     }
     Ok(())
 }
 
-pub async fn cmd_unstaged(client: &LlmClient, stream: bool, alg: u8, max_diff_chars: usize) -> Result<()> {
-    let raw_diff = get_diff(None, false, usize::MAX)?;
+/// Tracked changes followed by the new-file diffs of untracked files.
+pub(crate) fn append_untracked(tracked: &str, untracked: &str) -> String {
+    match (tracked.trim().is_empty(), untracked.trim().is_empty()) {
+        (_, true) => tracked.to_string(),
+        (true, false) => untracked.to_string(),
+        (false, false) => format!("{}\n{}", tracked.trim_end(), untracked),
+    }
+}
+
+/// Build the `staged` / `unstaged` prompt, optionally limited to `paths`. With
+/// `include_untracked`, new files are described too (nothing gets staged).
+pub(crate) fn prepare_staged(
+    staged: bool,
+    paths: &[String],
+    include_untracked: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Prompt> {
+    let mut raw_diff = get_diff(None, staged, usize::MAX, &opts.diff, paths)?;
+    if include_untracked {
+        raw_diff = append_untracked(&raw_diff, &get_untracked_diff(Path::new("."), opts.diff.context)?);
+    }
     if raw_diff.trim().is_empty() {
+        if staged {
+            bail!("No staged changes.");
+        }
         bail!("No unstaged changes.");
     }
 
-    let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
-    let prompt = COMMIT_USER_PROMPT.replace("{diff}", &diff);
-    let msg = client.chat(COMMIT_SYSTEM_PROMPT, &prompt, stream).await?;
+    let scope = dominant_scope(&split_diff_by_file(&raw_diff, opts.diff.max_line_chars));
+    let numstat = get_numstat(None, staged, paths).ok();
+    let diff = apply_smart_diff(&raw_diff, numstat.as_deref(), false, alg, opts)?;
+    let scope = scope.as_deref().map(CommitScope::Detected);
+    Ok(commit_prompt(&diff, scope, opts.emoji, opts.subject_max_len))
+}
+
+pub async fn cmd_staged(
+    client: &LlmClient,
+    paths: Vec<String>,
+    stream: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<()> {
+    let prompt = prepare_staged(true, &paths, false, alg, opts)?;
+    let msg = client.chat(&prompt.system, &prompt.user, stream).await?;
+    if stream {
+        println!();
+    } else {
+        println!("{}", truncate_subject(&msg, opts.subject_max_len));
+    }
+    Ok(())
+}
+
+pub async fn cmd_unstaged(
+    client: &LlmClient,
+    include_untracked: bool,
+    stream: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<()> {
+    let prompt = prepare_staged(false, &[], include_untracked, alg, opts)?;
+    let msg = client.chat(&prompt.system, &prompt.user, stream).await?;
     if stream {
         println!();
     } else {
-        println!("{}", msg);
+        println!("{}", truncate_subject(&msg, opts.subject_max_len));
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_review_choices() {
+        assert_eq!(parse_review("\n"), Review::Accept);
+        assert_eq!(parse_review("g"), Review::Regenerate);
+        assert_eq!(parse_review("F\n"), Review::Feedback);
+        assert_eq!(parse_review("e"), Review::Edit);
+        assert_eq!(parse_review("x"), Review::Cancel);
+    }
+
+    #[test]
+    fn feedback_conversation_alternates_roles() {
+        let mut turns = Vec::new();
+        push_feedback(&mut turns, "Add login", "make it shorter\n");
+        let conv = feedback_conversation("DIFF", &turns);
+        assert_eq!(
+            conv,
+            vec![
+                ChatMessage::user("DIFF"),
+                ChatMessage::assistant("Add login"),
+                ChatMessage::user("make it shorter"),
+            ]
+        );
+    }
+
+    #[test]
+    fn feedback_conversation_accumulates_rounds() {
+        let mut turns = Vec::new();
+        push_feedback(&mut turns, "v1", "shorter");
+        push_feedback(&mut turns, "v2", "mention the migration");
+        let conv = feedback_conversation("DIFF", &turns);
+        let roles: Vec<&str> = conv.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "user", "assistant", "user"]);
+        assert_eq!(conv[4].content, "mention the migration");
+    }
+
+    #[tokio::test]
+    async fn accept_then_commit_failed_generation_leaves_index() {
+        let steps = std::cell::RefCell::new(Vec::new());
+        let result = accept_then_commit(
+            async { Err(anyhow::anyhow!("rate limited")) },
+            true,
+            || {
+                steps.borrow_mut().push("stage");
+                Ok(())
+            },
+            |_| {
+                steps.borrow_mut().push("commit");
+                Ok(true)
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(steps.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn accept_then_commit_cancel_leaves_index() {
+        let staged = std::cell::Cell::new(false);
+        let result = accept_then_commit(
+            async { Ok(None) },
+            true,
+            || {
+                staged.set(true);
+                Ok(())
+            },
+            |_| Ok(true),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, None);
+        assert!(!staged.get());
+    }
+
+    #[tokio::test]
+    async fn accept_then_commit_stages_after_acceptance() {
+        let steps = std::cell::RefCell::new(Vec::new());
+        let review = async {
+            steps.borrow_mut().push("accept".to_string());
+            Ok(Some("Add login".to_string()))
+        };
+        let result = accept_then_commit(
+            review,
+            true,
+            || {
+                steps.borrow_mut().push("stage".into());
+                Ok(())
+            },
+            |m| {
+                steps.borrow_mut().push(format!("commit {}", m));
+                Ok(true)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Some(true));
+        assert_eq!(*steps.borrow(), ["accept", "stage", "commit Add login"]);
+    }
+
+    #[tokio::test]
+    async fn accept_then_commit_skips_staging_without_all() {
+        let staged = std::cell::Cell::new(false);
+        let result = accept_then_commit(
+            async { Ok(Some("Fix".to_string())) },
+            false,
+            || {
+                staged.set(true);
+                Ok(())
+            },
+            |_| Ok(false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Some(false));
+        assert!(!staged.get());
+    }
+
+    #[test]
+    fn feedback_conversation_without_feedback_is_single_turn() {
+        assert_eq!(feedback_conversation("DIFF", &[]), vec![ChatMessage::user("DIFF")]);
+    }
+
+    #[test]
+    fn append_untracked_adds_new_file_after_tracked_changes() {
+        let tracked = "diff --git a/src/lib.rs b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n";
+        let new_file = "diff --git a/src/new.rs b/src/new.rs\nnew file mode 100644\n\
+                        --- /dev/null\n+++ b/src/new.rs\n@@ -0,0 +1 @@\n+fn new() {}\n";
+        let combined = append_untracked(tracked, new_file);
+        assert_eq!(combined, format!("{}\n{}", tracked.trim_end(), new_file));
+
+        let chunks = split_diff_by_file(&combined, crate::diff::DEFAULT_MAX_LINE_CHARS);
+        let paths: Vec<&str> = chunks.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["src/lib.rs", "src/new.rs"]);
+        assert_eq!(chunks[1].lines_added, 1);
+
+        assert_eq!(append_untracked("", new_file), new_file);
+        assert_eq!(append_untracked(tracked, "\n"), tracked);
+    }
+
+    #[test]
+    fn set_subject_scope_rewrites_or_inserts() {
+        assert_eq!(set_subject_scope("Feat(ui): Add export", "api"), "Feat(api): Add export");
+        assert_eq!(set_subject_scope("fix: handle 404", "api"), "fix(api): handle 404");
+        assert_eq!(set_subject_scope("feat(db)!: drop v1 tables", "api"), "feat(api)!: drop v1 tables");
+        assert_eq!(
+            set_subject_scope(":sparkles: feat: add export\n\nDetails", "api"),
+            ":sparkles: feat(api): add export\n\nDetails"
+        );
+    }
+
+    #[test]
+    fn set_subject_scope_leaves_plain_subjects() {
+        assert_eq!(set_subject_scope("Add export", "api"), "Add export");
+        assert_eq!(set_subject_scope("Fix the bug: retry on 503", "api"), "Fix the bug: retry on 503");
+        assert_eq!(set_subject_scope("Docs(readme: fix", "api"), "Docs(readme: fix");
+    }
+
+    #[test]
+    fn tag_message_appends_single_tag() {
+        assert_eq!(tag_message("Fix login", "gpt-4o", true, 0), "Fix login [AI:gpt-4o]");
+        assert_eq!(tag_message("Fix login", "gpt-4o", false, 0), "Fix login");
+    }
+
+    #[test]
+    fn truncate_subject_cuts_with_ellipsis() {
+        let msg = "Add retry with exponential backoff to the payment client";
+        assert_eq!(truncate_subject(msg, 20), "Add retry with ex...");
+        assert_eq!(truncate_subject(msg, 20).chars().count(), 20);
+        assert_eq!(truncate_subject(msg, 0), msg);
+        assert_eq!(truncate_subject(msg, msg.len()), msg);
+        // Trailing space before the ellipsis is dropped; the body is kept
+        assert_eq!(truncate_subject("Fix the login bug\n\nDetails", 11), "Fix the...\n\nDetails");
+        assert_eq!(truncate_subject("Añadir ñandú", 9), "Añadir...");
+        // Too short for the ellipsis
+        assert_eq!(truncate_subject(msg, 2), "Ad");
+        assert_eq!(truncate_subject(msg, 3), "Add");
+        assert_eq!(truncate_subject(msg, 4), "A...");
+    }
+
+    #[test]
+    fn shape_message_keeps_ticket_within_max_len() {
+        let msg = "feat: add retry with exponential backoff to the payment client\n\n- cap at 5 tries";
+        let ticket = Some(("PAY-42", TicketPosition::Suffix));
+        let shaped = shape_message(msg, Some("payments"), false, ticket, 50);
+        let subject = shaped.lines().next().unwrap();
+        assert!(subject.starts_with("feat(payments): add retry"), "{}", subject);
+        assert!(subject.ends_with("... (PAY-42)"), "{}", subject);
+        assert_eq!(subject.chars().count(), 50);
+        assert!(shaped.ends_with("\n\n- cap at 5 tries"));
+
+        let ticket = Some(("PAY-42", TicketPosition::Prefix));
+        let subject = shape_message(msg, None, true, ticket, 30);
+        assert_eq!(subject, "PAY-42 feat: add retry with...");
+        // Without a limit nothing is cut
+        let shaped = shape_message(msg, None, true, Some(("PAY-42", TicketPosition::Suffix)), 0);
+        assert_eq!(shaped, "feat: add retry with exponential backoff to the payment client (PAY-42)");
+    }
+
+    #[test]
+    fn subject_only_drops_body() {
+        let msg = "Fix login timeout\n\n- retry once\n- log the failure";
+        assert_eq!(subject_only(msg), "Fix login timeout");
+        assert_eq!(subject_only("\n  Bump deps  \n"), "Bump deps");
+        assert_eq!(subject_only("Fix typo"), "Fix typo");
+        assert_eq!(subject_only(""), "");
+    }
+
+    #[test]
+    fn tag_message_strips_echoed_tag() {
+        let msg = "Fix login timeout [AI:gpt-4o]";
+        assert_eq!(tag_message(msg, "gpt-4o", true, 0), "Fix login timeout [AI:gpt-4o]");
+        assert_eq!(tag_message("[AI:old] Fix login [AI:x]", "m", false, 0), "Fix login");
+    }
+
+    #[test]
+    fn tag_message_keeps_subject_within_max_len() {
+        let msg = "Add retry with exponential backoff";
+        let tagged = tag_message(msg, "gpt-4o", true, 30);
+        assert_eq!(tagged, "Add retry with... [AI:gpt-4o]");
+        assert!(tagged.chars().count() <= 30);
+        assert_eq!(tag_message("Fix typo", "gpt-4o", true, 30), "Fix typo [AI:gpt-4o]");
+        // With a body the tag goes after it, so the subject keeps its length
+        let msg = "Add retry with exponential backoff\n\n- cap at 5 tries";
+        assert_eq!(tag_message(msg, "m", true, 34), format!("{} [AI:m]", msg));
+    }
+
+    #[test]
+    fn build_commit_args_signoff() {
+        let args = build_commit_args("Fix login [AI:m]", false, true, None, None);
+        assert_eq!(args, ["commit", "-s", "--message=Fix login [AI:m]"]);
+        let args = build_commit_args("Fix login", true, false, None, None);
+        assert_eq!(args, ["commit", "-a", "--message=Fix login"]);
+        assert!(!args.contains(&"-s".to_string()));
+    }
+
+    #[test]
+    fn select_commit_diff_combines_by_default() {
+        let staged = "diff --git a/a b/a\n+1\n";
+        let unstaged = "diff --git a/b b/b\n+2\n";
+        assert_eq!(select_commit_diff(staged, unstaged, false).unwrap(), format!("{}\n{}", staged, unstaged));
+        assert_eq!(select_commit_diff("", unstaged, false).unwrap(), unstaged);
+        assert_eq!(select_commit_diff("", " \n", false).unwrap(), "");
+    }
+
+    #[test]
+    fn select_commit_diff_staged_only() {
+        let staged = "diff --git a/a b/a\n+1\n";
+        let unstaged = "diff --git a/b b/b\n+2\n";
+        assert_eq!(select_commit_diff(staged, unstaged, true).unwrap(), staged);
+        let err = select_commit_diff("", unstaged, true).unwrap_err();
+        assert!(err.to_string().starts_with("Nothing staged"));
+    }
+
+    #[test]
+    fn build_commit_args_gpg_sign() {
+        let args = build_commit_args("Fix login [AI:m]", false, true, Some(""), None);
+        assert_eq!(args, ["commit", "-s", "-S", "--message=Fix login [AI:m]"]);
+        let args = build_commit_args("Fix login", true, false, Some("ABCD1234"), None);
+        assert_eq!(args, ["commit", "-a", "-SABCD1234", "--message=Fix login"]);
+    }
+
+    const CONFLICT_DIFF: &str = r#"diff --git a/src/app.rs b/src/app.rs
+index 1111111..2222222 100644
+--- a/src/app.rs
++++ b/src/app.rs
+@@ -1,3 +1,7 @@
+ fn main() {
++<<<<<<< HEAD
+     run(1);
++=======
++    run(2);
++>>>>>>> feature
+ }
+diff --git a/README.md b/README.md
+--- a/README.md
++++ b/README.md
+@@ -1 +1,3 @@
++Title
++=======
++Text mentioning <<<<<<< markers inline
+"#;
+
+    #[test]
+    fn conflict_markers_found_in_added_lines() {
+        assert_eq!(conflict_marker_files(CONFLICT_DIFF), ["src/app.rs"]);
+        let err = check_conflicts(&[], CONFLICT_DIFF).unwrap_err().to_string();
+        assert!(err.contains("Conflict markers in: src/app.rs"));
+    }
+
+    #[test]
+    fn conflict_markers_in_combined_diff() {
+        let diff = r#"diff --cc src/app.rs
+index 1111111,3333333..0000000
+--- a/src/app.rs
++++ b/src/app.rs
+@@@ -1,3 -1,3 +1,7 @@@
+  fn main() {
+++<<<<<<< HEAD
+ +    run(1);
+++=======
++     run(2);
+++>>>>>>> feature
+  }
+"#;
+        assert_eq!(conflict_marker_files(diff), ["src/app.rs"]);
+    }
+
+    #[test]
+    fn removed_conflict_markers_are_fine() {
+        let diff = "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -1,3 +1 @@\n-<<<<<<< HEAD\n-x\n->>>>>>> b\n+x\n";
+        assert!(conflict_marker_files(diff).is_empty());
+        assert!(check_conflicts(&[], diff).is_ok());
+    }
+
+    #[test]
+    fn unmerged_paths_refused() {
+        let err = check_conflicts(&["src/app.rs".into(), "b.rs".into()], "").unwrap_err().to_string();
+        assert!(err.contains("Unresolved merge conflicts in: src/app.rs, b.rs"));
+    }
+
+    #[test]
+    fn git_commit_binds_leading_dash_message() {
+        let mut seen = Vec::new();
+        git_commit("--amend is not a flag here", false, false, None, |args| {
+            seen = args.iter().map(|s| s.to_string()).collect();
+            (String::new(), String::new(), true)
+        });
+        assert_eq!(seen, ["commit", "--message=--amend is not a flag here"]);
+    }
+
+    #[test]
+    fn git_commit_multiline_uses_message_file() {
+        let message = "-Fix parser\n\n- handle empty input\n\n[AI:m]";
+        let mut file = None;
+        let (_, _, ok) = git_commit(message, true, false, None, |args| {
+            assert_eq!(&args[..3], ["commit", "-a", "-F"]);
+            assert!(args[3].ends_with("GITAR_COMMIT_MSG"), "{}", args[3]);
+            assert_eq!(fs::read_to_string(args[3]).unwrap(), message);
+            file = Some(args[3].to_string());
+            (String::new(), String::new(), true)
+        });
+        assert!(ok);
+        assert!(!Path::new(&file.unwrap()).exists());
+    }
+
+    #[test]
+    fn push_branch_retries_with_upstream() {
+        let mut calls: Vec<String> = Vec::new();
+        let (_, e, ok) = push_branch(false, "feature/x", |args| {
+            calls.push(args.join(" "));
+            if args.len() == 1 {
+                let err = "fatal: The current branch feature/x has no upstream branch.\n";
+                (String::new(), err.to_string(), false)
+            } else {
+                (String::new(), "branch 'feature/x' set up to track 'origin/feature/x'.\n".into(), true)
+            }
+        });
+        assert_eq!(calls, ["push", "push -u origin feature/x"]);
+        assert!(e.contains("set up to track"));
+        assert!(ok);
+    }
+
+    #[test]
+    fn push_branch_no_retry_on_other_failures() {
+        let mut calls = 0;
+        let (_, e, ok) = push_branch(false, "main", |_| {
+            calls += 1;
+            (String::new(), "rejected (non-fast-forward)".into(), false)
+        });
+        assert_eq!(calls, 1);
+        assert!(e.contains("rejected"));
+        assert!(!ok);
+        assert_eq!(git_step("Push", ok).unwrap_err().to_string(), "Push failed.");
+    }
+
+    #[test]
+    fn review_skipped_only_without_tty_or_override() {
+        assert!(accept_without_review(false, false));
+        assert!(!accept_without_review(false, true));
+        assert!(!accept_without_review(true, false));
+        assert!(!accept_without_review(true, true));
+    }
+
+    #[test]
+    fn git_step_maps_success_to_result() {
+        assert!(git_step("Commit", true).is_ok());
+        assert_eq!(git_step("Commit", false).unwrap_err().to_string(), "Commit failed.");
+    }
+
+    #[test]
+    fn push_branch_set_upstream_pushes_once() {
+        let mut calls: Vec<String> = Vec::new();
+        push_branch(true, "main", |args| {
+            calls.push(args.join(" "));
+            (String::new(), String::new(), true)
+        });
+        assert_eq!(calls, ["push -u origin main"]);
+        assert_eq!(build_push_args(None), ["push"]);
+    }
+
+    #[test]
+    fn append_signoff_adds_trailer_once() {
+        let line = "Signed-off-by: Ana <ana@example.com>";
+        let msg = append_signoff("Fix login\n", line);
+        assert_eq!(msg, format!("Fix login\n\n{}", line));
+        assert_eq!(append_signoff(&msg, line), msg);
+    }
+
+    #[test]
+    fn resolve_editor_from_env() {
+        assert_eq!(resolve_editor(Some("nano".into()), false), "nano");
+        assert_eq!(resolve_editor(Some(" code --wait ".into()), true), "code --wait");
+    }
+
+    #[test]
+    fn resolve_editor_fallbacks() {
+        assert_eq!(resolve_editor(None, false), "vi");
+        assert_eq!(resolve_editor(None, true), "notepad");
+        assert_eq!(resolve_editor(Some("  ".into()), false), "vi");
+    }
+
+    #[test]
+    fn clean_edited_message_drops_comments() {
+        let content = "Fix login\n\nHandle expired tokens\n\n# Edit the commit message.\n# ignored\n";
+        assert_eq!(clean_edited_message(content, '#'), "Fix login\n\nHandle expired tokens");
+        assert_eq!(clean_edited_message("# only comments\n\n", '#'), "");
+        assert_eq!(clean_edited_message("; note\nFix", ';'), "Fix");
+    }
+
+    #[test]
+    fn strip_ai_tags_keeps_other_brackets() {
+        assert_eq!(strip_ai_tags("Fix [core] parser"), "Fix [core] parser");
+        assert_eq!(strip_ai_tags("Fix parser [AI:unterminated"), "Fix parser [AI:unterminated");
+        assert_eq!(strip_ai_tags("Fix [AI:m] parser\n  - keep indent"), "Fix parser\n  - keep indent");
+    }
+
+    fn info(message: &str) -> CommitInfo {
+        CommitInfo {
+            hash: "abc".into(),
+            author: "dev".into(),
+            date: "2024-01-01".into(),
+            message: message.into(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn strip_ai_tag_cleans_subjects_and_bodies() {
+        let mut commits = vec![
+            CommitInfo {
+                hash: "a".into(),
+                author: "Ann".into(),
+                date: "2024-01-01".into(),
+                message: "Fix login timeout [AI:gpt-4o]".into(),
+                body: "- retry once\n\n[AI:gpt-4o]".into(),
+            },
+            CommitInfo {
+                hash: "b".into(),
+                author: "Bob".into(),
+                date: "2024-01-02".into(),
+                message: "Bump [deps] to latest".into(),
+                body: String::new(),
+            },
+        ];
+        strip_ai_tag(&mut commits);
+        assert_eq!(commits[0].message, "Fix login timeout");
+        assert_eq!(commits[0].body, "- retry once");
+        assert_eq!(commits[1].message, "Bump [deps] to latest");
+    }
+
+    #[test]
+    fn style_subjects_skips_tagged_commits() {
+        let commits = vec![
+            info("Fix parser [AI:gpt-4o]"),
+            info("Fix(auth): Refresh expired tokens"),
+            info("  "),
+            info("Docs: Update install steps"),
+            info("Feat: Add export"),
+        ];
+        assert_eq!(
+            style_subjects(&commits, 2),
+            vec!["Fix(auth): Refresh expired tokens", "Docs: Update install steps"]
+        );
+        assert!(style_subjects(&[info("Add x [AI:claude]")], 3).is_empty());
+    }
+}
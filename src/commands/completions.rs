@@ -0,0 +1,56 @@
+// src/commands/completions.rs
+use anyhow::Result;
+use clap::builder::PossibleValuesParser;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::{self, Write};
+
+use crate::cli::Cli;
+
+use super::models::cached_model_names;
+
+/// Completion script for `shell`, as clap_complete generates it from the CLI definition.
+/// `models` (from the `gitar models` cache) become the `--model` candidates.
+fn completion_script(shell: Shell, models: &[String]) -> Vec<u8> {
+    let mut cmd = Cli::command();
+    if !models.is_empty() {
+        let names = PossibleValuesParser::new(models.iter().map(String::from));
+        cmd = cmd.mut_arg("model", |arg| arg.value_parser(names));
+    }
+    let mut out = Vec::new();
+    clap_complete::generate(shell, &mut cmd, "gitar", &mut out);
+    out
+}
+
+pub fn cmd_completions(shell: Shell) -> Result<()> {
+    io::stdout().write_all(&completion_script(shell, &cached_model_names()))?;
+    Ok(())
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn completions_generated_for_every_shell() {
+        for shell in Shell::value_variants() {
+            let script = String::from_utf8(completion_script(*shell, &[])).unwrap();
+            assert!(!script.is_empty(), "{:?}", shell);
+            assert!(script.contains("gitar"), "{:?}", shell);
+            assert!(script.contains("commit"), "{:?}", shell);
+        }
+    }
+
+    #[test]
+    fn cached_models_complete_model_flag() {
+        let models = ["gpt-4o".to_string(), "o3-mini".to_string()];
+        let script = String::from_utf8(completion_script(Shell::Bash, &models)).unwrap();
+        assert!(script.contains("gpt-4o o3-mini"));
+        let script = String::from_utf8(completion_script(Shell::Bash, &[])).unwrap();
+        assert!(!script.contains("gpt-4o"));
+    }
+}
@@ -1,10 +1,27 @@
 // src/commands/config.rs
 use anyhow::{bail, Result};
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
 
 use crate::cli::Cli;
-use crate::config::{normalize_provider, Config, DEFAULT_MAX_DIFF_CHARS};
+use crate::client::LlmClient;
+use crate::color;
+use crate::config::{
+    env_var_for_provider, is_secret_header, normalize_provider, Config, ResolvedConfig, DEFAULT_MAX_DIFF_CHARS,
+    ENV_MAX_TOKENS, ENV_MODEL, ENV_PROVIDER, ENV_TEMPERATURE,
+};
+use crate::diff::DEFAULT_MAX_DIFF_FILES;
+
+/// Whether `gitar init` got any setting to write; without one it runs the wizard.
+pub fn init_has_flags(cli: &Cli) -> bool {
+    cli.provider.is_some()
+        || cli.api_key.is_some()
+        || cli.api_key_file.is_some()
+        || cli.model.is_some()
+        || cli.max_tokens.is_some()
+        || cli.temperature.is_some()
+        || cli.base_url.is_some()
+        || cli.base_branch.is_some()
+        || cli.stream
+}
 
 pub fn cmd_init(cli: &Cli, file: &Config) -> Result<()> {
     let mut config = file.clone();
@@ -25,6 +42,9 @@ pub fn cmd_init(cli: &Cli, file: &Config) -> Result<()> {
         if cli.api_key.is_some() {
             pc.api_key = cli.api_key.clone();
         }
+        if cli.api_key_file.is_some() {
+            pc.api_key_file = cli.api_key_file.clone();
+        }
         if cli.model.is_some() {
             pc.model = cli.model.clone();
         }
@@ -46,6 +66,7 @@ pub fn cmd_init(cli: &Cli, file: &Config) -> Result<()> {
         }
     } else if cli.stream
         || cli.api_key.is_some()
+        || cli.api_key_file.is_some()
         || cli.model.is_some()
         || cli.max_tokens.is_some()
         || cli.temperature.is_some()
@@ -92,13 +113,22 @@ pub fn cmd_config() -> Result<()> {
             .map(|n| n.to_string())
             .unwrap_or_else(|| format!("(default: {})", DEFAULT_MAX_DIFF_CHARS))
     );
+    println!(
+        "max_diff_files:   {}",
+        config
+            .max_diff_files
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| format!("(default: {})", DEFAULT_MAX_DIFF_FILES))
+    );
 
     let providers = [
         ("openai", &config.openai, "OPENAI_API_KEY"),
         ("claude", &config.claude, "ANTHROPIC_API_KEY"),
         ("gemini", &config.gemini, "GEMINI_API_KEY"),
         ("groq", &config.groq, "GROQ_API_KEY"),
+        ("openrouter", &config.openrouter, "OPENROUTER_API_KEY"),
         ("ollama", &config.ollama, "(none)"),
+        ("bedrock", &config.bedrock, "AWS_ACCESS_KEY_ID"),
     ];
 
     for (name, pc, env_var) in providers {
@@ -111,6 +141,9 @@ pub fn cmd_config() -> Result<()> {
                     .map(|k| format!("{}...", &k[..8.min(k.len())]))
                     .unwrap_or_else(|| format!("(env: {})", env_var))
             );
+            if let Some(path) = &p.api_key_file {
+                println!("  api_key_file: {}", path);
+            }
             println!(
                 "  model:       {}",
                 p.model.as_deref().unwrap_or("(default)")
@@ -139,8 +172,254 @@ pub fn cmd_config() -> Result<()> {
         }
     }
 
+    if let Some(models) = config.models.as_ref().filter(|m| !m.is_empty()) {
+        println!("\n[models]");
+        for (command, model) in models {
+            println!("  {:<12} {}", format!("{}:", command), model);
+        }
+    }
+
+    if let Some(headers) = config.headers.as_ref().filter(|h| !h.is_empty()) {
+        println!("\n[headers]");
+        for (name, value) in headers {
+            println!("  {}: {}", name, display_header_value(name, value));
+        }
+    }
+
     println!("\nUsage: gitar --provider <n> [command]");
-    println!("Priority: CLI args > provider config > env var > defaults");
+    for var in [ENV_PROVIDER, ENV_MODEL, ENV_MAX_TOKENS, ENV_TEMPERATURE] {
+        if let Ok(v) = std::env::var(var) {
+            if !v.trim().is_empty() {
+                println!("{}={} (active)", var, v.trim());
+            }
+        }
+    }
+    println!("Priority: CLI args > GITAR_* env vars > provider config > API key env var > defaults");
     Ok(())
 }
 
+/// Header values as `gitar config` shows them, hiding secret-looking ones.
+fn display_header_value(name: &str, value: &str) -> String {
+    if is_secret_header(name) {
+        "(redacted)".into()
+    } else {
+        value.to_string()
+    }
+}
+
+// =============================================================================
+// CONFIG VALIDATE
+// =============================================================================
+/// One line of the `config validate` / `doctor` checklist, with a hint when it failed
+#[derive(Debug)]
+pub(crate) struct Check {
+    pub(crate) label: &'static str,
+    pub(crate) ok: bool,
+    pub(crate) detail: String,
+    pub(crate) hint: Option<String>,
+}
+
+impl Check {
+    pub(crate) fn new(label: &'static str, ok: bool, detail: impl Into<String>) -> Self {
+        Self { label, ok, detail: detail.into(), hint: None }
+    }
+
+    pub(crate) fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Assemble the checklist from the resolved config plus the ping and model-list results
+/// (each skipped when None).
+pub(crate) fn validation_checks(
+    config: &ResolvedConfig,
+    ping: Option<&Result<()>>,
+    models: Option<&Result<Vec<String>>>,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let key_check = match (&config.api_key, env_var_for_provider(&config.provider)) {
+        (Some(_), _) => Check::new("API key", true, format!("present for {}", config.provider)),
+        (None, None) => Check::new("API key", true, format!("not required for {}", config.provider)),
+        (None, Some(var)) => Check::new("API key", false, format!("missing (set {} or api_key)", var))
+            .with_hint(format!("export {}=... or run `gitar init --provider {}`", var, config.provider)),
+    };
+    checks.push(key_check);
+
+    if let Some(ping) = ping {
+        checks.push(match ping {
+            Ok(()) => Check::new("Base URL", true, format!("{} reachable", config.base_url)),
+            Err(e) => {
+                let hint = match config.provider.as_str() {
+                    "ollama" => "Start Ollama (`ollama serve`) or point base_url at it",
+                    _ => "Check network and proxy settings, or set base_url (`gitar init --base-url URL`)",
+                };
+                Check::new("Base URL", false, format!("{} unreachable: {}", config.base_url, e)).with_hint(hint)
+            }
+        });
+    }
+
+    let Some(models) = models else {
+        return checks;
+    };
+    checks.push(match models {
+        Ok(list) if list.iter().any(|m| m == &config.model) => {
+            Check::new("Model", true, format!("{} available", config.model))
+        }
+        Ok(list) if list.is_empty() => {
+            Check::new("Model", false, format!("{}: provider listed no models", config.model))
+        }
+        Ok(_) => Check::new("Model", false, format!("{} not in the provider's model list", config.model)),
+        Err(e) => Check::new("Model", false, format!("could not list models: {}", e)),
+    });
+
+    checks
+}
+
+/// `[ok]` / `[fail]` lines, each failure followed by its hint.
+pub(crate) fn print_checks(checks: &[Check]) {
+    for c in checks {
+        let mark = match c.ok {
+            true => color::green("[ok]  "),
+            false => color::yellow("[fail]"),
+        };
+        println!("{} {:<10} {}", mark, c.label, c.detail);
+        if let Some(hint) = &c.hint {
+            println!("       {:<10} {}", "", color::dim(&format!("-> {}", hint)));
+        }
+    }
+}
+
+pub async fn cmd_config_validate(config: &ResolvedConfig, client: &LlmClient, ping: bool) -> Result<()> {
+    println!(
+        "Validating {} ({}, {} API)\n",
+        config.provider,
+        config.base_url,
+        client.api()
+    );
+
+    let ping_result = match ping {
+        true => Some(client.ping().await),
+        false => None,
+    };
+    let models = client.list_models().await;
+    let checks = validation_checks(config, ping_result.as_ref(), Some(&models));
+    print_checks(&checks);
+
+    let failed = checks.iter().filter(|c| !c.ok).count();
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DEFAULT_POOL_IDLE_TIMEOUT_SECS, DEFAULT_POOL_MAX_IDLE_PER_HOST};
+
+    fn resolved(provider: &str, api_key: Option<&str>) -> ResolvedConfig {
+        ResolvedConfig {
+            provider: provider.into(),
+            api_key: api_key.map(String::from),
+            model: "gpt-4o".into(),
+            max_tokens: 500,
+            temperature: 0.5,
+            base_url: "https://api.openai.com/v1".into(),
+            base_branch: "main".into(),
+            stream: false,
+            max_diff_chars: 10_000,
+            pool_idle_timeout_secs: DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            headers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn secret_header_values_are_redacted() {
+        assert_eq!(display_header_value("X-Org-Id", "42"), "42");
+        assert_eq!(display_header_value("Authorization", "Bearer abc123"), "(redacted)");
+        assert_eq!(display_header_value("X-Api-Key", "k1"), "(redacted)");
+    }
+
+    #[test]
+    fn init_without_flags_runs_wizard() {
+        use clap::Parser;
+        let cli = Cli::try_parse_from(["gitar", "init"]).unwrap();
+        assert!(!init_has_flags(&cli));
+        let cli = Cli::try_parse_from(["gitar", "--provider", "groq", "init"]).unwrap();
+        assert!(init_has_flags(&cli));
+        // Flags unrelated to the config file don't count
+        let cli = Cli::try_parse_from(["gitar", "--no-color", "init"]).unwrap();
+        assert!(!init_has_flags(&cli));
+    }
+
+    fn models(names: &[&str]) -> Result<Vec<String>> {
+        Ok(names.iter().map(|m| m.to_string()).collect())
+    }
+
+    fn results(checks: &[Check]) -> Vec<(&str, bool)> {
+        checks.iter().map(|c| (c.label, c.ok)).collect()
+    }
+
+    #[test]
+    fn validate_all_pass_without_ping() {
+        let checks = validation_checks(&resolved("openai", Some("sk-x")), None, Some(&models(&["gpt-4o", "o3"])));
+        assert_eq!(results(&checks), vec![("API key", true), ("Model", true)]);
+    }
+
+    #[test]
+    fn validate_missing_key_names_env_var() {
+        let checks = validation_checks(&resolved("claude", None), None, Some(&models(&["gpt-4o"])));
+        assert!(!checks[0].ok);
+        assert!(checks[0].detail.contains("ANTHROPIC_API_KEY"));
+        assert!(checks[0].hint.as_deref().unwrap().starts_with("export ANTHROPIC_API_KEY="));
+    }
+
+    #[test]
+    fn validate_ollama_needs_no_key() {
+        let checks = validation_checks(&resolved("ollama", None), None, Some(&models(&["gpt-4o"])));
+        assert!(checks[0].ok);
+    }
+
+    #[test]
+    fn validate_unknown_model_and_list_failure() {
+        let config = resolved("openai", Some("sk-x"));
+        let checks = validation_checks(&config, None, Some(&models(&["o3"])));
+        assert_eq!(results(&checks)[1], ("Model", false));
+
+        let checks = validation_checks(&config, None, Some(&models(&[])));
+        assert_eq!(results(&checks)[1], ("Model", false));
+
+        let err = Err(anyhow::anyhow!("401 Unauthorized"));
+        let checks = validation_checks(&config, None, Some(&err));
+        assert!(!checks[1].ok);
+        assert!(checks[1].detail.contains("401"));
+    }
+
+    #[test]
+    fn validate_ping_adds_base_url_check() {
+        let config = resolved("openai", Some("sk-x"));
+        let ok = Ok(());
+        let checks = validation_checks(&config, Some(&ok), Some(&models(&["gpt-4o"])));
+        assert_eq!(
+            results(&checks),
+            vec![("API key", true), ("Base URL", true), ("Model", true)]
+        );
+
+        let down = Err(anyhow::anyhow!("connection refused"));
+        let checks = validation_checks(&config, Some(&down), Some(&models(&["gpt-4o"])));
+        assert_eq!(results(&checks)[1], ("Base URL", false));
+        assert!(checks[1].detail.contains("connection refused"));
+        assert!(checks[1].hint.as_deref().unwrap().contains("--base-url"));
+
+        let checks = validation_checks(&resolved("ollama", None), Some(&down), None);
+        assert_eq!(results(&checks), vec![("API key", true), ("Base URL", false)]);
+        assert!(checks[1].hint.as_deref().unwrap().contains("ollama serve"));
+    }
+}
+
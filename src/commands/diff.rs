@@ -1,22 +1,28 @@
 // src/commands/diff.rs
 use anyhow::Result;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
-use crate::diff::{get_llm_diff_preview, DiffAlg};
-use crate::git::{get_diff, get_diff_stats};
+use crate::color;
+use crate::diff::{get_llm_diff_preview, pick_alg, selectable_paths, DiffAlg, DiffOptions, DiffStats};
+use crate::git::{get_diff, get_diff_stats, get_numstat, linguist_generated};
 
+#[allow(clippy::too_many_arguments)]
 pub fn cmd_diff(
     target: Option<String>,
     staged: bool,
-    max_chars: usize,
     alg: Option<u8>,
     include_stats: bool,
     stats_only: bool,
     compare: bool,
+    algo_bench: bool,
+    diff: &DiffOptions,
 ) -> Result<()> {
+    let max_chars = diff.max_chars;
     let raw_diff = if staged {
-        get_diff(None, true, usize::MAX)?
+        get_diff(None, true, usize::MAX, diff, &[])?
     } else {
-        get_diff(target.as_deref(), false, usize::MAX)?
+        get_diff(target.as_deref(), false, usize::MAX, diff, &[])?
     };
 
     if raw_diff.trim().is_empty() {
@@ -29,6 +35,32 @@ pub fn cmd_diff(
     } else {
         None
     };
+    // Only the algorithms use per-file counts
+    let numstat = if alg.is_some() || compare || algo_bench {
+        get_numstat(target.as_deref(), staged, &[]).ok()
+    } else {
+        None
+    };
+    let (stat, numstat) = (diff_stats.as_deref(), numstat.as_deref());
+    let generated = if alg.is_some() || compare || algo_bench {
+        linguist_generated(&selectable_paths(&raw_diff))
+    } else {
+        HashSet::new()
+    };
+
+    if algo_bench {
+        let runs: Vec<(DiffStats, Duration)> = (1..=4u8)
+            .map(|alg_num| {
+                let start = Instant::now();
+                let algorithm = DiffAlg::from_num(alg_num);
+                let (_, stats) =
+                    get_llm_diff_preview(&raw_diff, stat, numstat, &generated, algorithm, diff, true);
+                (stats, start.elapsed())
+            })
+            .collect();
+        println!("{}", bench_table(&runs));
+        return Ok(());
+    }
 
     if compare {
         println!("================================================================");
@@ -38,9 +70,9 @@ pub fn cmd_diff(
         for alg_num in 1..=4u8 {
             let algorithm = DiffAlg::from_num(alg_num);
             let (output, stats) =
-                get_llm_diff_preview(&raw_diff, diff_stats.as_deref(), max_chars, algorithm, true);
+                get_llm_diff_preview(&raw_diff, stat, numstat, &generated, algorithm, diff, true);
 
-            println!("{}", stats.display());
+            println!("{}", color::cyan(&stats.display()));
 
             if !stats_only {
                 println!("{}", output);
@@ -57,11 +89,12 @@ pub fn cmd_diff(
 
     // If --alg is specified, use that algorithm and show stats
     if let Some(alg_num) = alg {
-        let algorithm = DiffAlg::from_num(alg_num);
-        let (output, stats) =
-            get_llm_diff_preview(&raw_diff, diff_stats.as_deref(), max_chars, algorithm, false);
+        let (algorithm, escalated_from) = pick_alg(alg_num, &raw_diff, diff);
+        let (output, mut stats) =
+            get_llm_diff_preview(&raw_diff, stat, numstat, &generated, algorithm, diff, false);
+        stats.escalated_from = escalated_from;
 
-        println!("{}\n", stats.display());
+        println!("{}\n", color::cyan(&stats.display()));
 
         if !stats_only {
             println!("{}", output);
@@ -86,4 +119,70 @@ pub fn cmd_diff(
     }
 
     Ok(())
+}
+
+/// `--algo-bench` table: one row per algorithm run
+fn bench_table(runs: &[(DiffStats, Duration)]) -> String {
+    let mut out = format!(
+        "{:<18} {:>10} {:>10} {:>10} {:>10}\n",
+        "Algorithm", "Chars", "~Tokens", "Reduction", "Time"
+    );
+    for (stats, elapsed) in runs {
+        out.push_str(&format!(
+            "{:<18} {:>10} {:>10} {:>9.1}% {:>8.2}ms\n",
+            format!("{} {}", stats.algorithm.num(), stats.algorithm.name()),
+            stats.output_chars,
+            stats.estimated_tokens,
+            stats.reduction_pct(),
+            elapsed.as_secs_f64() * 1000.0
+        ));
+    }
+    if let Some((first, _)) = runs.first() {
+        out.push_str(&format!("\nRaw diff: {} chars, {} files", first.total_chars, first.total_files));
+    }
+    out
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(alg: u8, output_chars: usize) -> DiffStats {
+        DiffStats {
+            total_files: 3,
+            included_files: 3,
+            excluded_files: 0,
+            total_chars: 1000,
+            output_chars,
+            estimated_tokens: output_chars * 2 / 7,
+            truncated: false,
+            algorithm: DiffAlg::from_num(alg),
+            escalated_from: None,
+        }
+    }
+
+    #[test]
+    fn bench_table_has_row_per_algorithm() {
+        let runs = vec![
+            (stats(1, 1000), Duration::from_micros(150)),
+            (stats(4, 250), Duration::from_millis(2)),
+        ];
+        let table = bench_table(&runs);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert!(lines[0].starts_with("Algorithm"));
+        assert!(lines[1].starts_with("1 Full"));
+        assert!(lines[1].contains("1000") && lines[1].contains("0.0%") && lines[1].ends_with("0.15ms"));
+        assert!(lines[2].contains("250") && lines[2].contains("71") && lines[2].contains("75.0%"));
+        assert!(lines[2].ends_with("2.00ms"));
+        assert_eq!(lines.last(), Some(&"Raw diff: 1000 chars, 3 files"));
+    }
+
+    #[test]
+    fn bench_table_empty_runs() {
+        assert_eq!(bench_table(&[]).lines().count(), 1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+// src/commands/doctor.rs
+//
+// `gitar doctor`: check the usual setup problems (git, repo, config file, API key,
+// base URL) and say how to fix each. Works without a valid key.
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use crate::client::LlmClient;
+use crate::config::{Config, ResolvedConfig};
+use crate::git::{is_git_installed, is_git_repo};
+
+use super::config::{print_checks, validation_checks, Check};
+
+pub(crate) fn check_git(installed: bool) -> Check {
+    match installed {
+        true => Check::new("Git", true, "installed"),
+        false => {
+            Check::new("Git", false, "not found in PATH").with_hint("Install git and make sure it is on your PATH")
+        }
+    }
+}
+
+pub(crate) fn check_repo(in_repo: bool) -> Check {
+    match in_repo {
+        true => Check::new("Repository", true, "inside a git repository"),
+        false => Check::new("Repository", false, "not a git repository").with_hint(
+            "Run gitar from inside a repository (or `git init` one); only `explain --files` works outside",
+        ),
+    }
+}
+
+/// `contents` is None when the file does not exist.
+pub(crate) fn check_config_file(path: Option<&Path>, contents: Option<&str>) -> Check {
+    let Some(path) = path else {
+        return Check::new("Config", false, "no location (HOME is not set)")
+            .with_hint("Set GITAR_CONFIG to a file path");
+    };
+    match contents {
+        None => Check::new("Config", true, format!("{} not created yet (defaults in use)", path.display())),
+        Some(text) => match toml::from_str::<Config>(text) {
+            Ok(_) => Check::new("Config", true, path.display().to_string()),
+            Err(e) => Check::new("Config", false, format!("{} is invalid: {}", path.display(), e.message()))
+                .with_hint("Fix the TOML; gitar ignores the whole file until it parses"),
+        },
+    }
+}
+
+/// `resolved` is the config as other commands would see it; resolving can fail
+/// (e.g. an empty api_key_file), which is reported rather than returned.
+pub async fn cmd_doctor(resolved: Result<ResolvedConfig>, offline: bool) -> Result<()> {
+    let mut report = Vec::new();
+
+    let git = is_git_installed();
+    report.push(check_git(git));
+    if git {
+        report.push(check_repo(is_git_repo()));
+    }
+
+    let path = Config::path();
+    let contents = path.as_deref().and_then(|p| std::fs::read_to_string(p).ok());
+    report.push(check_config_file(path.as_deref(), contents.as_deref()));
+
+    match resolved {
+        Ok(config) => {
+            let ping = match LlmClient::new(&config) {
+                Ok(client) => client.with_offline(offline).ping().await,
+                Err(e) => Err(e),
+            };
+            // Key and base URL as `config validate` checks them; the model list needs a working key
+            report.extend(validation_checks(&config, Some(&ping), None));
+        }
+        Err(e) => report.push(Check::new("Settings", false, e.to_string()).with_hint("Fix the value named above")),
+    }
+
+    print_checks(&report);
+
+    let failed = report.iter().filter(|c| !c.ok).count();
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    println!("\nAll good.");
+    Ok(())
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn git_and_repo_checks() {
+        assert!(check_git(true).ok);
+        let missing = check_git(false);
+        assert!(!missing.ok && missing.hint.unwrap().contains("PATH"));
+        assert!(check_repo(true).ok);
+        assert!(check_repo(false).hint.unwrap().contains("git init"));
+    }
+
+    #[test]
+    fn config_file_missing_valid_and_broken() {
+        let path = PathBuf::from("/home/ana/.gitar.toml");
+        let missing = check_config_file(Some(&path), None);
+        assert!(missing.ok && missing.detail.contains("not created yet"));
+        assert!(check_config_file(Some(&path), Some("default_provider = \"groq\"\n")).ok);
+
+        let broken = check_config_file(Some(&path), Some("default_provider = groq\n"));
+        assert!(!broken.ok);
+        assert!(broken.detail.starts_with("/home/ana/.gitar.toml is invalid"));
+        assert!(!check_config_file(None, None).ok);
+    }
+}
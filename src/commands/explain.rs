@@ -1,12 +1,67 @@
 // src/commands/explain.rs
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use std::future::Future;
+use std::path::Path;
 
 use crate::client::LlmClient;
-use crate::git::{build_diff_target, get_commit_logs, get_diff, get_diff_stats};
-use crate::prompt::{EXPLAIN_SYSTEM_PROMPT, EXPLAIN_USER_PROMPT};
+use crate::git::{
+    build_diff_target, get_commit_logs, get_diff, get_diff_stats, get_no_index_diff,
+    get_no_index_stats, get_numstat, LogQuery,
+};
+use crate::prompt::{add_additional_context, commit_prompt, explain_prompt, explain_risk_prompt, Prompt};
 
-use super::apply_smart_diff;
+use super::commit::truncate_subject;
+use super::{apply_smart_diff, load_context_file, stats_unless, CommandOptions};
 
+/// `explain --format`: the model writes markdown; text and html are converted from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+impl ExplainFormat {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "text" => Self::Text,
+            "html" => Self::Html,
+            _ => Self::Markdown,
+        }
+    }
+
+    fn render(self, output: &str) -> String {
+        match self {
+            Self::Text => markdown_to_text(output),
+            Self::Markdown => output.to_string(),
+            Self::Html => markdown_to_html(output),
+        }
+    }
+
+    /// `--commit-ready`: the explanation, then the message. HTML stays embeddable, so the
+    /// message goes in a `<pre>` under its own heading instead of between text bars.
+    fn render_commit_ready(self, explanation: &str, message: &str) -> String {
+        if self == Self::Html {
+            return format!(
+                "{}\n<h2>Commit Message</h2>\n<pre>{}</pre>",
+                markdown_to_html(explanation),
+                html_escape(message)
+            );
+        }
+        let bar = "=".repeat(50);
+        format!(
+            "{}\nEXPLANATION\n{}\n{}\n\n{}\nCOMMIT MESSAGE\n{}\n{}",
+            bar,
+            bar,
+            self.render(explanation),
+            bar,
+            bar,
+            message
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_explain(
     client: &LlmClient,
     from: Option<String>,
@@ -15,10 +70,217 @@ pub async fn cmd_explain(
     until: Option<String>,
     base_branch: &str,
     staged: bool,
+    audience: Option<String>,
+    commit_ready: bool,
+    diff_only: bool,
+    risk_only: bool,
+    format: ExplainFormat,
+    context_file: Option<String>,
     stream: bool,
     alg: u8,
-    max_diff_chars: usize,
+    opts: &CommandOptions,
+) -> Result<Option<RiskLevel>> {
+    let extra = load_context_file(context_file.as_deref())?;
+    let (mut prompt, diff) = match prepare_explain(
+        from,
+        to,
+        since,
+        until,
+        base_branch,
+        staged,
+        audience,
+        diff_only,
+        risk_only,
+        alg,
+        opts,
+    )? {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+    if let Some(extra) = &extra {
+        add_additional_context(&mut prompt, extra);
+    }
+
+    if commit_ready {
+        let (explanation, message) = explain_and_commit(
+            |system, user| async move { client.chat(&system, &user, false).await },
+            &prompt,
+            &commit_prompt(&diff, None, opts.emoji, opts.subject_max_len),
+        )
+        .await?;
+
+        let message = truncate_subject(&message, opts.subject_max_len);
+        println!("{}", format.render_commit_ready(&explanation, &message));
+        return Ok(None);
+    }
+
+    if risk_only {
+        return print_risk(client, &prompt).await.map(Some);
+    }
+    print_explanation(client, &prompt, format, stream).await.map(|_| None)
+}
+
+/// Stream only the unconverted markdown; other formats need the whole response.
+async fn print_explanation(
+    client: &LlmClient,
+    prompt: &Prompt,
+    format: ExplainFormat,
+    stream: bool,
 ) -> Result<()> {
+    let stream = stream && format == ExplainFormat::Markdown;
+    let r = client.chat(&prompt.system, &prompt.user, stream).await?;
+    if stream {
+        println!();
+    } else {
+        println!("{}", format.render(&r));
+    }
+    Ok(())
+}
+
+/// `explain --risk-only` levels; the exit code tells scripts which one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    /// The level named first in the reply (`Risk Level: Medium - ...`), as a whole word:
+    /// "slower" or "workflow" don't count as Low.
+    pub(crate) fn parse(reply: &str) -> Option<Self> {
+        reply
+            .split(|c: char| !c.is_alphanumeric())
+            .find_map(|word| match word.to_lowercase().as_str() {
+                "low" => Some(Self::Low),
+                "medium" => Some(Self::Medium),
+                "high" => Some(Self::High),
+                _ => None,
+            })
+    }
+
+    /// 0 for Low, so `gitar explain --risk-only && deploy` only goes ahead on low risk.
+    /// 1 (errors) and 2 (usage errors) are taken.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Low => 0,
+            Self::Medium => 3,
+            Self::High => 4,
+        }
+    }
+}
+
+/// Print the one-line answer; the caller exits with the level's code.
+async fn print_risk(client: &LlmClient, prompt: &Prompt) -> Result<RiskLevel> {
+    let r = client.chat(&prompt.system, &prompt.user, false).await?;
+    let line = r.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+    println!("{}", line);
+    RiskLevel::parse(line).context("No risk level (Low/Medium/High) in the reply")
+}
+
+/// The full explanation, or with `risk_only` the one-line risk prompt.
+fn build_explain_prompt(risk_only: bool, audience: Option<&str>, range: &str, stats: &str, diff: &str) -> Prompt {
+    if risk_only {
+        explain_risk_prompt(range, stats, diff)
+    } else {
+        explain_prompt(audience, range, stats, diff)
+    }
+}
+
+/// `explain --files OLD NEW`: explain the difference between two files (no repo needed).
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_explain_files(
+    client: &LlmClient,
+    old: &str,
+    new: &str,
+    audience: Option<String>,
+    diff_only: bool,
+    risk_only: bool,
+    format: ExplainFormat,
+    context_file: Option<String>,
+    stream: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<RiskLevel>> {
+    let extra = load_context_file(context_file.as_deref())?;
+    let prepared = prepare_explain_files(old, new, audience, diff_only, risk_only, alg, opts)?;
+    let mut prompt = match prepared {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+    if let Some(extra) = &extra {
+        add_additional_context(&mut prompt, extra);
+    }
+
+    if risk_only {
+        return print_risk(client, &prompt).await.map(Some);
+    }
+    print_explanation(client, &prompt, format, stream).await.map(|_| None)
+}
+
+/// Build the `explain --files` prompt from `git diff --no-index` (None if the files match).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_explain_files(
+    old: &str,
+    new: &str,
+    audience: Option<String>,
+    diff_only: bool,
+    risk_only: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<Prompt>> {
+    for path in [old, new] {
+        if !Path::new(path).exists() {
+            bail!("File not found: {}", path);
+        }
+    }
+
+    let display = format!("{} -> {}", old, new);
+    if !opts.quiet {
+        println!("Explaining changes for {}...\n", display);
+    }
+
+    let raw_diff = get_no_index_diff(old, new, opts.diff.context)?;
+    if raw_diff.trim().is_empty() {
+        println!("No changes detected.");
+        return Ok(None);
+    }
+
+    let diff = apply_smart_diff(&raw_diff, None, false, alg, opts)?;
+    let stats = stats_unless(diff_only, || get_no_index_stats(old, new))?;
+    Ok(Some(build_explain_prompt(risk_only, audience.as_deref(), &display, &stats, &diff)))
+}
+
+/// Run the explanation and the commit message for the same diff, in that order.
+pub(crate) async fn explain_and_commit<F, Fut>(
+    chat: F,
+    explain: &Prompt,
+    commit: &Prompt,
+) -> Result<(String, String)>
+where
+    F: Fn(String, String) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let explanation = chat(explain.system.clone(), explain.user.clone()).await?;
+    let message = chat(commit.system.clone(), commit.user.clone()).await?;
+    Ok((explanation, message))
+}
+
+/// Build the `explain` prompt and the shaped diff it embeds (None if there are no changes).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_explain(
+    from: Option<String>,
+    to: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    base_branch: &str,
+    staged: bool,
+    audience: Option<String>,
+    diff_only: bool,
+    risk_only: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<(Prompt, String)>> {
     let display = match (&from, &to, &since, &until) {
         (Some(r), Some(t), _, _) => format!("{}..{}", r, t),
         (Some(r), None, _, _) => format!("{}..HEAD", r),
@@ -32,15 +294,19 @@ pub async fn cmd_explain(
     let mut commit_count: Option<usize> = None;
 
     let (diff, stats) = if staged {
-        println!("Explaining staged changes...\n");
-        let raw_diff = get_diff(None, true, usize::MAX)?;
-        let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
-        (diff, get_diff_stats(None, true)?)
+        if !opts.quiet {
+            println!("Explaining staged changes...\n");
+        }
+        let raw_diff = get_diff(None, true, usize::MAX, &opts.diff, &[])?;
+        let numstat = get_numstat(None, true, &[]).ok();
+        let diff = apply_smart_diff(&raw_diff, numstat.as_deref(), false, alg, opts)?;
+        (diff, stats_unless(diff_only, || get_diff_stats(None, true))?)
     } else {
         let effective_from = match (&from, &since, &until) {
             (Some(_), _, _) => from.clone(),
             (None, Some(_), _) | (None, None, Some(_)) => {
-                let commits = get_commit_logs(None, since.as_deref(), until.as_deref(), None)?;
+                let (since, until) = (since.as_deref(), until.as_deref());
+                let commits = get_commit_logs(&LogQuery { since, until, ..Default::default() })?;
                 commit_count = Some(commits.len());
                 commits.last().map(|c| c.hash.clone())
             }
@@ -48,6 +314,7 @@ pub async fn cmd_explain(
         };
 
         match commit_count {
+            _ if opts.quiet => {}
             Some(n) => println!("Explaining changes for {} ({} commits)...\n", display, n),
             None => println!("Explaining changes for {}...\n", display),
         }
@@ -59,26 +326,265 @@ pub async fn cmd_explain(
             Some(diff_target.as_str())
         };
 
-        let raw_diff = get_diff(diff_target_ref, false, usize::MAX)?;
-        let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
-        (diff, get_diff_stats(diff_target_ref, false)?)
+        let raw_diff = get_diff(diff_target_ref, false, usize::MAX, &opts.diff, &[])?;
+        let numstat = get_numstat(diff_target_ref, false, &[]).ok();
+        let diff = apply_smart_diff(&raw_diff, numstat.as_deref(), false, alg, opts)?;
+        (
+            diff,
+            stats_unless(diff_only, || get_diff_stats(diff_target_ref, false))?,
+        )
     };
 
     if diff.trim().is_empty() {
         println!("No changes detected.");
-        return Ok(());
+        return Ok(None);
     }
 
-    let prompt = EXPLAIN_USER_PROMPT
-        .replace("{range}", if staged { "staged" } else { &display })
-        .replace("{stats}", &stats)
-        .replace("{diff}", &diff);
+    let range = if staged { "staged" } else { &display };
+    Ok(Some((build_explain_prompt(risk_only, audience.as_deref(), range, &stats, &diff), diff)))
+}
 
-    let r = client.chat(EXPLAIN_SYSTEM_PROMPT, &prompt, stream).await?;
-    if stream {
-        println!();
-    } else {
-        println!("{}", r);
+// =============================================================================
+// OUTPUT FORMATS
+// =============================================================================
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text.trim()))
+}
+
+fn bullet(line: &str) -> Option<&str> {
+    line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))
+}
+
+/// `1. item` -> `item`
+fn numbered(line: &str) -> Option<&str> {
+    let (n, rest) = line.split_once(". ")?;
+    (!n.is_empty() && n.chars().all(|c| c.is_ascii_digit())).then_some(rest)
+}
+
+/// Plain text: heading markers and `**` dropped, bullets kept.
+pub(crate) fn markdown_to_text(md: &str) -> String {
+    md.lines()
+        .map(|l| {
+            let l = heading(l.trim_start()).map(|(_, t)| t).unwrap_or(l);
+            l.replace("**", "")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Escape, then `**bold**` and `` `code` `` (unpaired markers stay as typed).
+fn html_inline(text: &str) -> String {
+    let mut out = html_escape(text);
+    for (marker, tag) in [("**", "strong"), ("`", "code")] {
+        let parts: Vec<&str> = out.split(marker).collect();
+        if parts.len() < 3 {
+            continue;
+        }
+        let mut joined = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                let closing = i % 2 == 0;
+                let unpaired = !closing && i == parts.len() - 1;
+                joined.push_str(&match (unpaired, closing) {
+                    (true, _) => marker.to_string(),
+                    (false, false) => format!("<{}>", tag),
+                    (false, true) => format!("</{}>", tag),
+                });
+            }
+            joined.push_str(part);
+        }
+        out = joined;
     }
-    Ok(())
-}
\ No newline at end of file
+    out
+}
+
+/// Headings, bullet and numbered lists, and paragraphs to basic HTML.
+pub(crate) fn markdown_to_html(md: &str) -> String {
+    let mut out = Vec::new();
+    let mut list: Option<&str> = None;
+    let mut paragraph: Vec<String> = Vec::new();
+
+    fn flush(out: &mut Vec<String>, list: &mut Option<&str>, paragraph: &mut Vec<String>) {
+        if let Some(tag) = list.take() {
+            out.push(format!("</{}>", tag));
+        }
+        if !paragraph.is_empty() {
+            out.push(format!("<p>{}</p>", paragraph.join("\n")));
+            paragraph.clear();
+        }
+    }
+
+    for line in md.lines() {
+        let line = line.trim();
+        let list_item = bullet(line).map(|t| ("ul", t)).or_else(|| numbered(line).map(|t| ("ol", t)));
+        if let Some((level, text)) = heading(line) {
+            flush(&mut out, &mut list, &mut paragraph);
+            out.push(format!("<h{0}>{1}</h{0}>", level, html_inline(text)));
+        } else if let Some((tag, item)) = list_item {
+            if list != Some(tag) {
+                flush(&mut out, &mut list, &mut paragraph);
+                out.push(format!("<{}>", tag));
+                list = Some(tag);
+            }
+            out.push(format!("<li>{}</li>", html_inline(item)));
+        } else if line.is_empty() {
+            flush(&mut out, &mut list, &mut paragraph);
+        } else {
+            if list.is_some() {
+                flush(&mut out, &mut list, &mut paragraph);
+            }
+            paragraph.push(html_inline(line));
+        }
+    }
+    flush(&mut out, &mut list, &mut paragraph);
+    out.join("\n")
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{COMMIT_SYSTEM_PROMPT, EXPLAIN_RISK_SYSTEM_PROMPT, EXPLAIN_USER_PROMPT};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn explain_and_commit_makes_two_calls() {
+        let diff = "+fn answer() -> u32 { 42 }";
+        let explain = explain_prompt(None, "staged", "1 file changed", diff);
+        let commit = commit_prompt(diff, None, false, 0);
+        let calls = Mutex::new(Vec::new());
+
+        let (explanation, message) = explain_and_commit(
+            |system, user| {
+                calls.lock().unwrap().push((system.clone(), user));
+                async move {
+                    if system == COMMIT_SYSTEM_PROMPT {
+                        Ok("Add answer helper".to_string())
+                    } else {
+                        Ok("Adds a helper that returns 42.".to_string())
+                    }
+                }
+            },
+            &explain,
+            &commit,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(explanation, "Adds a helper that returns 42.");
+        assert_eq!(message, "Add answer helper");
+
+        let calls = calls.into_inner().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].1.starts_with(&EXPLAIN_USER_PROMPT[..12]));
+        assert!(calls[0].1.contains(diff) && calls[1].1.contains(diff));
+        assert_eq!(calls[1].0, COMMIT_SYSTEM_PROMPT);
+    }
+
+    #[tokio::test]
+    async fn explain_and_commit_stops_on_first_error() {
+        let p = commit_prompt("x", None, false, 0);
+        let calls = Mutex::new(0);
+        let result = explain_and_commit(
+            |_, _| {
+                *calls.lock().unwrap() += 1;
+                async { Err(anyhow::anyhow!("boom")) }
+            },
+            &p,
+            &p,
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn risk_only_selects_trimmed_prompt() {
+        let full = build_explain_prompt(false, Some("qa"), "staged", "S", "+x");
+        assert!(full.system.contains("## User Impact"));
+        assert!(full.system.contains("QA engineers"));
+
+        let risk = build_explain_prompt(true, Some("qa"), "staged", "S", "+x");
+        assert_eq!(risk.system, EXPLAIN_RISK_SYSTEM_PROMPT);
+        assert!(!risk.system.contains("## User Impact"));
+        assert!(risk.user.starts_with("Assess the risk of staged."));
+        assert!(risk.user.contains("+x"));
+        assert!(risk.system.len() < full.system.len());
+    }
+
+    #[test]
+    fn risk_level_from_reply() {
+        assert_eq!(RiskLevel::parse("Risk Level: Medium - touches auth"), Some(RiskLevel::Medium));
+        assert_eq!(RiskLevel::parse("HIGH: drops a table"), Some(RiskLevel::High));
+        // The level comes first; later words don't count
+        assert_eq!(RiskLevel::parse("Low - docs only, high confidence"), Some(RiskLevel::Low));
+        assert_eq!(RiskLevel::parse("Unclear"), None);
+        // Whole words only: "slower" and "workflow" contain "low"
+        assert_eq!(RiskLevel::parse("Slower rollout of the cache - High"), Some(RiskLevel::High));
+        assert_eq!(RiskLevel::parse("Workflow change: Medium"), Some(RiskLevel::Medium));
+        assert_eq!(RiskLevel::parse("Risk Level: below threshold"), None);
+        assert_eq!(RiskLevel::Low.exit_code(), 0);
+        assert_eq!(RiskLevel::Medium.exit_code(), 3);
+        assert_eq!(RiskLevel::High.exit_code(), 4);
+    }
+
+    #[test]
+    fn diff_only_prompt_has_empty_stats_section() {
+        let p = explain_prompt(None, "staged", "", "+x");
+        assert!(p.user.contains("**Stats:**\n\n\n**Diff:**"));
+    }
+
+    #[test]
+    fn commit_ready_html_has_no_text_bars() {
+        let html = ExplainFormat::Html.render_commit_ready("## What's Changing\nRetries.", "fix: retry <login>");
+        assert_eq!(
+            html,
+            "<h2>What's Changing</h2>\n<p>Retries.</p>\n\
+             <h2>Commit Message</h2>\n<pre>fix: retry &lt;login&gt;</pre>"
+        );
+        let text = ExplainFormat::Markdown.render_commit_ready("Retries.", "fix: retry");
+        assert!(text.contains("EXPLANATION\n"));
+        assert!(text.ends_with("COMMIT MESSAGE\n==================================================\nfix: retry"));
+    }
+
+    #[test]
+    fn html_headings_and_bullets() {
+        let md = "## What's Changing\nLogin now retries.\n\n## User Impact\n- Fewer errors\n- Faster <login>\n\n## Risk Level\nLow";
+        assert_eq!(
+            markdown_to_html(md),
+            "<h2>What's Changing</h2>\n<p>Login now retries.</p>\n<h2>User Impact</h2>\n<ul>\n\
+             <li>Fewer errors</li>\n<li>Faster &lt;login&gt;</li>\n</ul>\n<h2>Risk Level</h2>\n<p>Low</p>"
+        );
+    }
+
+    #[test]
+    fn html_numbered_lists_and_inline_markup() {
+        let md = "# Title\n1. **Run** `cargo test`\n2. Check 2 ** 3\n* after";
+        assert_eq!(
+            markdown_to_html(md),
+            "<h1>Title</h1>\n<ol>\n<li><strong>Run</strong> <code>cargo test</code></li>\n\
+             <li>Check 2 ** 3</li>\n</ol>\n<ul>\n<li>after</li>\n</ul>"
+        );
+    }
+
+    #[test]
+    fn html_text_after_list_closes_it() {
+        assert_eq!(markdown_to_html("- a\nthen\n#nope"), "<ul>\n<li>a</li>\n</ul>\n<p>then\n#nope</p>");
+    }
+
+    #[test]
+    fn text_format_drops_markdown_markers() {
+        let md = "## User Impact\n- **Fewer** errors\n#hashtag";
+        assert_eq!(markdown_to_text(md), "User Impact\n- Fewer errors\n#hashtag");
+        assert_eq!(ExplainFormat::from_name("html"), ExplainFormat::Html);
+        assert_eq!(ExplainFormat::Markdown.render(md), md);
+    }
+}
@@ -1,12 +1,16 @@
 // src/commands/history.rs
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::future::Future;
+use std::time::Duration;
 
 use crate::client::LlmClient;
-use crate::git::{get_commit_diff, get_commit_logs};
-use crate::prompt::{HISTORY_SYSTEM_PROMPT, HISTORY_USER_PROMPT};
+use crate::git::{get_commit_diff, get_commit_logs, CommitInfo, LogQuery};
+use crate::prompt::{history_prompt, Prompt};
 
-use super::apply_smart_diff;
+use super::commit::strip_ai_tag;
+use super::{apply_smart_diff, no_request_line, CommandOptions};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_history(
     client: &LlmClient,
     from: Option<String>,
@@ -15,9 +19,17 @@ pub async fn cmd_history(
     until: Option<String>,
     limit: Option<usize>,
     delay: u64,
+    with_body: bool,
+    no_merges: bool,
+    first_parent: bool,
+    strip_tags: bool,
+    authors: Vec<String>,
+    count_only: bool,
+    timeout_per_commit: Option<u64>,
+    max_failures: Option<usize>,
     stream: bool,
     alg: u8,
-    max_diff_chars: usize,
+    opts: &CommandOptions,
 ) -> Result<()> {
     let limit = match (&from, limit) {
         (Some(_), None) => None,
@@ -35,15 +47,34 @@ pub async fn cmd_history(
         _ => "recent".into(),
     };
 
-    println!("Fetching commits ({})...", display);
-    let commits = get_commit_logs(limit, since.as_deref(), until.as_deref(), range.as_deref())?;
+    if !opts.quiet {
+        println!("Fetching commits ({})...", display);
+    }
+    let mut commits = get_commit_logs(&LogQuery {
+        limit,
+        since: since.as_deref(),
+        until: until.as_deref(),
+        range: range.as_deref(),
+        with_body,
+        no_merges,
+        first_parent,
+        authors: &authors,
+    })?;
+    if strip_tags {
+        strip_ai_tag(&mut commits);
+    }
 
-    if commits.is_empty() {
-        println!("No commits found.");
+    if let Some(line) = no_request_line(&commits, count_only) {
+        println!("{}", line);
         return Ok(());
     }
 
-    println!("Processing {} commits...\n", commits.len());
+    if let Some(line) = processing_line(commits.len(), opts.quiet) {
+        println!("{}\n", line);
+    }
+
+    let mut streak = FailureStreak::new(max_failures);
+    let timeout = timeout_per_commit.map(Duration::from_secs);
 
     for (i, c) in commits.iter().enumerate() {
         let h = &c.hash[..8.min(c.hash.len())];
@@ -69,21 +100,23 @@ pub async fn cmd_history(
             m
         );
 
-        let raw_diff = match get_commit_diff(&c.hash, usize::MAX)? {
-            Some(d) if !d.trim().is_empty() => d,
-            _ => {
+        let prompt = match prepare_history(c, alg, opts)? {
+            Some(p) => p,
+            None => {
                 println!("  - No diff");
                 continue;
             }
         };
 
-        let diff = apply_smart_diff(&raw_diff, max_diff_chars, true, alg)?;
-
-        let prompt = HISTORY_USER_PROMPT
-            .replace("{original_message}", &c.message)
-            .replace("{diff}", &diff);
+        let reply = with_timeout(timeout, client.chat(&prompt.system, &prompt.user, stream)).await;
+        if streak.record(reply.is_ok()) {
+            if let Err(e) = &reply {
+                println!("  x {}", e);
+            }
+            bail!("Aborting after {} consecutive failures.", streak.count);
+        }
 
-        match client.chat(HISTORY_SYSTEM_PROMPT, &prompt, stream).await {
+        match reply {
             Ok(r) => {
                 if stream {
                     println!();
@@ -104,4 +137,106 @@ pub async fn cmd_history(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The header before the per-commit lines, unless `--quiet`.
+fn processing_line(count: usize, quiet: bool) -> Option<String> {
+    (!quiet).then(|| format!("Processing {} commits...", count))
+}
+
+/// `--timeout-per-commit`: give up on one request after `limit` instead of the client timeout.
+async fn with_timeout(limit: Option<Duration>, chat: impl Future<Output = Result<String>>) -> Result<String> {
+    let Some(limit) = limit else {
+        return chat.await;
+    };
+    match tokio::time::timeout(limit, chat).await {
+        Ok(reply) => reply,
+        Err(_) => bail!("Timed out after {:?}", limit),
+    }
+}
+
+/// Consecutive failed commits, for `--max-failures`.
+struct FailureStreak {
+    limit: Option<usize>,
+    count: usize,
+}
+
+impl FailureStreak {
+    fn new(limit: Option<usize>) -> Self {
+        Self { limit, count: 0 }
+    }
+
+    /// Count one result; true once the run should stop. A success resets the streak.
+    fn record(&mut self, ok: bool) -> bool {
+        if ok {
+            self.count = 0;
+            return false;
+        }
+        self.count += 1;
+        self.limit.is_some_and(|k| k > 0 && self.count >= k)
+    }
+}
+
+/// Build the `history` prompt for a single commit (None if it has no diff).
+pub(crate) fn prepare_history(c: &CommitInfo, alg: u8, opts: &CommandOptions) -> Result<Option<Prompt>> {
+    let raw_diff = match get_commit_diff(&c.hash, usize::MAX, &opts.diff)? {
+        Some(d) if !d.trim().is_empty() => d,
+        _ => return Ok(None),
+    };
+
+    let diff = apply_smart_diff(&raw_diff, None, true, alg, opts)?;
+    Ok(Some(history_prompt(&c.full_message(), &diff)))
+}
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_streak_aborts_after_k_in_a_row() {
+        let mut streak = FailureStreak::new(Some(3));
+        assert!(!streak.record(false));
+        assert!(!streak.record(false));
+        assert!(streak.record(false));
+        assert_eq!(streak.count, 3);
+    }
+
+    #[test]
+    fn failure_streak_resets_on_success() {
+        let mut streak = FailureStreak::new(Some(2));
+        assert!(!streak.record(false));
+        assert!(!streak.record(true));
+        assert_eq!(streak.count, 0);
+        assert!(!streak.record(false));
+        assert!(streak.record(false));
+    }
+
+    #[test]
+    fn failure_streak_without_limit_never_aborts() {
+        for limit in [None, Some(0)] {
+            let mut streak = FailureStreak::new(limit);
+            assert!((0..100).all(|_| !streak.record(false)));
+        }
+    }
+
+    #[test]
+    fn processing_line_suppressed_when_quiet() {
+        assert_eq!(processing_line(3, false).as_deref(), Some("Processing 3 commits..."));
+        assert_eq!(processing_line(3, true), None);
+    }
+
+    #[tokio::test]
+    async fn with_timeout_cuts_off_slow_requests() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok("late".to_string())
+        };
+        let err = with_timeout(Some(Duration::from_millis(20)), slow).await.unwrap_err();
+        assert_eq!(err.to_string(), "Timed out after 20ms");
+
+        let fast = async { Ok("ok".to_string()) };
+        assert_eq!(with_timeout(None, fast).await.unwrap(), "ok");
+    }
+}
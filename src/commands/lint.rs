@@ -0,0 +1,159 @@
+// src/commands/lint.rs
+use anyhow::{bail, Result};
+
+use crate::git::{get_commit_logs, LogQuery};
+
+/// Commit types accepted by the gitar format (see HISTORY_SYSTEM_PROMPT)
+pub const COMMIT_TYPES: &[&str] = &[
+    "Feat", "Fix", "Refactor", "Docs", "Style", "Test", "Chore", "Perf",
+];
+
+/// Default maximum subject length
+pub const DEFAULT_MAX_SUBJECT_LEN: usize = 72;
+
+/// Check commit subjects against the Type(scope) format without calling the model.
+pub fn cmd_lint(
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<usize>,
+    max_len: usize,
+) -> Result<()> {
+    let limit = match (&from, limit) {
+        (Some(_), None) => None,
+        (None, None) => Some(50),
+        (_, Some(n)) => Some(n),
+    };
+    let end = to.as_deref().unwrap_or("HEAD");
+    let range = from.as_ref().map(|r| format!("{}..{}", r, end));
+
+    let commits = get_commit_logs(&LogQuery {
+        limit,
+        range: range.as_deref(),
+        no_merges: true,
+        ..Default::default()
+    })?;
+    if commits.is_empty() {
+        println!("No commits found.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for c in &commits {
+        let issues = lint_subject(&c.message, max_len);
+        if issues.is_empty() {
+            continue;
+        }
+        failed += 1;
+        println!("{} {}", &c.hash[..8.min(c.hash.len())], c.message);
+        for issue in issues {
+            println!("  - {}", issue);
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} of {} commits do not follow the format", failed, commits.len());
+    }
+    println!("All {} commits follow the format.", commits.len());
+    Ok(())
+}
+
+/// Violations for one subject line; empty when it conforms.
+pub(crate) fn lint_subject(subject: &str, max_len: usize) -> Vec<String> {
+    let mut issues = Vec::new();
+    let subject = subject.trim();
+
+    let len = subject.chars().count();
+    if len > max_len {
+        issues.push(format!("subject is {} chars (max {})", len, max_len));
+    }
+
+    let head = match subject.split_once(':') {
+        Some((head, _)) => head,
+        None => {
+            issues.push("missing 'Type(scope):' prefix".into());
+            return issues;
+        }
+    };
+
+    let (ty, scope) = match head.split_once('(') {
+        Some((t, rest)) => (t, Some(rest)),
+        None => (head, None),
+    };
+
+    match COMMIT_TYPES.iter().find(|t| t.eq_ignore_ascii_case(ty)) {
+        None => issues.push(format!(
+            "unknown type '{}' (expected one of: {})",
+            ty,
+            COMMIT_TYPES.join(", ")
+        )),
+        Some(t) if *t != ty => issues.push(format!("type '{}' should be capitalized as '{}'", ty, t)),
+        Some(_) => {}
+    }
+
+    if let Some(rest) = scope {
+        match rest.strip_suffix(')') {
+            Some(s) if !s.trim().is_empty() && !s.contains(['(', ')']) => {}
+            _ => issues.push("malformed scope, expected 'Type(scope):'".into()),
+        }
+    }
+
+    issues
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::HISTORY_SYSTEM_PROMPT;
+
+    #[test]
+    fn commit_types_match_history_prompt() {
+        for t in COMMIT_TYPES {
+            assert!(HISTORY_SYSTEM_PROMPT.contains(&format!("- {}:", t)), "{} missing", t);
+        }
+    }
+
+    #[test]
+    fn conforming_subjects_pass() {
+        for s in [
+            "Feat(auth):",
+            "Fix(api): handle expired tokens",
+            "Docs: update README",
+            "Chore(deps): bump serde",
+        ] {
+            assert!(lint_subject(s, DEFAULT_MAX_SUBJECT_LEN).is_empty(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn missing_prefix_fails() {
+        let issues = lint_subject("Add login page", DEFAULT_MAX_SUBJECT_LEN);
+        assert_eq!(issues, ["missing 'Type(scope):' prefix"]);
+    }
+
+    #[test]
+    fn unknown_type_and_capitalization() {
+        let issues = lint_subject("Feature(ui): add button", DEFAULT_MAX_SUBJECT_LEN);
+        assert!(issues[0].starts_with("unknown type 'Feature'"));
+
+        let issues = lint_subject("fix(ui): align button", DEFAULT_MAX_SUBJECT_LEN);
+        assert_eq!(issues, ["type 'fix' should be capitalized as 'Fix'"]);
+    }
+
+    #[test]
+    fn malformed_scope_fails() {
+        assert_eq!(lint_subject("Fix(: x", 72).len(), 1);
+        assert_eq!(lint_subject("Fix(): x", 72).len(), 1);
+    }
+
+    #[test]
+    fn long_subject_fails() {
+        let s = format!("Fix(core): {}", "x".repeat(80));
+        let issues = lint_subject(&s, DEFAULT_MAX_SUBJECT_LEN);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].contains("max 72"));
+        assert!(lint_subject(&s, 100).is_empty());
+    }
+}
@@ -1,7 +1,9 @@
 // src/commands/mod.rs
 mod changelog;
 mod commit;
+mod completions;
 mod diff;
+mod doctor;
 mod explain;
 mod history;
 mod pr;
@@ -9,34 +11,249 @@ mod version;
 mod config;
 mod models;
 mod hook;
+mod lint;
+mod preview;
+mod rewrite;
+mod reword;
+mod split;
+mod ticket;
+mod wizard;
 
-pub use models::cmd_models;
-pub use changelog::cmd_changelog;
+pub use models::{cmd_models, cmd_models_cached};
+pub use changelog::{cmd_changelog, ChangelogFormat};
 pub use commit::{cmd_commit, cmd_staged, cmd_unstaged};
+pub use completions::cmd_completions;
 pub use diff::cmd_diff;
-pub use explain::cmd_explain;
+pub use doctor::cmd_doctor;
+pub use explain::{cmd_explain, cmd_explain_files, ExplainFormat, RiskLevel};
 pub use history::cmd_history;
-pub use pr::cmd_pr;
+pub use pr::{cmd_pr, DEFAULT_PR_MAX_COMMITS};
 pub use version::cmd_version;
-pub use config::{cmd_init, cmd_config};
+pub use config::{cmd_init, cmd_config, cmd_config_validate, init_has_flags};
+pub use wizard::cmd_init_wizard;
 pub use hook::cmd_hook;
+pub use lint::{cmd_lint, DEFAULT_MAX_SUBJECT_LEN};
+pub use preview::{cmd_prompt_preview, PREVIEW_COMMANDS};
+pub use rewrite::cmd_rewrite;
+pub use reword::cmd_reword;
+pub use ticket::{TicketPosition, DEFAULT_TICKET_PATTERN};
 
-use anyhow::Result;
-use crate::diff::{get_llm_diff_preview, DiffAlg};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use crate::color;
+use crate::diff::{get_llm_diff_preview, pick_alg, selectable_paths, DiffAlg, DiffOptions, DiffStats};
+use crate::git::{linguist_generated, CommitInfo};
 
-/// Shared helper: apply smart diff algorithm
+/// Flags and config shared by the commands, resolved once in main.
+#[derive(Debug, Clone, Default)]
+pub struct CommandOptions {
+    /// `--quiet`: drop progress lines and diff stats. Results and errors still print.
+    pub quiet: bool,
+    /// `--emoji` / `emoji = true`: gitmoji-style commit messages
+    pub emoji: bool,
+    /// `subject_max_len`: asked for in the commit prompt and enforced on the result. 0 = no limit.
+    pub subject_max_len: usize,
+    /// How diffs are fetched and shaped
+    pub diff: DiffOptions,
+}
+
+/// Shared helper: apply smart diff algorithm. `numstat` (`git diff --numstat` for the
+/// same changes) gives exact per-file line counts when the caller has it.
 pub(crate) fn apply_smart_diff(
     raw_diff: &str,
-    max_chars: usize,
+    numstat: Option<&str>,
     silent: bool,
     alg: u8,
+    opts: &CommandOptions,
 ) -> Result<String> {
-    let algorithm = DiffAlg::from_num(alg);
-    let (shaped_diff, stats) = get_llm_diff_preview(raw_diff, None, max_chars, algorithm, false);
+    let (algorithm, escalated_from) = pick_alg(alg, raw_diff, &opts.diff);
+    let generated = generated_files(raw_diff, algorithm);
+    let (shaped_diff, mut stats) =
+        get_llm_diff_preview(raw_diff, None, numstat, &generated, algorithm, &opts.diff, false);
+    stats.escalated_from = escalated_from;
 
-    if !silent {
-        eprintln!("{}", stats.display());
+    if let Some(box_text) = stats_box(&stats, silent, opts.quiet) {
+        eprintln!("{}", color::cyan(&box_text));
     }
 
     Ok(shaped_diff)
 }
+
+/// `linguist-generated` files in a gathered diff, looked up once for the algorithms that rank
+/// files. Full sends everything, so it skips the lookup.
+pub(crate) fn generated_files(raw_diff: &str, algorithm: DiffAlg) -> HashSet<String> {
+    match algorithm {
+        DiffAlg::Full => HashSet::new(),
+        _ => linguist_generated(&selectable_paths(raw_diff)),
+    }
+}
+
+/// The stats box printed before a request, unless silenced by the caller or `--quiet`.
+fn stats_box(stats: &DiffStats, silent: bool, quiet: bool) -> Option<String> {
+    if silent || quiet {
+        return None;
+    }
+    Some(stats.display())
+}
+
+/// Diff stats for the prompt, or an empty string (and no `--stat` git call) when
+/// `skip` is set (`--no-stats`, `explain --diff-only`).
+pub(crate) fn stats_unless(skip: bool, stats: impl FnOnce() -> Result<String>) -> Result<String> {
+    if skip {
+        Ok(String::new())
+    } else {
+        stats()
+    }
+}
+
+/// Note shown (and sent to the model) when a commit list was capped.
+pub(crate) fn omitted_commits_note(shown: usize, total: usize) -> Option<String> {
+    if total > shown {
+        Some(format!("(showing {} of {} commits)", shown, total))
+    } else {
+        None
+    }
+}
+
+/// `--context-file` text is cut to this many characters
+pub const MAX_CONTEXT_FILE_CHARS: usize = 8_000;
+
+/// Cut `text` to `max_chars` characters, noting how much was left out.
+pub(crate) fn cap_context(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!(
+            "{}\n[... truncated {} of {} chars]",
+            &text[..end],
+            text[end..].chars().count(),
+            text.chars().count()
+        ),
+        None => text.to_string(),
+    }
+}
+
+/// Read `--context-file`, capped at `MAX_CONTEXT_FILE_CHARS`.
+pub(crate) fn load_context_file(path: Option<&str>) -> Result<Option<String>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read context file {}", path))?;
+    Ok(Some(cap_context(&text, MAX_CONTEXT_FILE_CHARS)))
+}
+
+/// `--count-only` output: how many commits matched.
+pub(crate) fn count_only_line(commits: &[CommitInfo]) -> String {
+    match commits.len() {
+        1 => "1 commit".to_string(),
+        n => format!("{} commits", n),
+    }
+}
+
+/// What `history` / `changelog` print instead of calling the model: the `--count-only`
+/// count, or a note that nothing matched. None when the commits go to the model.
+pub(crate) fn no_request_line(commits: &[CommitInfo], count_only: bool) -> Option<String> {
+    if count_only {
+        Some(count_only_line(commits))
+    } else if commits.is_empty() {
+        Some("No commits found.".to_string())
+    } else {
+        None
+    }
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_context_truncates_with_note() {
+        assert_eq!(cap_context("short note", 100), "short note");
+        assert_eq!(cap_context("abcdef", 6), "abcdef");
+        assert_eq!(cap_context("abcdefgh", 3), "abc\n[... truncated 5 of 8 chars]");
+        assert_eq!(cap_context("ñandú", 2), "ña\n[... truncated 3 of 5 chars]");
+    }
+
+    #[test]
+    fn load_context_file_reads_and_caps() {
+        assert_eq!(load_context_file(None).unwrap(), None);
+        assert!(load_context_file(Some("/nonexistent/gitar-note.md")).is_err());
+
+        let path = std::env::temp_dir().join(format!("gitar_context_{}.md", std::process::id()));
+        std::fs::write(&path, "x".repeat(MAX_CONTEXT_FILE_CHARS + 10)).unwrap();
+        let text = load_context_file(path.to_str()).unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(text.ends_with("[... truncated 10 of 8010 chars]"));
+    }
+
+    #[test]
+    fn omitted_commits_note_when_capped() {
+        assert_eq!(
+            omitted_commits_note(20, 47),
+            Some("(showing 20 of 47 commits)".to_string())
+        );
+    }
+
+    fn commit(message: &str) -> CommitInfo {
+        CommitInfo {
+            hash: "abc123".into(),
+            author: "Ana".into(),
+            date: "2024-01-15".into(),
+            message: message.into(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn count_only_line_matches_commit_list() {
+        assert_eq!(count_only_line(&[]), "0 commits");
+        assert_eq!(count_only_line(&[commit("a")]), "1 commit");
+        let commits: Vec<_> = ["a", "b", "c"].iter().map(|m| commit(m)).collect();
+        assert_eq!(count_only_line(&commits), format!("{} commits", commits.len()));
+    }
+
+    #[test]
+    fn count_only_skips_the_request() {
+        let commits: Vec<_> = ["a", "b"].iter().map(|m| commit(m)).collect();
+        assert_eq!(no_request_line(&commits, true).as_deref(), Some("2 commits"));
+        assert_eq!(no_request_line(&[], true).as_deref(), Some("0 commits"));
+        assert_eq!(no_request_line(&[], false).as_deref(), Some("No commits found."));
+        assert_eq!(no_request_line(&commits, false), None);
+    }
+
+    fn sample_stats() -> DiffStats {
+        let opts = DiffOptions { max_chars: 1000, ..Default::default() };
+        let raw = "diff --git a/x b/x\n+1\n";
+        let (_, stats) = get_llm_diff_preview(raw, None, None, &HashSet::new(), DiffAlg::Full, &opts, false);
+        stats
+    }
+
+    #[test]
+    fn stats_box_shown_by_default() {
+        let stats = sample_stats();
+        assert_eq!(stats_box(&stats, false, false), Some(stats.display()));
+    }
+
+    #[test]
+    fn stats_box_suppressed_when_quiet_or_silent() {
+        let stats = sample_stats();
+        assert_eq!(stats_box(&stats, false, true), None);
+        assert_eq!(stats_box(&stats, true, false), None);
+    }
+
+    #[test]
+    fn stats_unless_skips_stats_call() {
+        let stats = stats_unless(true, || panic!("stats should not be fetched")).unwrap();
+        assert_eq!(stats, "");
+
+        let stats = stats_unless(false, || Ok("1 file changed".into())).unwrap();
+        assert_eq!(stats, "1 file changed");
+    }
+
+    #[test]
+    fn omitted_commits_note_none_when_all_shown() {
+        assert_eq!(omitted_commits_note(5, 5), None);
+        assert_eq!(omitted_commits_note(0, 0), None);
+    }
+}
@@ -1,13 +1,117 @@
 // src/commands/models.rs
-use anyhow::Result;
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use crate::client::LlmClient;
+use crate::config::Config;
+
+pub const MODELS_CACHE_FILENAME: &str = ".gitar_models_cache.json";
+
+/// One base URL's model list and when it was fetched (unix seconds).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedModels {
+    pub fetched_at: u64,
+    pub models: Vec<String>,
+}
+
+/// Model lists keyed by base URL, so each provider/endpoint keeps its own.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ModelsCache {
+    #[serde(flatten)]
+    entries: BTreeMap<String, CachedModels>,
+}
+
+impl ModelsCache {
+    /// A missing or unreadable file is an empty cache.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize models cache")?;
+        std::fs::write(path, json).context("Failed to write models cache")
+    }
+
+    /// Cached models for `base_url`, stale or not
+    pub fn get(&self, base_url: &str) -> Option<&[String]> {
+        self.entries.get(base_url).map(|e| e.models.as_slice())
+    }
+
+    /// Cached models for `base_url` fetched less than `ttl_secs` before `now`
+    pub fn fresh(&self, base_url: &str, now: u64, ttl_secs: u64) -> Option<&[String]> {
+        self.entries
+            .get(base_url)
+            .filter(|e| is_fresh(e.fetched_at, now, ttl_secs))
+            .map(|e| e.models.as_slice())
+    }
+
+    pub fn insert(&mut self, base_url: &str, now: u64, models: Vec<String>) {
+        self.entries.insert(base_url.to_string(), CachedModels { fetched_at: now, models });
+    }
+}
 
+/// A TTL of 0 never counts as fresh; a timestamp from the future (clock change) does.
+fn is_fresh(fetched_at: u64, now: u64, ttl_secs: u64) -> bool {
+    now.saturating_sub(fetched_at) < ttl_secs
+}
 
-pub async fn cmd_models(client: &LlmClient) -> Result<()> {
-    println!("Fetching available models...\n");
-    let models = client.list_models().await?;
+/// Next to the config file (`~/.gitar_models_cache.json` with the default `~/.gitar.toml`).
+fn models_cache_path() -> Option<PathBuf> {
+    Config::sibling_path(MODELS_CACHE_FILENAME)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Every cached model name across base URLs, sorted, for `--model` completion.
+pub(crate) fn cached_model_names() -> Vec<String> {
+    let cache = models_cache_path().as_deref().map(ModelsCache::load).unwrap_or_default();
+    let mut names: Vec<String> = cache.entries.into_values().flat_map(|e| e.models).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// `models --cached`: the cached names for `base_url`, one per line. No client and no
+/// repository needed, so shell completion works anywhere.
+pub fn cmd_models_cached(base_url: &str) -> Result<()> {
+    let cache = models_cache_path().as_deref().map(ModelsCache::load).unwrap_or_default();
+    for model in cache.get(base_url).unwrap_or_default() {
+        println!("{}", model);
+    }
+    Ok(())
+}
+
+pub async fn cmd_models(client: &LlmClient, refresh: bool, ttl_secs: u64, quiet: bool) -> Result<()> {
+    let path = models_cache_path();
+    let mut cache = path.as_deref().map(ModelsCache::load).unwrap_or_default();
+
+    let now = now_secs();
+    let models = match cache.fresh(client.base_url(), now, ttl_secs) {
+        Some(models) if !refresh => models.to_vec(),
+        _ => {
+            if !quiet {
+                println!("Fetching available models...\n");
+            }
+            let models = client.list_models().await?;
+            cache.insert(client.base_url(), now, models.clone());
+            if let Some(path) = &path {
+                // Best effort: without a cache the next run just fetches again
+                let _ = cache.save(path);
+            }
+            models
+        }
+    };
 
     if models.is_empty() {
         println!("No models found.");
@@ -18,4 +122,59 @@ pub async fn cmd_models(client: &LlmClient) -> Result<()> {
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://api.openai.com/v1";
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn cache_fresh_until_ttl_expires() {
+        let mut cache = ModelsCache::default();
+        cache.insert(URL, 1_000, vec!["gpt-4o".into()]);
+
+        assert_eq!(cache.fresh(URL, 1_000, DAY), Some(&["gpt-4o".to_string()][..]));
+        assert!(cache.fresh(URL, 1_000 + DAY - 1, DAY).is_some());
+        assert!(cache.fresh(URL, 1_000 + DAY, DAY).is_none());
+        // Expired entries are still there for completion
+        assert!(cache.get(URL).is_some());
+    }
+
+    #[test]
+    fn cache_ttl_edge_cases() {
+        assert!(!is_fresh(1_000, 1_000, 0));
+        assert!(is_fresh(2_000, 1_000, 60));
+        assert!(!is_fresh(0, u64::MAX, DAY));
+    }
+
+    #[test]
+    fn cache_is_per_base_url() {
+        let mut cache = ModelsCache::default();
+        cache.insert(URL, 1_000, vec!["gpt-4o".into()]);
+        cache.insert("https://api.groq.com/openai/v1", 1_000, vec!["llama".into()]);
+        assert_eq!(cache.get("https://api.groq.com/openai/v1").unwrap(), ["llama"]);
+        assert!(cache.get("http://localhost:11434/v1").is_none());
+    }
+
+    #[test]
+    fn cache_save_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("gitar_models_cache_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(ModelsCache::load(&path), ModelsCache::default());
+
+        let mut cache = ModelsCache::default();
+        cache.insert(URL, 42, vec!["gpt-4o".into(), "o3-mini".into()]);
+        cache.save(&path).unwrap();
+        assert_eq!(ModelsCache::load(&path), cache);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(ModelsCache::load(&path), ModelsCache::default());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
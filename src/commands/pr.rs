@@ -1,78 +1,364 @@
 // src/commands/pr.rs
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::client::LlmClient;
-use crate::git::{build_diff_target, build_range, get_commit_logs, get_current_branch, get_diff, get_diff_stats};
-use crate::prompt::{PR_SYSTEM_PROMPT, PR_USER_PROMPT};
+use crate::git::{
+    build_diff_target, build_range, count_commits, get_commit_logs, get_current_branch, get_diff, get_diff_stats,
+    get_numstat, get_repo_root, CommitInfo, LogQuery,
+};
+use crate::diff::{partition_chunks, split_diff_by_file};
+use crate::prompt::{add_additional_context, pr_group_prompt, pr_prompt, pr_reduce_prompt, Prompt};
 
-use super::apply_smart_diff;
+use super::{apply_smart_diff, load_context_file, omitted_commits_note, stats_unless, CommandOptions};
 
+/// Default cap on commit subjects included in the PR prompt
+pub const DEFAULT_PR_MAX_COMMITS: usize = 20;
+
+/// Where GitHub looks for a PR template, relative to the repo root (first match wins)
+const PR_TEMPLATE_PATHS: &[&str] = &[
+    ".github/pull_request_template.md",
+    ".github/PULL_REQUEST_TEMPLATE.md",
+    "pull_request_template.md",
+    "PULL_REQUEST_TEMPLATE.md",
+    "docs/pull_request_template.md",
+    "docs/PULL_REQUEST_TEMPLATE.md",
+];
+
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_pr(
     client: &LlmClient,
     base: Option<String>,
     to: Option<String>,
     base_branch: &str,
     staged: bool,
+    max_commits: usize,
+    template: Option<String>,
+    map_reduce: bool,
+    no_stats: bool,
+    compare_commits: bool,
+    context_file: Option<String>,
     stream: bool,
     alg: u8,
-    max_diff_chars: usize,
+    opts: &CommandOptions,
 ) -> Result<()> {
+    let extra = load_context_file(context_file.as_deref())?;
+    let input = match collect_pr(base, to, base_branch, staged, max_commits, template, no_stats, opts)? {
+        Some(i) => i,
+        None => return Ok(()),
+    };
+
+    let mut prompt = if map_reduce && input.raw_diff.len() > opts.diff.max_chars {
+        let summaries = summarize_groups(client, &input, alg, opts).await?;
+        pr_reduce_prompt(
+            &input.branch,
+            &input.commits,
+            &input.stats,
+            &summaries,
+            input.template.as_deref(),
+        )
+    } else {
+        let diff = apply_smart_diff(&input.raw_diff, Some(&input.numstat), false, alg, opts)?;
+        pr_prompt(&input.branch, &input.commits, &input.stats, &diff, input.template.as_deref())
+    };
+    if let Some(extra) = &extra {
+        add_additional_context(&mut prompt, extra);
+    }
+
+    let r = client.chat(&prompt.system, &prompt.user, stream).await?;
+    if stream {
+        println!();
+    } else {
+        println!("{}", r);
+    }
+    if compare_commits {
+        if let Some(section) = commits_section(&input.commit_log, input.total_commits) {
+            println!("\n{}", section);
+        }
+    }
+    Ok(())
+}
+
+/// `--compare-commits`: the branch's commits, verbatim, to go under the generated body.
+pub(crate) fn commits_section(commits: &[CommitInfo], total: usize) -> Option<String> {
+    if commits.is_empty() {
+        return None;
+    }
+    let mut section = String::from("## Commits\n\n");
+    for c in commits {
+        section.push_str(&format!("- {} {}\n", &c.hash[..7.min(c.hash.len())], c.message));
+    }
+    if let Some(note) = omitted_commits_note(commits.len(), total) {
+        section.push_str(&format!("\n{}\n", note));
+    }
+    Some(section.trim_end().to_string())
+}
+
+/// Map step of `--map-reduce`: one cheap summary per group of files that fits the budget.
+async fn summarize_groups(
+    client: &LlmClient,
+    input: &PrInput,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Vec<String>> {
+    let chunks = split_diff_by_file(&input.raw_diff, opts.diff.max_line_chars);
+    let groups = partition_chunks(&chunks, opts.diff.max_chars);
+    let mut summaries = Vec::with_capacity(groups.len());
+
+    for (i, group) in groups.iter().enumerate() {
+        if !opts.quiet {
+            eprintln!("Summarizing part {}/{} ({} files)...", i + 1, groups.len(), group.len());
+        }
+        let raw: String = group.iter().map(|c| c.content.as_str()).collect();
+        let diff = apply_smart_diff(&raw, Some(&input.numstat), true, alg, opts)?;
+        let p = pr_group_prompt(&input.branch, i + 1, groups.len(), &diff);
+        summaries.push(client.chat(&p.system, &p.user, false).await?);
+    }
+    Ok(summaries)
+}
+
+/// Everything the PR prompt needs, before diff shaping.
+struct PrInput {
+    branch: String,
+    commits: String,
+    /// Commits behind `commits` (empty for `--staged`), newest first
+    commit_log: Vec<CommitInfo>,
+    /// All commits in the range, `commit_log` may be capped
+    total_commits: usize,
+    stats: String,
+    raw_diff: String,
+    /// `git diff --numstat` for `raw_diff`
+    numstat: String,
+    template: Option<String>,
+}
+
+/// First known PR template location under `root`.
+fn find_pr_template(root: &Path, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    PR_TEMPLATE_PATHS
+        .iter()
+        .map(|p| root.join(p))
+        .find(|p| exists(p))
+}
+
+/// Read the template given with `--template`, or the repo's GitHub template if any.
+fn load_pr_template(explicit: Option<String>, quiet: bool) -> Result<Option<String>> {
+    let path = match explicit {
+        Some(p) => PathBuf::from(p),
+        None => match get_repo_root().and_then(|root| find_pr_template(&root, |p| p.is_file())) {
+            Some(p) => p,
+            None => return Ok(None),
+        },
+    };
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read PR template {}", path.display()))?;
+    if !quiet {
+        println!("Template: {}\n", path.display());
+    }
+    Ok(Some(text))
+}
+
+/// Gather branch, commits, stats and the raw diff (None if there are no changes).
+#[allow(clippy::too_many_arguments)]
+fn collect_pr(
+    base: Option<String>,
+    to: Option<String>,
+    base_branch: &str,
+    staged: bool,
+    max_commits: usize,
+    template: Option<String>,
+    no_stats: bool,
+    opts: &CommandOptions,
+) -> Result<Option<PrInput>> {
+    let diff = &opts.diff;
+    let template = load_pr_template(template, opts.quiet)?;
     let branch = to.clone().unwrap_or_else(get_current_branch);
     let target_base = base.as_deref().unwrap_or(base_branch);
 
-    println!("PR: {} -> {}\n", branch, target_base);
+    if !opts.quiet {
+        println!("PR: {} -> {}\n", branch, target_base);
+    }
 
-    let (diff, stats, commits_text) = if staged {
-        let raw_diff = get_diff(None, true, usize::MAX)?;
-        let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
-        (diff, get_diff_stats(None, true)?, "(staged changes)".into())
+    let (raw_diff, numstat, stats, commits, commit_log, total_commits) = if staged {
+        let raw_diff = get_diff(None, true, usize::MAX, diff, &[])?;
+        let numstat = get_numstat(None, true, &[]).unwrap_or_default();
+        let stats = stats_unless(no_stats, || get_diff_stats(None, true))?;
+        (raw_diff, numstat, stats, "(staged changes)".into(), Vec::new(), 0)
     } else {
         let diff_target = build_diff_target(base.as_deref(), to.as_deref(), base_branch);
         let range = build_range(base.as_deref(), to.as_deref(), base_branch);
 
-        let commits = get_commit_logs(Some(20), None, None, range.as_deref())?;
-        let ct = commits
+        let limit = Some(max_commits);
+        let commits = get_commit_logs(&LogQuery {
+            limit,
+            range: range.as_deref(),
+            ..Default::default()
+        })?;
+        let mut ct = commits
             .iter()
             .map(|c| format!("- {}", c.message))
             .collect::<Vec<_>>()
             .join("\n");
 
+        let total = count_commits(None, None, range.as_deref(), false, &[]).unwrap_or(commits.len());
+        if let Some(note) = omitted_commits_note(commits.len(), total) {
+            if !opts.quiet {
+                println!("{}\n", note);
+            }
+            ct.push_str(&format!("\n{}", note));
+        }
+
         let diff_target_ref = if diff_target.is_empty() {
             None
         } else {
             Some(diff_target.as_str())
         };
 
-        let raw_diff = get_diff(diff_target_ref, false, usize::MAX)?;
-        let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
+        let raw_diff = get_diff(diff_target_ref, false, usize::MAX, diff, &[])?;
 
         (
-            diff,
-            get_diff_stats(diff_target_ref, false)?,
+            raw_diff,
+            get_numstat(diff_target_ref, false, &[]).unwrap_or_default(),
+            stats_unless(no_stats, || get_diff_stats(diff_target_ref, false))?,
             if ct.is_empty() {
                 "(no commits)".into()
             } else {
                 ct
             },
+            commits,
+            total,
         )
     };
 
-    if diff.trim().is_empty() {
+    if raw_diff.trim().is_empty() {
         println!("No changes detected.");
-        return Ok(());
+        return Ok(None);
     }
 
-    let prompt = PR_USER_PROMPT
-        .replace("{branch}", &branch)
-        .replace("{commits}", &commits_text)
-        .replace("{stats}", &stats)
-        .replace("{diff}", &diff);
+    Ok(Some(PrInput {
+        branch,
+        commits,
+        commit_log,
+        total_commits,
+        stats,
+        raw_diff,
+        numstat,
+        template,
+    }))
+}
 
-    let r = client.chat(PR_SYSTEM_PROMPT, &prompt, stream).await?;
-    if stream {
-        println!();
-    } else {
-        println!("{}", r);
+/// Build the `pr` prompt (None if there are no changes).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_pr(
+    base: Option<String>,
+    to: Option<String>,
+    base_branch: &str,
+    staged: bool,
+    max_commits: usize,
+    template: Option<String>,
+    no_stats: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<Prompt>> {
+    let input = match collect_pr(base, to, base_branch, staged, max_commits, template, no_stats, opts)? {
+        Some(i) => i,
+        None => return Ok(None),
+    };
+    let diff = apply_smart_diff(&input.raw_diff, Some(&input.numstat), false, alg, opts)?;
+    Ok(Some(pr_prompt(
+        &input.branch,
+        &input.commits,
+        &input.stats,
+        &diff,
+        input.template.as_deref(),
+    )))
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, message: &str) -> CommitInfo {
+        CommitInfo {
+            hash: hash.into(),
+            author: "Ana".into(),
+            date: "2024-01-15".into(),
+            message: message.into(),
+            body: String::new(),
+        }
     }
-    Ok(())
-}
\ No newline at end of file
+
+    #[test]
+    fn commits_section_lists_short_hashes_and_subjects() {
+        let commits = [
+            commit("0123456789abcdef", "Add login form"),
+            commit("fedcba9876543210", "Fix | pipe in subject"),
+        ];
+        assert_eq!(
+            commits_section(&commits, 2).unwrap(),
+            "## Commits\n\n- 0123456 Add login form\n- fedcba9 Fix | pipe in subject"
+        );
+        assert!(commits_section(&commits, 5).unwrap().ends_with("\n\n(showing 2 of 5 commits)"));
+        assert_eq!(commits_section(&[], 0), None);
+    }
+
+    #[test]
+    fn find_pr_template_prefers_github_dir() {
+        let root = Path::new("/repo");
+        let found = find_pr_template(root, |_| true);
+        assert_eq!(found, Some(PathBuf::from("/repo/.github/pull_request_template.md")));
+    }
+
+    #[test]
+    fn find_pr_template_checks_root_and_docs() {
+        let root = Path::new("/repo");
+        let found = find_pr_template(root, |p| {
+            p.ends_with("PULL_REQUEST_TEMPLATE.md") && !p.starts_with("/repo/.github")
+        });
+        assert_eq!(found, Some(PathBuf::from("/repo/PULL_REQUEST_TEMPLATE.md")));
+
+        let found = find_pr_template(root, |p| p.starts_with("/repo/docs"));
+        assert_eq!(found, Some(PathBuf::from("/repo/docs/pull_request_template.md")));
+    }
+
+    #[test]
+    fn find_pr_template_none_when_missing() {
+        assert_eq!(find_pr_template(Path::new("/repo"), |_| false), None);
+    }
+
+    #[test]
+    fn load_pr_template_reads_explicit_file() {
+        let path = std::env::temp_dir().join(format!("gitar_pr_template_{}.md", std::process::id()));
+        fs::write(&path, "## Summary\n## Checklist\n").unwrap();
+        let text = load_pr_template(Some(path.to_string_lossy().into()), false).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(text.as_deref(), Some("## Summary\n## Checklist\n"));
+    }
+
+    #[test]
+    fn load_pr_template_missing_explicit_file_errors() {
+        assert!(load_pr_template(Some("/nonexistent/gitar/template.md".into()), false).is_err());
+    }
+
+    #[test]
+    fn collect_pr_caps_commits_at_max() {
+        let repo = crate::git::temp_repo("pr_commits");
+        repo.commit("README.md", "base\n", "Initial commit");
+        repo.git(&["checkout", "-q", "-b", "feature"]);
+        for i in 0..4 {
+            repo.commit("src/lib.rs", &format!("// {}\n", i), &format!("Step {}", i));
+        }
+
+        // What `pr --commits 2` collects
+        let opts = CommandOptions { quiet: true, ..Default::default() };
+        let input = collect_pr(None, None, "main", false, 2, None, true, &opts).unwrap().unwrap();
+        let subjects: Vec<&str> = input.commit_log.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(subjects, ["Step 3", "Step 2"]);
+        assert_eq!(input.total_commits, 4);
+        assert!(!input.commits.contains("Step 1"));
+        assert!(input.raw_diff.contains("src/lib.rs"));
+    }
+}
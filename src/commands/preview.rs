@@ -0,0 +1,173 @@
+// src/commands/preview.rs
+use anyhow::{bail, Result};
+
+use crate::git::{get_commit_logs, LogQuery};
+use crate::prompt::{add_instruction, Prompt};
+
+use super::changelog::prepare_changelog;
+use super::commit::{prepare_commit, prepare_staged};
+use super::explain::prepare_explain;
+use super::history::prepare_history;
+use super::pr::{prepare_pr, DEFAULT_PR_MAX_COMMITS};
+use super::version::prepare_version;
+use super::CommandOptions;
+
+/// Commands whose prompts can be previewed
+pub const PREVIEW_COMMANDS: &[&str] = &[
+    "commit",
+    "staged",
+    "unstaged",
+    "history",
+    "pr",
+    "changelog",
+    "explain",
+    "version",
+];
+
+/// Print the exact prompts a command would send, without calling the API.
+///
+/// REF is passed where the command takes one (pr/version base, history/changelog/explain start).
+#[allow(clippy::too_many_arguments)]
+pub fn cmd_prompt_preview(
+    command: &str,
+    reference: Option<String>,
+    staged: bool,
+    base_branch: &str,
+    instruct: Option<&str>,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<()> {
+    let prompt = match command {
+        "commit" => prepare_commit(false, false, None, alg, opts)?,
+        "staged" => Some(prepare_staged(true, &[], false, alg, opts)?),
+        "unstaged" => Some(prepare_staged(false, &[], false, alg, opts)?),
+        "history" => {
+            let range = reference.map(|r| format!("{}..HEAD", r));
+            let commits = get_commit_logs(&LogQuery {
+                limit: Some(1),
+                range: range.as_deref(),
+                ..Default::default()
+            })?;
+            match commits.first() {
+                Some(c) => prepare_history(c, alg, opts)?,
+                None => None,
+            }
+        }
+        "pr" => prepare_pr(
+            reference,
+            None,
+            base_branch,
+            staged,
+            DEFAULT_PR_MAX_COMMITS,
+            None,
+            false,
+            alg,
+            opts,
+        )?,
+        "changelog" => prepare_changelog(
+            reference,
+            &[],
+            None,
+            None,
+            None,
+            None,
+            false,
+            false,
+            &[],
+            false,
+            false,
+            alg,
+            opts,
+        )?,
+        "explain" => prepare_explain(
+            reference,
+            None,
+            None,
+            None,
+            base_branch,
+            staged,
+            None,
+            false,
+            false,
+            alg,
+            opts,
+        )?
+        .map(|(p, _)| p),
+        "version" => prepare_version(
+            reference,
+            None,
+            base_branch,
+            None,
+            false,
+            alg,
+            opts,
+        )?,
+        other => bail!(
+            "Unknown command '{}'. Expected one of: {}",
+            other,
+            PREVIEW_COMMANDS.join(", ")
+        ),
+    };
+
+    match prompt {
+        Some(mut p) => {
+            // What the client adds at send time
+            p.system = add_instruction(&p.system, instruct);
+            print!("{}", format_prompt_preview(&p));
+        }
+        None => println!("Nothing to preview."),
+    }
+    Ok(())
+}
+
+/// Render prompts with clear delimiters.
+pub(crate) fn format_prompt_preview(prompt: &Prompt) -> String {
+    let bar = "=".repeat(60);
+    format!(
+        "{bar}\nSYSTEM PROMPT\n{bar}\n{}\n{bar}\nUSER PROMPT\n{bar}\n{}\n{bar}\n",
+        prompt.system, prompt.user
+    )
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{commit_prompt, COMMIT_SYSTEM_PROMPT, COMMIT_USER_PROMPT};
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n+pub fn answer() -> u32 { 42 }";
+
+    #[test]
+    fn preview_contains_exact_commit_prompts() {
+        let out = format_prompt_preview(&commit_prompt(DIFF, None, false, 0));
+        assert!(out.contains(COMMIT_SYSTEM_PROMPT));
+        assert!(out.contains(
+            &COMMIT_USER_PROMPT
+                .replace("{diff}", DIFF)
+                .replace("{scope_hint}", "")
+        ));
+    }
+
+    #[test]
+    fn preview_orders_system_before_user() {
+        let p = Prompt {
+            system: "SYS".into(),
+            user: "USR".into(),
+        };
+        let out = format_prompt_preview(&p);
+        let sys = out.find("SYSTEM PROMPT").unwrap();
+        let usr = out.find("USER PROMPT").unwrap();
+        assert!(sys < out.find("SYS\n").unwrap());
+        assert!(usr > out.find("SYS\n").unwrap());
+        assert!(usr < out.find("USR").unwrap());
+    }
+
+    #[test]
+    fn preview_commands_cover_llm_commands() {
+        for c in ["commit", "staged", "pr", "changelog", "explain", "version", "history"] {
+            assert!(PREVIEW_COMMANDS.contains(&c));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// src/commands/reword.rs
+//
+// `gitar reword [<hash>]`: regenerate one commit's message. HEAD is amended in place;
+// older commits (needs --force) go through the same scripted rebase as `gitar rewrite`.
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::client::LlmClient;
+use crate::git::{get_commit_logs, get_git_path, run_git, run_git_status, LogQuery};
+
+use super::commit::{git_step, tag_message};
+use super::history::prepare_history;
+use super::rewrite::{check_rewrite_safe, create_rebase_dir, rewrite_state, run_rebase};
+use super::CommandOptions;
+
+/// `git commit --amend` arguments that replace HEAD's message only: `--only` keeps
+/// whatever is staged out of the amended commit. `sign` as in `build_commit_args`.
+pub(crate) fn build_amend_args(message_file: &Path, sign: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = ["commit", "--amend", "--only", "--allow-empty", "--no-verify"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    if let Some(key) = sign {
+        args.push(format!("-S{}", key));
+    }
+    args.extend(["-F".to_string(), message_file.display().to_string()]);
+    args
+}
+
+/// Older commits are rewritten by a rebase of everything after them, so they need `--force`.
+pub(crate) fn check_reword_allowed(short: &str, later: usize, force: bool) -> Result<()> {
+    if later > 0 && !force {
+        bail!(
+            "{} is {} commit(s) behind HEAD; rewording it rebases the commits after it. \
+             Rerun with --force to proceed.",
+            short,
+            later
+        );
+    }
+    Ok(())
+}
+
+/// HEAD is amended in place, but once pushed that still needs a force push: refuse unless `--force`.
+pub(crate) fn check_head_unpushed(short: &str, unpushed: usize, force: bool) -> Result<()> {
+    if unpushed < 1 && !force {
+        bail!(
+            "{} is already pushed; rewording it would need a force push. Rerun with --force to proceed.",
+            short
+        );
+    }
+    Ok(())
+}
+
+/// Full hash of `rev`, or an error if it does not name a commit.
+fn resolve_commit(rev: &str) -> Result<String> {
+    let hash = run_git(&["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])?;
+    match hash.trim() {
+        "" => bail!("Unknown commit: {}", rev),
+        h => Ok(h.to_string()),
+    }
+}
+
+/// Write `message` to a file in the git directory and amend HEAD with it.
+fn amend_head(message: &str, sign: Option<&str>) -> Result<()> {
+    let path = get_git_path("GITAR_REWORD_MSG").context("Could not locate the git directory")?;
+    fs::write(&path, message).with_context(|| format!("Could not write {}", path.display()))?;
+    let args_vec = build_amend_args(&path, sign);
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    let (_, err, ok) = run_git_status(&args);
+    fs::remove_file(&path).ok();
+    if !ok && !err.trim().is_empty() {
+        eprintln!("{}", err.trim());
+    }
+    git_step("Amend", ok)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_reword(
+    client: &LlmClient,
+    commit: Option<String>,
+    force: bool,
+    tag: bool,
+    sign: Option<String>,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<()> {
+    let rev = commit.as_deref().unwrap_or("HEAD");
+    let hash = resolve_commit(rev)?;
+    let short = &hash[..8.min(hash.len())];
+
+    let (_, _, on_branch) = run_git_status(&["merge-base", "--is-ancestor", &hash, "HEAD"]);
+    if !on_branch {
+        bail!("{} is not on the current branch.", short);
+    }
+    let later: Vec<String> = run_git(&["rev-list", "--reverse", &format!("{}..HEAD", hash)])?
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    check_reword_allowed(short, later.len(), force)?;
+    let count = later.len() + 1;
+    // The rebase has the same requirements as `gitar rewrite -n <count>`
+    let available = match later.len() {
+        0 => {
+            check_head_unpushed(short, rewrite_state(1)?.unpushed, force)?;
+            0
+        }
+        _ => {
+            let state = rewrite_state(count)?;
+            check_rewrite_safe(count, true, state)?;
+            state.available
+        }
+    };
+
+    let c = get_commit_logs(&LogQuery {
+        limit: Some(1),
+        range: Some(&hash),
+        with_body: true,
+        ..Default::default()
+    })?
+        .into_iter()
+        .next()
+        .with_context(|| format!("Could not read commit {}", short))?;
+    if !opts.quiet {
+        println!("{} | {}", short, c.message);
+    }
+    let prompt = match prepare_history(&c, alg, opts)? {
+        Some(p) => p,
+        None => bail!("{} has no changes to describe.", short),
+    };
+    let reply = client.chat(&prompt.system, &prompt.user, false).await?;
+    let message = tag_message(reply.trim(), client.model(), tag, 0);
+    for (j, l) in message.lines().enumerate() {
+        println!("{}{}", if j == 0 { "  - " } else { "    " }, l);
+    }
+
+    if later.is_empty() {
+        amend_head(&message, sign.as_deref())?;
+    } else {
+        let mut rewritten = vec![(hash.clone(), Some(message))];
+        rewritten.extend(later.into_iter().map(|h| (h, None)));
+        let dir = create_rebase_dir("gitar-reword")?;
+        let result = run_rebase(&dir, count, available, &rewritten, sign.as_deref());
+        fs::remove_dir_all(&dir).ok();
+        result?;
+    }
+
+    println!("Reworded {}.", short);
+    Ok(())
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amend_args_replace_message_only() {
+        let args = build_amend_args(Path::new("/tmp/gitar-reword-1.txt"), None);
+        assert_eq!(
+            args,
+            ["commit", "--amend", "--only", "--allow-empty", "--no-verify", "-F", "/tmp/gitar-reword-1.txt"]
+        );
+        let args = build_amend_args(Path::new("/tmp/gitar-reword-1.txt"), Some(""));
+        assert_eq!(
+            args,
+            ["commit", "--amend", "--only", "--allow-empty", "--no-verify", "-S", "-F", "/tmp/gitar-reword-1.txt"]
+        );
+        assert!(build_amend_args(Path::new("m.txt"), Some("ABCD1234")).contains(&"-SABCD1234".to_string()));
+    }
+
+    #[test]
+    fn reword_older_commit_needs_force() {
+        assert!(check_reword_allowed("abc12345", 0, false).is_ok());
+        assert!(check_reword_allowed("abc12345", 2, true).is_ok());
+        let err = check_reword_allowed("abc12345", 2, false).unwrap_err().to_string();
+        assert!(err.starts_with("abc12345 is 2 commit(s) behind HEAD") && err.contains("--force"));
+    }
+
+    #[test]
+    fn reword_pushed_head_needs_force() {
+        assert!(check_head_unpushed("abc12345", 1, false).is_ok());
+        assert!(check_head_unpushed("abc12345", 0, true).is_ok());
+        let err = check_head_unpushed("abc12345", 0, false).unwrap_err().to_string();
+        assert!(err.starts_with("abc12345 is already pushed") && err.contains("--force"));
+    }
+}
@@ -0,0 +1,348 @@
+// src/commands/rewrite.rs
+//
+// `gitar rewrite -n N --force`: regenerate the last N commit messages and amend them
+// with a scripted interactive rebase (`pick` + `exec git commit --amend -F`).
+use anyhow::{bail, Context, Result};
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+
+use crate::client::LlmClient;
+use crate::git::{get_commit_logs, get_git_path, run_git, run_git_status, run_git_status_env, LogQuery};
+
+use super::commit::tag_message;
+use super::history::prepare_history;
+use super::CommandOptions;
+
+/// What the safety checks look at, gathered before any LLM call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RewriteState {
+    /// Commits reachable from HEAD
+    pub available: usize,
+    /// Tracked files with uncommitted changes
+    pub dirty: bool,
+    /// Commits on HEAD not on any remote branch (the upstream included)
+    pub unpushed: usize,
+    /// Merge commits among the last N
+    pub merges: usize,
+}
+
+/// Refuse unless `--force` is given, the tree is clean, and the last `count` commits are
+/// linear and unpushed.
+pub(crate) fn check_rewrite_safe(count: usize, force: bool, state: RewriteState) -> Result<()> {
+    if count == 0 {
+        bail!("Nothing to rewrite: -n must be at least 1.");
+    }
+    if !force {
+        bail!(
+            "gitar rewrite changes commit hashes of the last {} commit(s). Rerun with --force to proceed.",
+            count
+        );
+    }
+    if state.available < count {
+        bail!("Only {} commit(s) on this branch, cannot rewrite {}.", state.available, count);
+    }
+    if state.dirty {
+        bail!("Working tree has uncommitted changes. Commit or stash them first.");
+    }
+    if state.unpushed < count {
+        bail!(
+            "Only {} of the last {} commits are unpushed. Rewriting pushed commits would need a force push.",
+            state.unpushed,
+            count
+        );
+    }
+    if state.merges > 0 {
+        bail!("The last {} commits include merge commits, which a rebase would flatten.", count);
+    }
+    Ok(())
+}
+
+/// Merge commits in `git rev-list --parents` output (lines with two or more parents).
+pub(crate) fn count_merges(rev_list_parents: &str) -> usize {
+    rev_list_parents
+        .lines()
+        .filter(|l| l.split_whitespace().count() > 2)
+        .count()
+}
+
+/// Quote for the POSIX shell git runs `exec` lines and the sequence editor with.
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Rebase todo list: pick each commit (oldest first), then amend it with its new
+/// message, signed with `sign` (key, or "" for the default) when set. Commits without
+/// a message file are picked as they are.
+pub(crate) fn build_rebase_todo(entries: &[(String, Option<PathBuf>)], sign: Option<&str>) -> String {
+    let sign_arg = sign.map(|key| format!(" {}", sh_quote(&format!("-S{}", key)))).unwrap_or_default();
+    entries
+        .iter()
+        .map(|(hash, message_file)| match message_file {
+            Some(file) => format!(
+                "pick {}\nexec git commit --amend --allow-empty --no-verify{} -F {}\n",
+                hash,
+                sign_arg,
+                sh_quote(&file.display().to_string())
+            ),
+            None => format!("pick {}\n", hash),
+        })
+        .collect()
+}
+
+/// `git rebase -i` arguments; rewriting every commit on the branch needs `--root`.
+fn rebase_args(count: usize, available: usize) -> Vec<String> {
+    let mut args = vec!["rebase".to_string(), "-i".to_string()];
+    if count >= available {
+        args.push("--root".into());
+    } else {
+        args.push(format!("HEAD~{}", count));
+    }
+    args
+}
+
+pub(crate) fn rewrite_state(count: usize) -> Result<RewriteState> {
+    let available = run_git(&["rev-list", "--count", "HEAD"])?.trim().parse().unwrap_or(0);
+    let dirty = !run_git(&["status", "--porcelain", "--untracked-files=no"])?.trim().is_empty();
+    let unpushed = run_git(&["rev-list", "--count", "HEAD", "--not", "--remotes"])?
+        .trim()
+        .parse()
+        .unwrap_or(0);
+    let merges = count_merges(&run_git(&["rev-list", "--parents", &format!("-n{}", count), "HEAD"])?);
+    Ok(RewriteState {
+        available,
+        dirty,
+        unpushed,
+        merges,
+    })
+}
+
+/// Create `dir` readable and writable by the current user only (0700). An existing
+/// directory is refused, not reused: whoever made it controls what the rebase runs.
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder.create(dir).with_context(|| {
+        format!("Could not create {} (left over from an interrupted run? Remove it and retry)", dir.display())
+    })
+}
+
+/// Fresh scratch directory `name` for a rebase, inside the git directory.
+pub(crate) fn create_rebase_dir(name: &str) -> Result<PathBuf> {
+    let dir = get_git_path(name).context("Could not locate the git directory")?;
+    create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+/// Write the messages and todo list to `dir` (from `create_rebase_dir`), then run the
+/// rebase with `GIT_SEQUENCE_EDITOR` copying the todo list over git's. Commits without
+/// a new message are replayed unchanged.
+pub(crate) fn run_rebase(
+    dir: &Path,
+    count: usize,
+    available: usize,
+    rewritten: &[(String, Option<String>)],
+    sign: Option<&str>,
+) -> Result<()> {
+    let mut entries = Vec::new();
+    for (i, (hash, message)) in rewritten.iter().enumerate() {
+        let Some(message) = message else {
+            entries.push((hash.clone(), None));
+            continue;
+        };
+        let path = dir.join(format!("{}.txt", i + 1));
+        fs::write(&path, message).with_context(|| format!("Could not write {}", path.display()))?;
+        entries.push((hash.clone(), Some(path)));
+    }
+    let todo = dir.join("git-rebase-todo");
+    fs::write(&todo, build_rebase_todo(&entries, sign)).context("Could not write the rebase todo list")?;
+
+    let editor = format!("cp {}", sh_quote(&todo.display().to_string()));
+    let args_vec = rebase_args(count, available);
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    let (_, err, ok) = run_git_status_env(&args, &[("GIT_SEQUENCE_EDITOR", &editor)]);
+    if !ok {
+        run_git_status(&["rebase", "--abort"]);
+        bail!("Rebase failed and was aborted:\n{}", err.trim());
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_rewrite(
+    client: &LlmClient,
+    count: usize,
+    force: bool,
+    tag: bool,
+    sign: Option<String>,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<()> {
+    let state = rewrite_state(count)?;
+    check_rewrite_safe(count, force, state)?;
+
+    // Newest first from git log; the rebase replays oldest first
+    let mut commits = get_commit_logs(&LogQuery {
+        limit: Some(count),
+        with_body: true,
+        ..Default::default()
+    })?;
+    commits.reverse();
+
+    let mut rewritten = Vec::new();
+    for (i, c) in commits.iter().enumerate() {
+        let h = &c.hash[..8.min(c.hash.len())];
+        if !opts.quiet {
+            println!("[{}/{}] {} | {}", i + 1, commits.len(), h, c.message);
+        }
+        let message = match prepare_history(c, alg, opts)? {
+            Some(prompt) => {
+                let reply = client
+                    .chat(&prompt.system, &prompt.user, false)
+                    .await
+                    .with_context(|| format!("No message for {}; nothing was rewritten", h))?;
+                tag_message(reply.trim(), client.model(), tag, 0)
+            }
+            // Nothing to describe: keep the original message
+            None => c.full_message(),
+        };
+        for (j, l) in message.lines().enumerate() {
+            println!("{}{}", if j == 0 { "  - " } else { "    " }, l);
+        }
+        rewritten.push((c.hash.clone(), Some(message)));
+    }
+
+    let dir = create_rebase_dir("gitar-rewrite")?;
+    let result = run_rebase(&dir, count, state.available, &rewritten, sign.as_deref());
+    fs::remove_dir_all(&dir).ok();
+    result?;
+
+    println!("Rewrote {} commit(s).", rewritten.len());
+    Ok(())
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn safe_state() -> RewriteState {
+        RewriteState {
+            available: 10,
+            dirty: false,
+            unpushed: 3,
+            merges: 0,
+        }
+    }
+
+    fn refusal(count: usize, force: bool, state: RewriteState) -> String {
+        check_rewrite_safe(count, force, state).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rewrite_allowed_on_clean_unpushed_commits() {
+        assert!(check_rewrite_safe(3, true, safe_state()).is_ok());
+        assert!(check_rewrite_safe(1, true, safe_state()).is_ok());
+    }
+
+    #[test]
+    fn rewrite_needs_force() {
+        assert!(refusal(3, false, safe_state()).contains("--force"));
+        assert!(refusal(0, true, safe_state()).contains("at least 1"));
+    }
+
+    #[test]
+    fn rewrite_refuses_dirty_tree() {
+        let state = RewriteState { dirty: true, ..safe_state() };
+        assert!(refusal(3, true, state).contains("uncommitted changes"));
+    }
+
+    #[test]
+    fn rewrite_refuses_pushed_commits() {
+        assert!(refusal(4, true, safe_state()).contains("Only 3 of the last 4 commits are unpushed"));
+        let state = RewriteState { unpushed: 0, ..safe_state() };
+        assert!(refusal(1, true, state).contains("force push"));
+    }
+
+    #[test]
+    fn rewrite_refuses_merges_and_short_history() {
+        let state = RewriteState { merges: 1, ..safe_state() };
+        assert!(refusal(2, true, state).contains("merge commits"));
+        let state = RewriteState { available: 2, unpushed: 2, ..safe_state() };
+        assert!(refusal(3, true, state).contains("Only 2 commit(s)"));
+    }
+
+    #[test]
+    fn count_merges_reads_parent_lists() {
+        let out = "aaa bbb\nbbb ccc ddd\nccc eee\neee\n";
+        assert_eq!(count_merges(out), 1);
+        assert_eq!(count_merges(""), 0);
+    }
+
+    #[test]
+    fn rebase_todo_picks_then_amends_each_commit() {
+        let entries = vec![
+            ("abc123".to_string(), Some(PathBuf::from("/tmp/gitar/1.txt"))),
+            ("def456".to_string(), Some(PathBuf::from("/tmp/it's/2.txt"))),
+        ];
+        let todo = build_rebase_todo(&entries, None);
+        let lines: Vec<&str> = todo.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "pick abc123",
+                "exec git commit --amend --allow-empty --no-verify -F '/tmp/gitar/1.txt'",
+                "pick def456",
+                r"exec git commit --amend --allow-empty --no-verify -F '/tmp/it'\''s/2.txt'",
+            ]
+        );
+    }
+
+    #[test]
+    fn rebase_todo_picks_commits_without_message_as_is() {
+        let entries = vec![
+            ("abc123".to_string(), Some(PathBuf::from("/tmp/1.txt"))),
+            ("def456".to_string(), None),
+        ];
+        assert_eq!(
+            build_rebase_todo(&entries, None),
+            "pick abc123\nexec git commit --amend --allow-empty --no-verify -F '/tmp/1.txt'\npick def456\n"
+        );
+    }
+
+    #[test]
+    fn rebase_todo_signs_amends() {
+        let entries = vec![("abc123".to_string(), Some(PathBuf::from("/tmp/1.txt")))];
+        assert_eq!(
+            build_rebase_todo(&entries, Some("")),
+            "pick abc123\nexec git commit --amend --allow-empty --no-verify '-S' -F '/tmp/1.txt'\n"
+        );
+        assert!(build_rebase_todo(&entries, Some("ABCD1234")).contains("--no-verify '-SABCD1234' -F"));
+    }
+
+    #[test]
+    fn rebase_args_use_root_for_whole_branch() {
+        assert_eq!(rebase_args(3, 10), ["rebase", "-i", "HEAD~3"]);
+        assert_eq!(rebase_args(2, 2), ["rebase", "-i", "--root"]);
+    }
+
+    #[test]
+    fn private_dir_refuses_existing_dir() {
+        let dir = std::env::temp_dir().join(format!("gitar_rebase_dir_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        create_private_dir(&dir).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+        }
+        // Someone else's directory at that path must not be reused
+        let err = create_private_dir(&dir).unwrap_err().to_string();
+        assert!(err.contains("Could not create"), "{}", err);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+// src/commands/split.rs
+//
+// `commit --interactive-split`: stage a subset, generate a message, commit, and
+// repeat until the working tree is clean (or the user quits).
+use anyhow::{bail, Result};
+use std::io::{self, Write};
+
+use crate::client::LlmClient;
+use crate::color;
+use crate::git::{run_git, run_git_status};
+use crate::types::ChatMessage;
+
+use super::commit::{
+    feedback_conversation, git_commit, parse_review, prepare_staged, push_feedback, tag_message,
+    truncate_subject, Review, REVIEW_MENU,
+};
+use super::CommandOptions;
+
+const STAGE_MENU: &str = "[Enter] Use staged | [a] Stage all | <paths> Stage paths | [q] Quit";
+
+/// What to stage for the next commit.
+#[derive(Debug, PartialEq)]
+pub(crate) enum SplitStep {
+    UseStaged,
+    StageAll,
+    Stage(Vec<String>),
+    Quit,
+}
+
+pub(crate) fn parse_split_input(input: &str) -> SplitStep {
+    match input.trim() {
+        "" => SplitStep::UseStaged,
+        "a" | "A" => SplitStep::StageAll,
+        "q" | "Q" => SplitStep::Quit,
+        paths => SplitStep::Stage(paths.split_whitespace().map(String::from).collect()),
+    }
+}
+
+/// Side effects of the split loop, so the loop itself can run against scripted input.
+pub(crate) trait SplitSession {
+    /// `git status --short`; empty when the tree is clean
+    fn status(&mut self) -> Result<String>;
+    fn ask(&mut self, menu: &str) -> Result<String>;
+    fn say(&mut self, text: &str);
+    /// Stage `paths`, or everything when empty
+    fn stage(&mut self, paths: &[String]) -> Result<()>;
+    fn has_staged(&mut self) -> Result<bool>;
+    /// Generate a message for the staged changes; `feedback` are the review rounds so far
+    async fn generate(&mut self, feedback: &[ChatMessage]) -> Result<String>;
+    fn commit(&mut self, message: &str) -> Result<bool>;
+}
+
+/// Returns the number of commits made.
+pub(crate) async fn run_split_loop<S: SplitSession>(s: &mut S) -> Result<usize> {
+    let mut commits = 0;
+
+    loop {
+        let status = s.status()?;
+        if status.trim().is_empty() {
+            s.say("Working tree clean.");
+            return Ok(commits);
+        }
+        s.say(&status);
+
+        match parse_split_input(&s.ask(STAGE_MENU)?) {
+            SplitStep::Quit => {
+                s.say("Stopped.");
+                return Ok(commits);
+            }
+            SplitStep::UseStaged => {}
+            SplitStep::StageAll => s.stage(&[])?,
+            SplitStep::Stage(paths) => s.stage(&paths)?,
+        }
+
+        if !s.has_staged()? {
+            s.say("Nothing staged.");
+            continue;
+        }
+
+        let mut feedback = Vec::new();
+        let message = loop {
+            let msg = s.generate(&feedback).await?;
+            s.say(&format!("\n{}\n", msg));
+            match parse_review(&s.ask(REVIEW_MENU)?) {
+                Review::Accept => break Some(msg),
+                Review::Regenerate => continue,
+                Review::Feedback => {
+                    let fb = s.ask("Feedback:")?;
+                    if !fb.trim().is_empty() {
+                        push_feedback(&mut feedback, &msg, &fb);
+                    }
+                }
+                Review::Edit => {
+                    let ed = s.ask("New message:")?;
+                    break Some(if ed.trim().is_empty() { msg } else { ed.trim().into() });
+                }
+                Review::Cancel => break None,
+            }
+        };
+
+        match message {
+            Some(m) => {
+                if !s.commit(&m)? {
+                    bail!("Commit failed after {} commit(s).", commits);
+                }
+                commits += 1;
+            }
+            None => {
+                s.say("Canceled.");
+                return Ok(commits);
+            }
+        }
+    }
+}
+
+pub(crate) struct GitSplitSession<'a> {
+    pub client: &'a LlmClient,
+    pub tag: bool,
+    pub signoff: bool,
+    /// GPG key id for `-S` (empty for the default key)
+    pub sign: Option<String>,
+    /// Few-shot style block from `--style-from-history`
+    pub style: String,
+    pub alg: u8,
+    pub opts: &'a CommandOptions,
+}
+
+impl SplitSession for GitSplitSession<'_> {
+    fn status(&mut self) -> Result<String> {
+        run_git(&["status", "--short"])
+    }
+
+    fn ask(&mut self, menu: &str) -> Result<String> {
+        println!("{}", color::rule());
+        println!("  {}", color::menu(menu));
+        println!("{}", color::rule());
+        print!("> ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input)
+    }
+
+    fn say(&mut self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn stage(&mut self, paths: &[String]) -> Result<()> {
+        let mut args = vec!["add"];
+        if paths.is_empty() {
+            args.push("-A");
+        } else {
+            args.push("--");
+            args.extend(paths.iter().map(|p| p.as_str()));
+        }
+        run_git(&args)?;
+        Ok(())
+    }
+
+    fn has_staged(&mut self) -> Result<bool> {
+        // --quiet exits 1 when there are differences
+        let (_, _, clean) = run_git_status(&["diff", "--cached", "--quiet"]);
+        Ok(!clean)
+    }
+
+    async fn generate(&mut self, feedback: &[ChatMessage]) -> Result<String> {
+        let mut prompt = prepare_staged(true, &[], false, self.alg, self.opts)?;
+        prompt.system.push_str(&self.style);
+        let conversation = feedback_conversation(&prompt.user, feedback);
+        let msg = self.client.chat_turns(&prompt.system, &conversation, false).await?;
+        Ok(truncate_subject(&msg, self.opts.subject_max_len))
+    }
+
+    fn commit(&mut self, message: &str) -> Result<bool> {
+        let full_msg = tag_message(message, self.client.model(), self.tag, self.opts.subject_max_len);
+        let (out, err, ok) = git_commit(&full_msg, false, self.signoff, self.sign.as_deref(), run_git_status);
+        println!("{}{}", out, err);
+        Ok(ok)
+    }
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Scripted session: each commit removes one entry from the status list.
+    struct Scripted {
+        files: Vec<String>,
+        staged: Vec<String>,
+        inputs: VecDeque<&'static str>,
+        generated: usize,
+        last_feedback: Vec<ChatMessage>,
+        commits: Vec<String>,
+        fail_commit: bool,
+    }
+
+    impl Scripted {
+        fn new(files: &[&str], inputs: &[&'static str]) -> Self {
+            Self {
+                files: files.iter().map(|f| f.to_string()).collect(),
+                staged: Vec::new(),
+                inputs: inputs.iter().copied().collect(),
+                generated: 0,
+                last_feedback: Vec::new(),
+                commits: Vec::new(),
+                fail_commit: false,
+            }
+        }
+    }
+
+    impl SplitSession for Scripted {
+        fn status(&mut self) -> Result<String> {
+            Ok(self.files.iter().map(|f| format!(" M {}\n", f)).collect())
+        }
+        fn ask(&mut self, _menu: &str) -> Result<String> {
+            Ok(self.inputs.pop_front().expect("script ran out of input").to_string())
+        }
+        fn say(&mut self, _text: &str) {}
+        fn stage(&mut self, paths: &[String]) -> Result<()> {
+            if paths.is_empty() {
+                self.staged = self.files.clone();
+            } else {
+                self.staged.extend(paths.iter().filter(|p| self.files.contains(p)).cloned());
+            }
+            Ok(())
+        }
+        fn has_staged(&mut self) -> Result<bool> {
+            Ok(!self.staged.is_empty())
+        }
+        async fn generate(&mut self, feedback: &[ChatMessage]) -> Result<String> {
+            self.generated += 1;
+            self.last_feedback = feedback.to_vec();
+            Ok(format!("Update {}", self.staged.join(", ")))
+        }
+        fn commit(&mut self, message: &str) -> Result<bool> {
+            if self.fail_commit {
+                return Ok(false);
+            }
+            self.files.retain(|f| !self.staged.contains(f));
+            self.staged.clear();
+            self.commits.push(message.to_string());
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn parse_split_input_variants() {
+        assert_eq!(parse_split_input("\n"), SplitStep::UseStaged);
+        assert_eq!(parse_split_input("a"), SplitStep::StageAll);
+        assert_eq!(parse_split_input(" q "), SplitStep::Quit);
+        assert_eq!(
+            parse_split_input("src/a.rs docs/"),
+            SplitStep::Stage(vec!["src/a.rs".into(), "docs/".into()])
+        );
+    }
+
+    #[tokio::test]
+    async fn split_loop_commits_until_clean() {
+        let mut s = Scripted::new(&["a.rs", "b.rs"], &["a.rs", "", "b.rs", ""]);
+        let n = run_split_loop(&mut s).await.unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(s.commits, ["Update a.rs", "Update b.rs"]);
+        assert!(s.files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn split_loop_commit_failure_is_an_error() {
+        let mut s = Scripted::new(&["a.rs"], &["a", ""]);
+        s.fail_commit = true;
+        let err = run_split_loop(&mut s).await.unwrap_err();
+        assert_eq!(err.to_string(), "Commit failed after 0 commit(s).");
+        assert_eq!(s.files, ["a.rs"]);
+    }
+
+    #[tokio::test]
+    async fn split_loop_quit_mid_way() {
+        let mut s = Scripted::new(&["a.rs", "b.rs"], &["a.rs", "", "q"]);
+        let n = run_split_loop(&mut s).await.unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(s.files, ["b.rs"]);
+    }
+
+    #[tokio::test]
+    async fn split_loop_regenerate_edit_and_cancel() {
+        // Regenerate once, then edit; second round cancels at review
+        let mut s = Scripted::new(&["a.rs", "b.rs"], &["a.rs", "g", "e", "Custom msg", "a", "x"]);
+        let n = run_split_loop(&mut s).await.unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(s.commits, ["Custom msg"]);
+        assert_eq!(s.generated, 3);
+    }
+
+    #[tokio::test]
+    async fn split_loop_feedback_is_passed_to_next_generation() {
+        let mut s = Scripted::new(&["a.rs"], &["a", "f", "shorter", ""]);
+        let n = run_split_loop(&mut s).await.unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(s.generated, 2);
+        assert_eq!(
+            s.last_feedback,
+            vec![ChatMessage::assistant("Update a.rs"), ChatMessage::user("shorter")]
+        );
+    }
+
+    #[tokio::test]
+    async fn split_loop_reprompts_when_nothing_staged() {
+        let mut s = Scripted::new(&["a.rs"], &["", "missing.rs", "a", ""]);
+        let n = run_split_loop(&mut s).await.unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(s.generated, 1);
+    }
+}
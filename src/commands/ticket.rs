@@ -0,0 +1,127 @@
+// src/commands/ticket.rs
+//
+// `commit --ticket-from-branch`: find a ticket id like `PROJ-123` in the branch name and
+// put it in the subject. `ticket_pattern` is a regular expression.
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Default `ticket_pattern`: Jira-style keys
+pub const DEFAULT_TICKET_PATTERN: &str = r"[A-Z]+-\d+";
+
+/// Where `--ticket-from-branch` puts the ticket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TicketPosition {
+    /// `PROJ-123 Fix login`
+    Prefix,
+    /// `Fix login (PROJ-123)`
+    Suffix,
+}
+
+impl TicketPosition {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "suffix" => Self::Suffix,
+            _ => Self::Prefix,
+        }
+    }
+}
+
+/// Put `ticket` in the subject, unless the model already wrote it there.
+pub(crate) fn add_ticket(msg: &str, ticket: &str, position: TicketPosition) -> String {
+    let (subject, body) = match msg.split_once('\n') {
+        Some((s, b)) => (s, Some(b)),
+        None => (msg, None),
+    };
+    let subject = if subject.contains(ticket) {
+        subject.to_string()
+    } else {
+        match position {
+            TicketPosition::Prefix => format!("{} {}", ticket, subject.trim()),
+            TicketPosition::Suffix => format!("{} ({})", subject.trim(), ticket),
+        }
+    };
+    match body {
+        Some(b) => format!("{}\n{}", subject, b),
+        None => subject,
+    }
+}
+
+/// Characters `add_ticket` adds to the subject of `msg`.
+pub(crate) fn ticket_width(msg: &str, ticket: &str, position: TicketPosition) -> usize {
+    let subject = msg.lines().next().unwrap_or_default();
+    if subject.contains(ticket) {
+        return 0;
+    }
+    let extra = match position {
+        TicketPosition::Prefix => 1,
+        TicketPosition::Suffix => 3,
+    };
+    ticket.chars().count() + extra
+}
+
+// =============================================================================
+// PATTERNS
+// =============================================================================
+/// Compile a `ticket_pattern` from config.
+pub(crate) fn ticket_regex(src: &str) -> Result<Regex> {
+    Regex::new(src).with_context(|| format!("Invalid ticket_pattern '{}'", src))
+}
+
+/// The first ticket id in `branch`, e.g. `PROJ-123` from `feature/PROJ-123-login`.
+pub(crate) fn ticket_from_branch(branch: &str, pattern: &Regex) -> Option<String> {
+    pattern.find(branch).map(|m| m.as_str()).filter(|t| !t.is_empty()).map(String::from)
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(branch: &str, pattern: &str) -> Option<String> {
+        ticket_from_branch(branch, &ticket_regex(pattern).unwrap())
+    }
+
+    #[test]
+    fn default_pattern_over_branch_names() {
+        let cases = [
+            ("feature/PROJ-123-foo", Some("PROJ-123")),
+            ("PROJ-7", Some("PROJ-7")),
+            ("bugfix/ABC-42_login-timeout", Some("ABC-42")),
+            ("hotfix/OPS-9001", Some("OPS-9001")),
+            ("JIRA-12/ABC-34", Some("JIRA-12")),
+            ("feature/proj-123-lowercase", None),
+            ("feature/PROJ-no-number", None),
+            ("main", None),
+        ];
+        for (branch, expected) in cases {
+            assert_eq!(ticket(branch, DEFAULT_TICKET_PATTERN).as_deref(), expected, "{}", branch);
+        }
+    }
+
+    #[test]
+    fn custom_patterns() {
+        assert_eq!(ticket("fix/gh-1234-crash", r"gh-\d+").as_deref(), Some("gh-1234"));
+        assert_eq!(ticket("feature/#77-search", r"#\d{2,4}").as_deref(), Some("#77"));
+        assert_eq!(ticket("PROJ-123-x", r"^[A-Z]{2,10}-[0-9]+").as_deref(), Some("PROJ-123"));
+        assert_eq!(ticket("feature/PROJ-123", r"^[A-Z]+-\d+"), None);
+        assert_eq!(ticket("release-2024", r"\d+$").as_deref(), Some("2024"));
+        assert_eq!(ticket("feat/AB_12", r"[A-Z]+[_-]\d+").as_deref(), Some("AB_12"));
+        assert_eq!(ticket("fix/OPS-5-crash", r"(PROJ|OPS)-\d+").as_deref(), Some("OPS-5"));
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        assert!(ticket_regex("[A-Z").is_err());
+    }
+
+    #[test]
+    fn add_ticket_prefix_and_suffix() {
+        let msg = "Fix login timeout\n\n- retry once";
+        assert_eq!(add_ticket(msg, "PROJ-1", TicketPosition::Prefix), "PROJ-1 Fix login timeout\n\n- retry once");
+        assert_eq!(add_ticket("Fix login", "PROJ-1", TicketPosition::Suffix), "Fix login (PROJ-1)");
+        // Already there: left alone
+        assert_eq!(add_ticket("PROJ-1: Fix login", "PROJ-1", TicketPosition::Prefix), "PROJ-1: Fix login");
+    }
+}
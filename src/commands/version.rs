@@ -3,22 +3,76 @@ use anyhow::Result;
 
 use crate::client::LlmClient;
 use crate::git::{build_diff_target, get_current_version, get_diff};
-use crate::prompt::{VERSION_SYSTEM_PROMPT, VERSION_USER_PROMPT};
+use crate::prompt::{version_prompt, Prompt};
 
-use super::apply_smart_diff;
+use super::{apply_smart_diff, CommandOptions};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_version(
     client: &LlmClient,
     base: Option<String>,
     to: Option<String>,
     base_branch: &str,
     current: Option<String>,
+    json: bool,
     stream: bool,
     alg: u8,
-    max_diff_chars: usize,
+    opts: &CommandOptions,
 ) -> Result<()> {
+    let prompt = match prepare_version(base, to, base_branch, current, json, alg, opts)? {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    if json {
+        let r = client.chat_json(&prompt.system, &prompt.user).await?;
+        let value = extract_json_object(&r)
+            .ok_or_else(|| anyhow::anyhow!("Model did not return valid JSON:\n{}", r))?;
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    let r = client.chat(&prompt.system, &prompt.user, stream).await?;
+    if stream {
+        println!();
+    } else {
+        println!("{}", r);
+    }
+    Ok(())
+}
+
+/// Parse a JSON object from a response, tolerating prose or code fences around it.
+pub(crate) fn extract_json_object(text: &str) -> Option<serde_json::Value> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    serde_json::from_str::<serde_json::Value>(&text[start..=end])
+        .ok()
+        .filter(|v| v.is_object())
+}
+
+/// Build the `version` prompt (None if there are no changes).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_version(
+    base: Option<String>,
+    to: Option<String>,
+    base_branch: &str,
+    current: Option<String>,
+    json: bool,
+    alg: u8,
+    opts: &CommandOptions,
+) -> Result<Option<Prompt>> {
     let current = current.unwrap_or_else(get_current_version);
-    println!("Version analysis (current: {})...\n", current);
+    // Keep stdout clean for --json
+    if !opts.quiet {
+        if json {
+            eprintln!("Version analysis (current: {})...\n", current);
+        } else {
+            println!("Version analysis (current: {})...\n", current);
+        }
+    }
 
     let diff_target = build_diff_target(base.as_deref(), to.as_deref(), base_branch);
     let diff_target_ref = if diff_target.is_empty() {
@@ -27,24 +81,37 @@ pub async fn cmd_version(
         Some(diff_target.as_str())
     };
 
-    let raw_diff = get_diff(diff_target_ref, false, usize::MAX)?;
+    let raw_diff = get_diff(diff_target_ref, false, usize::MAX, &opts.diff, &[])?;
 
     if raw_diff.trim().is_empty() {
         println!("No changes detected.");
-        return Ok(());
+        return Ok(None);
     }
 
-    let diff = apply_smart_diff(&raw_diff, max_diff_chars, false, alg)?;
+    let diff = apply_smart_diff(&raw_diff, None, false, alg, opts)?;
+    Ok(Some(version_prompt(&current, &diff, json)))
+}
 
-    let prompt = VERSION_USER_PROMPT
-        .replace("{version}", &current)
-        .replace("{diff}", &diff);
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let r = client.chat(VERSION_SYSTEM_PROMPT, &prompt, stream).await?;
-    if stream {
-        println!();
-    } else {
-        println!("{}", r);
+    #[test]
+    fn extract_json_object_plain_and_fenced() {
+        let v = extract_json_object(r#"{"bump": "minor", "breaking": false}"#).unwrap();
+        assert_eq!(v["bump"], "minor");
+
+        let v = extract_json_object("Here you go:\n```json\n{\"bump\": \"major\"}\n```").unwrap();
+        assert_eq!(v["bump"], "major");
+    }
+
+    #[test]
+    fn extract_json_object_rejects_invalid() {
+        assert!(extract_json_object("Recommendation: PATCH").is_none());
+        assert!(extract_json_object("} {").is_none());
+        assert!(extract_json_object("{not json}").is_none());
     }
-    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,242 @@
+// src/commands/wizard.rs
+//
+// `gitar init` with no flags (or `--interactive`): ask for provider, key and model,
+// then write the config file.
+use anyhow::{bail, Result};
+use std::io::{self, IsTerminal, Write};
+
+use crate::client::LlmClient;
+use crate::config::{env_var_for_provider, normalize_provider, CliOverrides, Config, ResolvedConfig};
+use crate::git::get_default_branch;
+
+const PROVIDERS: &[&str] = &["openai", "claude", "gemini", "groq", "openrouter", "ollama", "bedrock"];
+
+/// Models printed when the user asks to see the list
+const MAX_LISTED_MODELS: usize = 20;
+
+/// Side effects of the wizard, so the flow can run against scripted answers.
+pub(crate) trait InitWizard {
+    /// Show `question` and return the (untrimmed) answer
+    fn ask(&mut self, question: &str) -> Result<String>;
+    fn say(&mut self, text: &str);
+    /// Models for the provider configured so far in `config`
+    async fn list_models(&mut self, config: &Config, provider: &str) -> Result<Vec<String>>;
+}
+
+/// `anthropic` -> `claude`; None for names gitar doesn't know.
+fn parse_provider(answer: &str) -> Option<&'static str> {
+    let p = answer.trim().to_lowercase();
+    let known = PROVIDERS.contains(&p.as_str()) || matches!(p.as_str(), "anthropic" | "google" | "local" | "aws");
+    known.then(|| normalize_provider(&p))
+}
+
+/// Walk through provider, API key and model, returning the updated config.
+pub(crate) async fn run_init_wizard<W: InitWizard>(w: &mut W, file: &Config) -> Result<Config> {
+    let mut config = file.clone();
+    let current = config
+        .default_provider
+        .as_deref()
+        .map(normalize_provider)
+        .unwrap_or("openai");
+
+    let provider = loop {
+        let answer = w.ask(&format!("Provider [{}] (default: {}): ", PROVIDERS.join("/"), current))?;
+        if answer.trim().is_empty() {
+            break current;
+        }
+        match parse_provider(&answer) {
+            Some(p) => break p,
+            None => w.say(&format!("Unknown provider '{}'.", answer.trim())),
+        }
+    };
+    config.default_provider = Some(provider.to_string());
+
+    if let Some(var) = env_var_for_provider(provider) {
+        let answer = w.ask(&format!("API key (leave empty to use ${}): ", var))?;
+        let key = answer.trim();
+        if !key.is_empty() {
+            config.get_provider_mut(provider).api_key = Some(key.to_string());
+        }
+    }
+
+    let answer = w.ask("List available models? [y/N]: ")?;
+    if answer.trim().eq_ignore_ascii_case("y") {
+        match w.list_models(&config, provider).await {
+            Ok(models) if models.is_empty() => w.say("No models found."),
+            Ok(models) => {
+                for m in models.iter().take(MAX_LISTED_MODELS) {
+                    w.say(&format!("  {}", m));
+                }
+                if models.len() > MAX_LISTED_MODELS {
+                    w.say(&format!("  ... and {} more", models.len() - MAX_LISTED_MODELS));
+                }
+            }
+            Err(e) => w.say(&format!("Could not list models: {}", e)),
+        }
+    }
+
+    let current_model = config
+        .get_provider(provider)
+        .and_then(|pc| pc.model.clone())
+        .unwrap_or_else(|| "provider default".into());
+    let answer = w.ask(&format!("Model (default: {}): ", current_model))?;
+    let model = answer.trim();
+    if !model.is_empty() {
+        config.get_provider_mut(provider).model = Some(model.to_string());
+    }
+
+    Ok(config)
+}
+
+/// The wizard on stdin/stdout. `offline` as `--offline`: listing models fails without a request.
+struct TerminalWizard {
+    offline: bool,
+}
+
+impl InitWizard for TerminalWizard {
+    fn ask(&mut self, question: &str) -> Result<String> {
+        print!("{}", question);
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input)
+    }
+
+    fn say(&mut self, text: &str) {
+        println!("{}", text);
+    }
+
+    async fn list_models(&mut self, config: &Config, provider: &str) -> Result<Vec<String>> {
+        let overrides = CliOverrides {
+            provider: Some(provider.to_string()),
+            ..Default::default()
+        };
+        let resolved = ResolvedConfig::new(&overrides, config, get_default_branch)?;
+        LlmClient::new(&resolved)?.with_offline(self.offline).list_models().await
+    }
+}
+
+pub async fn cmd_init_wizard(file: &Config, offline: bool) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        bail!(
+            "gitar init needs a terminal for the interactive setup. Use flags instead, e.g.\n  \
+             gitar init --provider openai --model gpt-4o --api-key-file ~/.secrets/openai_key"
+        );
+    }
+
+    let config = run_init_wizard(&mut TerminalWizard { offline }, file).await?;
+    config.save()?;
+
+    let path = Config::path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "(unknown)".into());
+    println!("\nSaved {}", path);
+    Ok(())
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    struct Scripted {
+        answers: VecDeque<&'static str>,
+        said: Vec<String>,
+        models: Result<Vec<String>, String>,
+        listed_with_key: Option<Option<String>>,
+    }
+
+    impl Scripted {
+        fn new(answers: &[&'static str]) -> Self {
+            Self {
+                answers: answers.iter().copied().collect(),
+                said: Vec::new(),
+                models: Ok(vec!["gpt-4o".into(), "gpt-4.1".into()]),
+                listed_with_key: None,
+            }
+        }
+    }
+
+    impl InitWizard for Scripted {
+        fn ask(&mut self, _question: &str) -> Result<String> {
+            Ok(self.answers.pop_front().expect("script ran out of answers").to_string())
+        }
+        fn say(&mut self, text: &str) {
+            self.said.push(text.to_string());
+        }
+        async fn list_models(&mut self, config: &Config, provider: &str) -> Result<Vec<String>> {
+            self.listed_with_key = Some(config.get_provider(provider).and_then(|pc| pc.api_key.clone()));
+            self.models.clone().map_err(|e| anyhow::anyhow!(e))
+        }
+    }
+
+    #[tokio::test]
+    async fn wizard_sets_provider_key_and_model() {
+        let mut w = Scripted::new(&["claude\n", "sk-ant-123\n", "\n", "claude-haiku-4-5\n"]);
+        let config = run_init_wizard(&mut w, &Config::default()).await.unwrap();
+
+        assert_eq!(config.default_provider.as_deref(), Some("claude"));
+        let pc = config.claude.unwrap();
+        assert_eq!(pc.api_key.as_deref(), Some("sk-ant-123"));
+        assert_eq!(pc.model.as_deref(), Some("claude-haiku-4-5"));
+        assert!(w.listed_with_key.is_none());
+    }
+
+    #[tokio::test]
+    async fn wizard_defaults_keep_existing_config() {
+        let file: Config =
+            toml::from_str("default_provider = \"groq\"\n[groq]\nmodel = \"llama\"\napi_key = \"gsk\"\n").unwrap();
+        let mut w = Scripted::new(&["\n", "\n", "n\n", "\n"]);
+        let config = run_init_wizard(&mut w, &file).await.unwrap();
+
+        assert_eq!(config.default_provider.as_deref(), Some("groq"));
+        let pc = config.groq.unwrap();
+        assert_eq!(pc.model.as_deref(), Some("llama"));
+        assert_eq!(pc.api_key.as_deref(), Some("gsk"));
+    }
+
+    #[tokio::test]
+    async fn wizard_reasks_unknown_provider_and_skips_key_for_ollama() {
+        // No API key question for ollama
+        let mut w = Scripted::new(&["nope\n", "local\n", "n\n", "llama3.2:latest\n"]);
+        let config = run_init_wizard(&mut w, &Config::default()).await.unwrap();
+
+        assert_eq!(config.default_provider.as_deref(), Some("ollama"));
+        assert_eq!(config.ollama.unwrap().model.as_deref(), Some("llama3.2:latest"));
+        assert!(w.said.iter().any(|s| s.contains("Unknown provider 'nope'")));
+        assert!(w.answers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn wizard_lists_models_with_entered_key() {
+        let mut w = Scripted::new(&["openai\n", "sk-1\n", "y\n", "gpt-4.1\n"]);
+        let config = run_init_wizard(&mut w, &Config::default()).await.unwrap();
+
+        assert_eq!(w.listed_with_key, Some(Some("sk-1".into())));
+        assert!(w.said.contains(&"  gpt-4.1".to_string()));
+        assert_eq!(config.openai.unwrap().model.as_deref(), Some("gpt-4.1"));
+    }
+
+    #[tokio::test]
+    async fn wizard_survives_model_list_failure() {
+        let mut w = Scripted::new(&["gemini\n", "\n", "Y\n", "\n"]);
+        w.models = Err("401 Unauthorized".into());
+        let config = run_init_wizard(&mut w, &Config::default()).await.unwrap();
+
+        assert!(w.said.iter().any(|s| s.starts_with("Could not list models: 401")));
+        assert_eq!(config.default_provider.as_deref(), Some("gemini"));
+        assert!(config.gemini.is_none());
+    }
+
+    #[test]
+    fn parse_provider_aliases() {
+        assert_eq!(parse_provider(" Anthropic "), Some("claude"));
+        assert_eq!(parse_provider("google"), Some("gemini"));
+        assert_eq!(parse_provider("groq"), Some("groq"));
+        assert_eq!(parse_provider("OpenRouter"), Some("openrouter"));
+        assert_eq!(parse_provider("mistral"), None);
+    }
+}
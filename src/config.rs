@@ -1,11 +1,24 @@
 // src/config.rs
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::diff::DEFAULT_ALG;
+use crate::git::get_git_config;
 
 /// Default max characters for diff context (~14k tokens at 3.5 chars/token)
 pub const DEFAULT_MAX_DIFF_CHARS: usize = 50_000;
 
+/// Idle connections are kept this long for the next request (e.g. across `history` calls)
+pub const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+
+/// Requests are sequential, so a couple of idle connections per host is plenty
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 2;
+
+/// `gitar models` reuses a cached list for a day (`--refresh` fetches anyway)
+pub const DEFAULT_MODELS_CACHE_TTL_SECS: u64 = 24 * 60 * 60;
+
 // =============================================================================
 // PROVIDER CONSTANTS
 // =============================================================================
@@ -13,7 +26,14 @@ pub const PROVIDER_OPENAI: &str = "https://api.openai.com/v1";
 pub const PROVIDER_CLAUDE: &str = "https://api.anthropic.com/v1";
 pub const PROVIDER_GEMINI: &str = "https://generativelanguage.googleapis.com";
 pub const PROVIDER_GROQ: &str = "https://api.groq.com/openai/v1";
+pub const PROVIDER_OPENROUTER: &str = "https://openrouter.ai/api/v1";
 pub const PROVIDER_OLLAMA: &str = "http://localhost:11434/v1";
+pub const PROVIDER_BEDROCK: &str = "https://bedrock-runtime.us-east-1.amazonaws.com";
+
+/// Bedrock runtime endpoint for an AWS region
+pub fn bedrock_url(region: &str) -> String {
+    format!("https://bedrock-runtime.{}.amazonaws.com", region)
+}
 
 pub fn provider_to_url(provider: &str) -> Option<&'static str> {
     match provider.to_lowercase().as_str() {
@@ -21,7 +41,9 @@ pub fn provider_to_url(provider: &str) -> Option<&'static str> {
         "claude" | "anthropic" => Some(PROVIDER_CLAUDE),
         "gemini" | "google" => Some(PROVIDER_GEMINI),
         "groq" => Some(PROVIDER_GROQ),
+        "openrouter" => Some(PROVIDER_OPENROUTER),
         "ollama" | "local" => Some(PROVIDER_OLLAMA),
+        "bedrock" | "aws" => Some(PROVIDER_BEDROCK),
         _ => None,
     }
 }
@@ -31,11 +53,14 @@ pub fn normalize_provider(provider: &str) -> &'static str {
         "anthropic" => "claude",
         "google" => "gemini",
         "local" => "ollama",
+        "aws" => "bedrock",
         "openai" => "openai",
         "claude" => "claude",
         "gemini" => "gemini",
         "groq" => "groq",
+        "openrouter" => "openrouter",
         "ollama" => "ollama",
+        "bedrock" => "bedrock",
         _ => "openai",
     }
 }
@@ -45,18 +70,23 @@ fn default_model_for_provider(provider: &str) -> &'static str {
         "claude" => "claude-sonnet-4-5-20250929",
         "gemini" => "gemini-2.5-flash",
         "groq" => "llama-3.3-70b-versatile",
+        // OpenRouter ids are `vendor/model`
+        "openrouter" => "openai/gpt-4o",
         "ollama" => "llama3.2:latest",
+        "bedrock" => "us.anthropic.claude-sonnet-4-5-20250929-v1:0",
         _ => "gpt-4o",
     }
 }
 
-fn env_var_for_provider(provider: &str) -> Option<&'static str> {
+pub fn env_var_for_provider(provider: &str) -> Option<&'static str> {
     match provider {
         "openai" => Some("OPENAI_API_KEY"),
         "claude" => Some("ANTHROPIC_API_KEY"),
         "gemini" => Some("GEMINI_API_KEY"),
         "groq" => Some("GROQ_API_KEY"),
-        "ollama" => None,
+        "openrouter" => Some("OPENROUTER_API_KEY"),
+        // Bedrock signs with AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY instead
+        "ollama" | "bedrock" => None,
         _ => Some("OPENAI_API_KEY"),
     }
 }
@@ -66,9 +96,38 @@ fn env_var_for_provider(provider: &str) -> Option<&'static str> {
 // =============================================================================
 pub const CONFIG_FILENAME: &str = ".gitar.toml";
 
+/// Env var that overrides the config file location
+pub const CONFIG_ENV_VAR: &str = "GITAR_CONFIG";
+
+/// Config path: $GITAR_CONFIG > $XDG_CONFIG_HOME/gitar/config.toml (if it exists, or
+/// there is no home directory) > ~/.gitar.toml
+pub fn resolve_config_path(
+    env_path: Option<String>,
+    xdg_config_home: Option<PathBuf>,
+    home: Option<PathBuf>,
+    exists: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    if let Some(p) = env_path.filter(|p| !p.trim().is_empty()) {
+        return Some(PathBuf::from(p.trim()));
+    }
+
+    let xdg = xdg_config_home
+        .filter(|d| !d.as_os_str().is_empty())
+        .map(|d| d.join("gitar").join("config.toml"));
+
+    match (xdg, home) {
+        (Some(x), Some(h)) => Some(if exists(&x) { x } else { h.join(CONFIG_FILENAME) }),
+        (Some(x), None) => Some(x),
+        (None, Some(h)) => Some(h.join(CONFIG_FILENAME)),
+        (None, None) => None,
+    }
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub api_key: Option<String>,
+    /// Read the API key from this file instead of storing it in the config
+    pub api_key_file: Option<String>,
     pub model: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
@@ -82,16 +141,54 @@ pub struct Config {
     pub base_branch: Option<String>,
     /// Maximum characters to include in diff context for LLM
     pub max_diff_chars: Option<usize>,
+    /// Above this many files, diffs escalate to Hunks/Semantic (0 = off)
+    pub max_diff_files: Option<usize>,
+    /// Diff lines longer than this are truncated before selection (0 = off)
+    pub max_diff_line_chars: Option<usize>,
+    /// Seconds an idle HTTP connection is kept for reuse (0 = don't keep)
+    pub pool_idle_timeout_secs: Option<u64>,
+    /// Idle HTTP connections kept per host
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Seconds a cached `gitar models` list stays fresh (0 = always fetch)
+    pub models_cache_ttl_secs: Option<u64>,
+    /// Per-command `--alg` defaults, e.g. `[diff]` `commit = 2`, `pr = 4`
+    pub diff: Option<BTreeMap<String, u8>>,
+    /// `--alg` for messages written by the prepare-commit-msg hook (`commit --write-to`)
+    pub hook_alg: Option<u8>,
+    /// `commit --ticket-from-branch` pattern (a regex), default `[A-Z]+-\d+`
+    pub ticket_pattern: Option<String>,
+    /// GPG-sign commits made by `gitar commit` with the default key
+    pub sign_commits: Option<bool>,
+    /// Gitmoji-style commit messages, like `--emoji`
+    pub emoji: Option<bool>,
+    /// Commit subjects are asked to stay under this many characters and cut past it
+    pub subject_max_len: Option<usize>,
+    /// Per-command model, e.g. `[models]` `history = "gpt-4o-mini"`, `pr = "gpt-4o"`
+    pub models: Option<BTreeMap<String, String>>,
+    /// Extra headers sent with every request, e.g. `[headers]` `X-Org-Id = "42"`
+    pub headers: Option<BTreeMap<String, String>>,
     pub openai: Option<ProviderConfig>,
     pub claude: Option<ProviderConfig>,
     pub gemini: Option<ProviderConfig>,
     pub groq: Option<ProviderConfig>,
+    pub openrouter: Option<ProviderConfig>,
     pub ollama: Option<ProviderConfig>,
+    pub bedrock: Option<ProviderConfig>,
 }
 
 impl Config {
     pub fn path() -> Option<PathBuf> {
-        dirs::home_dir().map(|h| h.join(CONFIG_FILENAME))
+        resolve_config_path(
+            std::env::var(CONFIG_ENV_VAR).ok(),
+            std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+            dirs::home_dir(),
+            |p| p.exists(),
+        )
+    }
+
+    /// `name` in the config file's directory, where gitar keeps its caches.
+    pub fn sibling_path(name: &str) -> Option<PathBuf> {
+        Self::path().and_then(|p| p.parent().map(|d| d.join(name)))
     }
 
     pub fn load() -> Self {
@@ -102,20 +199,66 @@ impl Config {
     }
 
     pub fn save(&self) -> Result<()> {
-        let path = Self::path().context("Could not determine home directory")?;
+        let path = Self::path()
+            .context("Could not determine config path (set GITAR_CONFIG or HOME)")?;
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
         let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
         std::fs::write(&path, content).context("Failed to write config file")?;
         println!("Config saved to: {}", path.display());
         Ok(())
     }
 
+    /// Diff algorithm for `command`: CLI flag > `[diff]` entry > DEFAULT_ALG.
+    /// Out-of-range config values are ignored.
+    pub fn alg_for(&self, command: &str, cli_alg: Option<u8>) -> u8 {
+        cli_alg
+            .or_else(|| {
+                self.diff
+                    .as_ref()
+                    .and_then(|d| d.get(command).copied())
+                    .filter(|a| *a <= 4)
+            })
+            .unwrap_or(DEFAULT_ALG)
+    }
+
+    /// Diff algorithm for `commit`. Hook runs (`hook`, i.e. `--write-to`) take `hook_alg`
+    /// before the `[diff]` entry; an explicit `--alg` still wins.
+    pub fn commit_alg(&self, cli_alg: Option<u8>, hook: bool) -> u8 {
+        let hook_alg = self.hook_alg.filter(|a| hook && *a <= 4);
+        self.alg_for("commit", cli_alg.or(hook_alg))
+    }
+
+    /// Model from the `[models]` table for `command`, if any.
+    pub fn model_for(&self, command: &str) -> Option<&String> {
+        self.models
+            .as_ref()
+            .and_then(|m| m.get(command))
+            .filter(|m| !m.trim().is_empty())
+    }
+
+    /// Signing for `git commit`: `--gpg-sign[=keyid]` > `--no-gpg-sign` > `sign_commits`.
+    /// `Some("")` signs with the default key.
+    pub fn sign_for(&self, cli_sign: Option<String>, no_sign: bool) -> Option<String> {
+        if cli_sign.is_some() {
+            return cli_sign;
+        }
+        if no_sign {
+            return None;
+        }
+        self.sign_commits.unwrap_or(false).then(String::new)
+    }
+
     pub fn get_provider(&self, name: &str) -> Option<&ProviderConfig> {
         match name {
             "openai" => self.openai.as_ref(),
             "claude" => self.claude.as_ref(),
             "gemini" => self.gemini.as_ref(),
             "groq" => self.groq.as_ref(),
+            "openrouter" => self.openrouter.as_ref(),
             "ollama" => self.ollama.as_ref(),
+            "bedrock" => self.bedrock.as_ref(),
             _ => None,
         }
     }
@@ -126,7 +269,9 @@ impl Config {
             "claude" => self.claude.get_or_insert_with(ProviderConfig::default),
             "gemini" => self.gemini.get_or_insert_with(ProviderConfig::default),
             "groq" => self.groq.get_or_insert_with(ProviderConfig::default),
+            "openrouter" => self.openrouter.get_or_insert_with(ProviderConfig::default),
             "ollama" => self.ollama.get_or_insert_with(ProviderConfig::default),
+            "bedrock" => self.bedrock.get_or_insert_with(ProviderConfig::default),
             _ => self.openai.get_or_insert_with(ProviderConfig::default),
         }
     }
@@ -135,6 +280,36 @@ impl Config {
 // =============================================================================
 // RESOLVED CONFIG
 // =============================================================================
+/// Env overrides, between CLI flags and the config file in precedence
+pub const ENV_PROVIDER: &str = "GITAR_PROVIDER";
+pub const ENV_MODEL: &str = "GITAR_MODEL";
+pub const ENV_MAX_TOKENS: &str = "GITAR_MAX_TOKENS";
+pub const ENV_TEMPERATURE: &str = "GITAR_TEMPERATURE";
+
+/// `git config` keys for the env overrides, read when the env var is unset:
+/// env > git config > config file
+pub const GIT_CONFIG_KEYS: &[(&str, &str)] = &[
+    (ENV_PROVIDER, "gitar.provider"),
+    (ENV_MODEL, "gitar.model"),
+    (ENV_MAX_TOKENS, "gitar.maxTokens"),
+    (ENV_TEMPERATURE, "gitar.temperature"),
+];
+
+/// `env` with `git_config` filling in the GITAR_* settings it doesn't have.
+fn env_or_git_config(
+    env: impl Fn(&str) -> Option<String>,
+    git_config: impl Fn(&str) -> Option<String>,
+) -> impl Fn(&str) -> Option<String> {
+    move |var| {
+        env(var).filter(|v| !v.trim().is_empty()).or_else(|| {
+            GIT_CONFIG_KEYS
+                .iter()
+                .find(|(env_var, _)| *env_var == var)
+                .and_then(|(_, key)| git_config(key))
+        })
+    }
+}
+
 pub struct ResolvedConfig {
     pub provider: String,
     pub api_key: Option<String>,
@@ -145,24 +320,68 @@ pub struct ResolvedConfig {
     pub base_branch: String,
     pub stream: bool,
     pub max_diff_chars: usize,
+    pub pool_idle_timeout_secs: u64,
+    pub pool_max_idle_per_host: usize,
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Settings given on the command line, ahead of env vars, git config and the file.
+/// Unset fields fall through.
+#[derive(Debug, Clone, Default)]
+pub struct CliOverrides {
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub base_url: Option<String>,
+    pub provider: Option<String>,
+    pub base_branch: Option<String>,
+    pub stream: Option<bool>,
+    pub api_key_file: Option<String>,
+    /// Subcommand being run, for its `[models]` entry
+    pub command: Option<String>,
 }
 
 impl ResolvedConfig {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        cli_api_key: Option<&String>,
-        cli_model: Option<&String>,
-        cli_max_tokens: Option<u32>,
-        cli_temperature: Option<f32>,
-        cli_base_url: Option<&String>,
-        cli_provider: Option<&String>,
-        cli_base_branch: Option<&String>,
-        cli_stream: Option<bool>,
+    pub fn new(cli: &CliOverrides, file: &Config, default_branch_fn: impl Fn() -> String) -> Result<Self> {
+        Self::new_with_env(
+            cli,
+            file,
+            default_branch_fn,
+            env_or_git_config(|var| std::env::var(var).ok(), get_git_config),
+        )
+    }
+
+    /// `new` with environment lookups injected (tests can't safely mutate process env).
+    pub fn new_with_env(
+        cli: &CliOverrides,
         file: &Config,
         default_branch_fn: impl Fn() -> String,
-    ) -> Self {
-        // Determine provider: CLI > config default > "openai"
-        let provider = cli_provider
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let env = |var: &str| env(var).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+
+        let env_max_tokens = match env(ENV_MAX_TOKENS) {
+            Some(v) => Some(
+                v.parse::<u32>()
+                    .with_context(|| format!("Invalid {}: {}", ENV_MAX_TOKENS, v))?,
+            ),
+            None => None,
+        };
+        let env_temperature = match env(ENV_TEMPERATURE) {
+            Some(v) => Some(
+                v.parse::<f32>()
+                    .with_context(|| format!("Invalid {}: {}", ENV_TEMPERATURE, v))?,
+            ),
+            None => None,
+        };
+
+        // Determine provider: CLI > GITAR_PROVIDER > config default > "openai"
+        let env_provider = env(ENV_PROVIDER);
+        let provider = cli
+            .provider
+            .as_ref()
+            .or(env_provider.as_ref())
             .map(|p| normalize_provider(p))
             .or_else(|| file.default_provider.as_ref().map(|p| normalize_provider(p)))
             .unwrap_or("openai")
@@ -170,52 +389,79 @@ impl ResolvedConfig {
 
         let provider_config = file.get_provider(&provider);
 
-        // Base URL: CLI > provider config > provider default
-        let base_url = cli_base_url
-            .cloned()
+        // Base URL: CLI > provider config > AWS_REGION (Bedrock) > provider default
+        let aws_region = || env("AWS_REGION").or_else(|| env("AWS_DEFAULT_REGION"));
+        let base_url = cli
+            .base_url
+            .clone()
             .or_else(|| provider_config.and_then(|p| p.base_url.clone()))
+            .or_else(|| (provider == "bedrock").then(aws_region).flatten().map(|r| bedrock_url(&r)))
             .unwrap_or_else(|| provider_to_url(&provider).unwrap_or(PROVIDER_OPENAI).to_string());
 
-        // API key: CLI > provider config > env var
-        let env_api_key = env_var_for_provider(&provider)
-            .and_then(|var| std::env::var(var).ok());
+        // API key: CLI key > CLI key file > provider config key file > provider config > env var
+        let env_api_key = env_var_for_provider(&provider).and_then(env);
+
+        let key_file = cli
+            .api_key_file
+            .clone()
+            .or_else(|| provider_config.and_then(|p| p.api_key_file.clone()));
 
-        let api_key = cli_api_key
-            .cloned()
-            .or_else(|| provider_config.and_then(|p| p.api_key.clone()))
-            .or(env_api_key);
+        let api_key = match (&cli.api_key, key_file) {
+            (Some(k), _) => Some(k.clone()),
+            (None, Some(path)) => Some(read_api_key_file(&expand_home(&path))?),
+            (None, None) => provider_config
+                .and_then(|p| p.api_key.clone())
+                .or(env_api_key),
+        };
 
-        // Model: CLI > provider config > provider default
-        let model = cli_model
-            .cloned()
+        // Model: CLI > GITAR_MODEL > [models] entry for the command > provider config > provider default
+        let model = cli
+            .model
+            .clone()
+            .or_else(|| env(ENV_MODEL))
+            .or_else(|| cli.command.as_deref().and_then(|c| file.model_for(c)).cloned())
             .or_else(|| provider_config.and_then(|p| p.model.clone()))
             .unwrap_or_else(|| default_model_for_provider(&provider).to_string());
 
-        // Max tokens: CLI > provider config > default
-        let max_tokens = cli_max_tokens
+        // Max tokens: CLI > GITAR_MAX_TOKENS > provider config > default
+        let max_tokens = cli
+            .max_tokens
+            .or(env_max_tokens)
             .or_else(|| provider_config.and_then(|p| p.max_tokens))
             .unwrap_or(500);
 
-        // Temperature: CLI > provider config > default
-        let temperature = cli_temperature
+        // Temperature: CLI > GITAR_TEMPERATURE > provider config > default
+        let temperature = cli
+            .temperature
+            .or(env_temperature)
             .or_else(|| provider_config.and_then(|p| p.temperature))
             .unwrap_or(0.5);
 
         // Base branch: CLI > config > git default
-        let base_branch = cli_base_branch
-            .cloned()
+        let base_branch = cli
+            .base_branch
+            .clone()
             .or_else(|| file.base_branch.clone())
             .unwrap_or_else(default_branch_fn);
 
         // Stream: CLI > provider config > default (false)
-        let stream = cli_stream
+        let stream = cli
+            .stream
             .or_else(|| provider_config.and_then(|p| p.stream))
             .unwrap_or(false);
 
         // Max diff chars: config > default
         let max_diff_chars = file.max_diff_chars.unwrap_or(DEFAULT_MAX_DIFF_CHARS);
 
-        Self {
+        // Connection pool: config > default
+        let pool_idle_timeout_secs = file
+            .pool_idle_timeout_secs
+            .unwrap_or(DEFAULT_POOL_IDLE_TIMEOUT_SECS);
+        let pool_max_idle_per_host = file
+            .pool_max_idle_per_host
+            .unwrap_or(DEFAULT_POOL_MAX_IDLE_PER_HOST);
+
+        Ok(Self {
             provider,
             api_key,
             model,
@@ -225,7 +471,36 @@ impl ResolvedConfig {
             base_branch,
             stream,
             max_diff_chars,
-        }
+            pool_idle_timeout_secs,
+            pool_max_idle_per_host,
+            headers: file.headers.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Header names whose values shouldn't be printed, like `Authorization` or `X-Api-Key`.
+pub fn is_secret_header(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["auth", "key", "token", "secret", "cookie", "password", "session"]
+        .iter()
+        .any(|s| name.contains(s))
+}
+
+/// Read an API key from a file (e.g. rendered by a secrets manager), trimming whitespace.
+pub fn read_api_key_file(path: &Path) -> Result<String> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read API key file: {}", path.display()))?;
+    let key = content.trim();
+    if key.is_empty() {
+        anyhow::bail!("API key file is empty: {}", path.display());
+    }
+    Ok(key.to_string())
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
     }
 }
 
@@ -252,8 +527,22 @@ mod tests {
             default_provider: Some("claude".into()),
             base_branch: Some("main".into()),
             max_diff_chars: Some(30000),
+            max_diff_files: None,
+            max_diff_line_chars: None,
+            pool_idle_timeout_secs: None,
+            pool_max_idle_per_host: None,
+            models_cache_ttl_secs: None,
+            diff: None,
+            hook_alg: None,
+            ticket_pattern: None,
+            sign_commits: None,
+            emoji: None,
+            subject_max_len: None,
+            models: None,
+            headers: None,
             openai: Some(ProviderConfig {
                 api_key: Some("sk-test123".into()),
+                api_key_file: None,
                 model: Some("gpt-4o".into()),
                 max_tokens: Some(1000),
                 temperature: Some(0.7),
@@ -263,7 +552,9 @@ mod tests {
             claude: None,
             gemini: None,
             groq: None,
+            openrouter: None,
             ollama: None,
+            bedrock: None,
         };
         let toml_str = toml::to_string(&config).unwrap();
         assert!(toml_str.contains("default_provider = \"claude\""));
@@ -312,7 +603,9 @@ mod tests {
         assert_eq!(provider_to_url("anthropic"), Some(PROVIDER_CLAUDE));
         assert_eq!(provider_to_url("gemini"), Some(PROVIDER_GEMINI));
         assert_eq!(provider_to_url("groq"), Some(PROVIDER_GROQ));
+        assert_eq!(provider_to_url("OpenRouter"), Some(PROVIDER_OPENROUTER));
         assert_eq!(provider_to_url("ollama"), Some(PROVIDER_OLLAMA));
+        assert_eq!(provider_to_url("aws"), Some(PROVIDER_BEDROCK));
         assert_eq!(provider_to_url("invalid"), None);
     }
 
@@ -321,7 +614,9 @@ mod tests {
         assert_eq!(normalize_provider("anthropic"), "claude");
         assert_eq!(normalize_provider("google"), "gemini");
         assert_eq!(normalize_provider("local"), "ollama");
+        assert_eq!(normalize_provider("aws"), "bedrock");
         assert_eq!(normalize_provider("CLAUDE"), "claude");
+        assert_eq!(normalize_provider("openrouter"), "openrouter");
     }
 
     #[test]
@@ -335,11 +630,12 @@ mod tests {
     fn resolved_config_uses_provider_defaults() {
         std::env::remove_var("OPENAI_API_KEY");
         let file = Config::default();
-        let provider = "openai".to_string();
         let resolved = ResolvedConfig::new(
-            None, None, None, None, None, Some(&provider), None, None,
-            &file, || "main".into(),
-        );
+            &CliOverrides { provider: Some("openai".into()), ..Default::default() },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
         assert_eq!(resolved.provider, "openai");
         assert_eq!(resolved.model, "gpt-4o");
         assert_eq!(resolved.base_url, PROVIDER_OPENAI);
@@ -353,19 +649,60 @@ mod tests {
             max_diff_chars: Some(25000),
             ..Default::default()
         };
-        let resolved = ResolvedConfig::new(
-            None, None, None, None, None, None, None, None,
-            &file, || "main".into(),
-        );
+        let resolved = ResolvedConfig::new(&CliOverrides::default(), &file, || "main".into()).unwrap();
         assert_eq!(resolved.max_diff_chars, 25000);
     }
 
+    #[test]
+    fn resolved_config_pool_settings() {
+        let resolved = ResolvedConfig::new(
+            &CliOverrides::default(),
+            &Config::default(),
+            || "main".into(),
+        )
+        .unwrap();
+        assert_eq!(resolved.pool_idle_timeout_secs, DEFAULT_POOL_IDLE_TIMEOUT_SECS);
+        assert_eq!(resolved.pool_max_idle_per_host, DEFAULT_POOL_MAX_IDLE_PER_HOST);
+
+        let file: Config = toml::from_str("pool_idle_timeout_secs = 5\npool_max_idle_per_host = 8\n").unwrap();
+        let resolved = ResolvedConfig::new(&CliOverrides::default(), &file, || "main".into()).unwrap();
+        assert_eq!(resolved.pool_idle_timeout_secs, 5);
+        assert_eq!(resolved.pool_max_idle_per_host, 8);
+    }
+
+    #[test]
+    fn resolved_config_headers() {
+        let resolved = ResolvedConfig::new(
+            &CliOverrides::default(),
+            &Config::default(),
+            || "main".into(),
+        )
+        .unwrap();
+        assert!(resolved.headers.is_empty());
+
+        let file: Config = toml::from_str("[headers]\nX-Org-Id = \"42\"\nX-Team = \"infra\"\n").unwrap();
+        let resolved = ResolvedConfig::new(&CliOverrides::default(), &file, || "main".into()).unwrap();
+        assert_eq!(resolved.headers.get("X-Org-Id").map(String::as_str), Some("42"));
+        assert_eq!(resolved.headers.len(), 2);
+    }
+
+    #[test]
+    fn secret_headers_by_name() {
+        assert!(is_secret_header("Authorization"));
+        assert!(is_secret_header("X-Api-Key"));
+        assert!(is_secret_header("X-Gateway-Token"));
+        assert!(is_secret_header("Cookie"));
+        assert!(!is_secret_header("X-Org-Id"));
+        assert!(!is_secret_header("X-Title"));
+    }
+
     #[test]
     fn resolved_config_uses_provider_config() {
         std::env::remove_var("ANTHROPIC_API_KEY");
         let file = Config {
             claude: Some(ProviderConfig {
                 api_key: Some("sk-ant-test".into()),
+                api_key_file: None,
                 model: Some("claude-opus-4-5-20251101".into()),
                 max_tokens: Some(2000),
                 temperature: Some(0.8),
@@ -374,11 +711,12 @@ mod tests {
             }),
             ..Default::default()
         };
-        let provider = "claude".to_string();
         let resolved = ResolvedConfig::new(
-            None, None, None, None, None, Some(&provider), None, None,
-            &file, || "main".into(),
-        );
+            &CliOverrides { provider: Some("claude".into()), ..Default::default() },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
         assert_eq!(resolved.provider, "claude");
         assert_eq!(resolved.api_key, Some("sk-ant-test".into()));
         assert_eq!(resolved.model, "claude-opus-4-5-20251101");
@@ -397,14 +735,20 @@ mod tests {
             }),
             ..Default::default()
         };
-        let provider = "openai".to_string();
-        let cli_key = "cli-key".to_string();
-        let cli_model = "gpt-4o-mini".to_string();
         let resolved = ResolvedConfig::new(
-            Some(&cli_key), Some(&cli_model), Some(500), Some(0.9),
-            None, Some(&provider), None, Some(false),
-            &file, || "main".into(),
-        );
+            &CliOverrides {
+                api_key: Some("cli-key".into()),
+                model: Some("gpt-4o-mini".into()),
+                max_tokens: Some(500),
+                temperature: Some(0.9),
+                provider: Some("openai".into()),
+                stream: Some(false),
+                ..Default::default()
+            },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
         assert_eq!(resolved.api_key, Some("cli-key".into()));
         assert_eq!(resolved.model, "gpt-4o-mini");
         assert!(!resolved.stream);
@@ -421,10 +765,7 @@ mod tests {
             }),
             ..Default::default()
         };
-        let resolved = ResolvedConfig::new(
-            None, None, None, None, None, None, None, None,
-            &file, || "main".into(),
-        );
+        let resolved = ResolvedConfig::new(&CliOverrides::default(), &file, || "main".into()).unwrap();
         assert_eq!(resolved.provider, "gemini");
         assert_eq!(resolved.api_key, Some("gemini-key".into()));
     }
@@ -432,10 +773,7 @@ mod tests {
     #[test]
     fn resolved_config_stream_defaults_to_false() {
         let file = Config::default();
-        let resolved = ResolvedConfig::new(
-            None, None, None, None, None, None, None, None,
-            &file, || "main".into(),
-        );
+        let resolved = ResolvedConfig::new(&CliOverrides::default(), &file, || "main".into()).unwrap();
         assert!(!resolved.stream);
     }
 
@@ -448,11 +786,12 @@ mod tests {
             }),
             ..Default::default()
         };
-        let provider = "openai".to_string();
         let resolved = ResolvedConfig::new(
-            None, None, None, None, None, Some(&provider), None, None,
-            &file, || "main".into(),
-        );
+            &CliOverrides { provider: Some("openai".into()), ..Default::default() },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
         assert!(resolved.stream);
     }
 
@@ -465,15 +804,353 @@ mod tests {
             }),
             ..Default::default()
         };
-        let provider = "openai".to_string();
         let resolved = ResolvedConfig::new(
-            None, None, None, None, None, Some(&provider), None, Some(true),
-            &file, || "main".into(),
-        );
+            &CliOverrides {
+                provider: Some("openai".into()),
+                stream: Some(true),
+                ..Default::default()
+            },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
         assert!(resolved.stream);
     }
-}
 
+    fn temp_key_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("gitar_{}_{}", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_api_key_file_trims_whitespace() {
+        let path = temp_key_file("key_trim", "  sk-from-file\n\n");
+        assert_eq!(read_api_key_file(&path).unwrap(), "sk-from-file");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_api_key_file_missing_errors_clearly() {
+        let path = std::env::temp_dir().join("gitar_definitely_missing_key_file");
+        let err = read_api_key_file(&path).unwrap_err().to_string();
+        assert!(err.contains("Failed to read API key file"));
+        assert!(err.contains("gitar_definitely_missing_key_file"));
+    }
 
+    #[test]
+    fn read_api_key_file_empty_errors() {
+        let path = temp_key_file("key_empty", " \n");
+        assert!(read_api_key_file(&path).unwrap_err().to_string().contains("empty"));
+        std::fs::remove_file(&path).unwrap();
+    }
 
+    #[test]
+    fn resolved_config_api_key_file_precedence() {
+        let path = temp_key_file("key_precedence", "sk-file\n");
+        let path_str = path.display().to_string();
+        let file = Config {
+            openai: Some(ProviderConfig {
+                api_key: Some("sk-config".into()),
+                api_key_file: Some(path_str.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Config key file beats the raw config key
+        let resolved = ResolvedConfig::new(
+            &CliOverrides { provider: Some("openai".into()), ..Default::default() },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
+        assert_eq!(resolved.api_key.as_deref(), Some("sk-file"));
+
+        // CLI key beats any key file
+        let resolved = ResolvedConfig::new(
+            &CliOverrides {
+                api_key: Some("sk-cli".into()),
+                provider: Some("openai".into()),
+                api_key_file: Some(path_str.clone()),
+                ..Default::default()
+            },
+            &file,
+            || "main".into(),
+        )
+        .unwrap();
+        assert_eq!(resolved.api_key.as_deref(), Some("sk-cli"));
+
+        // A missing CLI key file is an error, not a silent fallback
+        assert!(ResolvedConfig::new(
+            &CliOverrides {
+                provider: Some("openai".into()),
+                api_key_file: Some("/nonexistent/gitar/key".into()),
+                ..Default::default()
+            },
+            &file,
+            || "main".into(),
+        )
+        .is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
 
+    #[test]
+    fn config_path_env_var_wins() {
+        let path = resolve_config_path(
+            Some("/etc/gitar.toml".into()),
+            Some("/xdg".into()),
+            Some("/home/u".into()),
+            |_| true,
+        );
+        assert_eq!(path, Some(PathBuf::from("/etc/gitar.toml")));
+    }
+
+    #[test]
+    fn config_path_xdg_when_present() {
+        let path = resolve_config_path(None, Some("/xdg".into()), Some("/home/u".into()), |_| true);
+        assert_eq!(path, Some(PathBuf::from("/xdg/gitar/config.toml")));
+    }
+
+    #[test]
+    fn config_path_home_when_xdg_file_missing() {
+        let path = resolve_config_path(None, Some("/xdg".into()), Some("/home/u".into()), |_| false);
+        assert_eq!(path, Some(PathBuf::from("/home/u/.gitar.toml")));
+        let path = resolve_config_path(Some("  ".into()), None, Some("/home/u".into()), |_| true);
+        assert_eq!(path, Some(PathBuf::from("/home/u/.gitar.toml")));
+    }
+
+    #[test]
+    fn config_path_without_home() {
+        let path = resolve_config_path(None, Some("/xdg".into()), None, |_| false);
+        assert_eq!(path, Some(PathBuf::from("/xdg/gitar/config.toml")));
+        assert_eq!(resolve_config_path(None, None, None, |_| true), None);
+    }
+
+    #[test]
+    fn alg_for_resolution_order() {
+        let config: Config = toml::from_str("[diff]\ncommit = 2\npr = 4\n").unwrap();
+        // CLI flag wins
+        assert_eq!(config.alg_for("commit", Some(3)), 3);
+        // then the per-command entry
+        assert_eq!(config.alg_for("commit", None), 2);
+        assert_eq!(config.alg_for("pr", None), 4);
+        // then the global default
+        assert_eq!(config.alg_for("history", None), DEFAULT_ALG);
+        assert_eq!(Config::default().alg_for("commit", None), DEFAULT_ALG);
+    }
+
+    #[test]
+    fn sign_for_resolution_order() {
+        let config: Config = toml::from_str("sign_commits = true\n").unwrap();
+        assert_eq!(config.sign_for(None, false), Some(String::new()));
+        assert_eq!(config.sign_for(Some("ABCD".into()), false), Some("ABCD".into()));
+        assert_eq!(config.sign_for(None, true), None);
+        assert_eq!(Config::default().sign_for(None, false), None);
+        assert_eq!(Config::default().sign_for(Some(String::new()), false), Some(String::new()));
+    }
+
+    #[test]
+    fn commit_alg_uses_hook_alg_for_hook_runs() {
+        let config: Config = toml::from_str("hook_alg = 2\n[diff]\ncommit = 3\n").unwrap();
+        assert_eq!(config.commit_alg(None, true), 2);
+        assert_eq!(config.commit_alg(None, false), 3);
+        assert_eq!(config.commit_alg(Some(1), true), 1);
+
+        let config: Config = toml::from_str("hook_alg = 9\n").unwrap();
+        assert_eq!(config.commit_alg(None, true), DEFAULT_ALG);
+        assert_eq!(Config::default().commit_alg(None, true), DEFAULT_ALG);
+    }
+
+    #[test]
+    fn alg_for_ignores_out_of_range_config() {
+        let config: Config = toml::from_str("[diff]\ncommit = 9\n").unwrap();
+        assert_eq!(config.alg_for("commit", None), DEFAULT_ALG);
+    }
+
+    fn env_of(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |k| vars.iter().find(|(n, _)| *n == k).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn env_vars_override_file_config() {
+        let file: Config = toml::from_str(
+            "default_provider = \"openai\"\n[openai]\nmodel = \"gpt-4o\"\nmax_tokens = 100\ntemperature = 0.1\n",
+        )
+        .unwrap();
+        let env = env_of(&[
+            (ENV_PROVIDER, "groq"),
+            (ENV_MODEL, "llama-3.3-70b"),
+            (ENV_MAX_TOKENS, "2048"),
+            (ENV_TEMPERATURE, "0.9"),
+        ]);
+        let resolved = ResolvedConfig::new_with_env(
+            &CliOverrides::default(),
+            &file,
+            || "main".into(),
+            env,
+        )
+        .unwrap();
+        assert_eq!(resolved.provider, "groq");
+        assert_eq!(resolved.model, "llama-3.3-70b");
+        assert_eq!(resolved.max_tokens, 2048);
+        assert_eq!(resolved.temperature, 0.9);
+    }
+
+    #[test]
+    fn git_config_between_env_and_file() {
+        let file: Config =
+            toml::from_str("default_provider = \"openai\"\n[openai]\nmodel = \"gpt-4o\"\n").unwrap();
+        let git = |key: &str| match key {
+            "gitar.provider" => Some("groq".to_string()),
+            "gitar.model" => Some("llama-3.1-8b".to_string()),
+            "gitar.maxTokens" => Some("900".to_string()),
+            _ => None,
+        };
+        let resolve = |model: Option<&str>, env: &'static [(&'static str, &'static str)]| {
+            ResolvedConfig::new_with_env(
+                &CliOverrides { model: model.map(String::from), ..Default::default() },
+                &file,
+                || "main".into(),
+                env_or_git_config(env_of(env), git),
+            )
+            .unwrap()
+        };
+
+        // git config beats the file
+        let resolved = resolve(None, &[]);
+        assert_eq!(resolved.provider, "groq");
+        assert_eq!(resolved.model, "llama-3.1-8b");
+        assert_eq!(resolved.max_tokens, 900);
+        assert_eq!(resolved.temperature, 0.5);
+
+        // env beats git config, CLI beats both
+        let resolved = resolve(None, &[(ENV_MODEL, "llama-3.3-70b")]);
+        assert_eq!(resolved.model, "llama-3.3-70b");
+        assert_eq!(resolve(Some("gpt-4.1"), &[(ENV_MODEL, "llama-3.3-70b")]).model, "gpt-4.1");
+    }
+
+    #[test]
+    fn git_config_only_for_gitar_settings() {
+        let env = env_or_git_config(env_of(&[(ENV_MODEL, " ")]), |key| Some(format!("from {}", key)));
+        assert_eq!(env(ENV_MODEL).as_deref(), Some("from gitar.model"));
+        assert_eq!(env(ENV_TEMPERATURE).as_deref(), Some("from gitar.temperature"));
+        assert_eq!(env("OPENAI_API_KEY"), None);
+    }
+
+    #[test]
+    fn command_model_resolution_order() {
+        let file: Config = toml::from_str(
+            "[openai]\nmodel = \"gpt-4o\"\n[models]\nhistory = \"gpt-4o-mini\"\npr = \"gpt-4.1\"\n",
+        )
+        .unwrap();
+        let resolve = |model: Option<&str>, command: Option<&str>, env: &'static [(&'static str, &'static str)]| {
+            ResolvedConfig::new_with_env(
+                &CliOverrides {
+                    model: model.map(String::from),
+                    command: command.map(String::from),
+                    ..Default::default()
+                },
+                &file,
+                || "main".into(),
+                env_of(env),
+            )
+            .unwrap()
+            .model
+        };
+
+        // [models] entry beats the provider model
+        assert_eq!(resolve(None, Some("history"), &[]), "gpt-4o-mini");
+        assert_eq!(resolve(None, Some("pr"), &[]), "gpt-4.1");
+        // commands without an entry keep the provider model
+        assert_eq!(resolve(None, Some("commit"), &[]), "gpt-4o");
+        assert_eq!(resolve(None, None, &[]), "gpt-4o");
+        // GITAR_MODEL and --model still win
+        assert_eq!(resolve(None, Some("history"), &[(ENV_MODEL, "env-model")]), "env-model");
+        assert_eq!(resolve(Some("cli-model"), Some("history"), &[(ENV_MODEL, "env-model")]), "cli-model");
+    }
+
+    #[test]
+    fn env_model_applies_to_file_provider() {
+        let file: Config = toml::from_str("[openai]\nmodel = \"gpt-4o\"\n").unwrap();
+        let resolved = ResolvedConfig::new_with_env(
+            &CliOverrides::default(),
+            &file,
+            || "main".into(),
+            env_of(&[(ENV_MODEL, "gpt-4.1")]),
+        )
+        .unwrap();
+        assert_eq!(resolved.provider, "openai");
+        assert_eq!(resolved.model, "gpt-4.1");
+    }
+
+    #[test]
+    fn bedrock_base_url_from_aws_region() {
+        let file: Config = toml::from_str("default_provider = \"aws\"\n").unwrap();
+        let resolve = |env: &'static [(&'static str, &'static str)]| {
+            ResolvedConfig::new_with_env(
+                &CliOverrides::default(),
+                &file,
+                || "main".into(),
+                env_of(env),
+            )
+            .unwrap()
+        };
+
+        let resolved = resolve(&[("AWS_REGION", "eu-central-1")]);
+        assert_eq!(resolved.provider, "bedrock");
+        assert_eq!(resolved.base_url, "https://bedrock-runtime.eu-central-1.amazonaws.com");
+        assert!(resolved.api_key.is_none());
+
+        assert_eq!(resolve(&[("AWS_DEFAULT_REGION", "us-west-2")]).base_url, bedrock_url("us-west-2"));
+        assert_eq!(resolve(&[]).base_url, PROVIDER_BEDROCK);
+    }
+
+    #[test]
+    fn cli_flags_override_env_vars() {
+        let env = env_of(&[
+            (ENV_PROVIDER, "groq"),
+            (ENV_MODEL, "env-model"),
+            (ENV_MAX_TOKENS, "2048"),
+            (ENV_TEMPERATURE, "0.9"),
+        ]);
+        let resolved = ResolvedConfig::new_with_env(
+            &CliOverrides {
+                model: Some("cli-model".into()),
+                max_tokens: Some(64),
+                temperature: Some(0.2),
+                provider: Some("claude".into()),
+                ..Default::default()
+            },
+            &Config::default(),
+            || "main".into(),
+            env,
+        )
+        .unwrap();
+        assert_eq!(resolved.provider, "claude");
+        assert_eq!(resolved.model, "cli-model");
+        assert_eq!(resolved.max_tokens, 64);
+        assert_eq!(resolved.temperature, 0.2);
+    }
+
+    #[test]
+    fn env_blank_values_ignored_and_bad_numbers_rejected() {
+        let resolved = ResolvedConfig::new_with_env(
+            &CliOverrides::default(),
+            &Config::default(),
+            || "main".into(),
+            env_of(&[(ENV_MODEL, "  ")]),
+        )
+        .unwrap();
+        assert_eq!(resolved.model, default_model_for_provider("openai"));
+
+        let err = ResolvedConfig::new_with_env(
+            &CliOverrides::default(),
+            &Config::default(),
+            || "main".into(),
+            env_of(&[(ENV_MAX_TOKENS, "lots")]),
+        );
+        assert!(err.is_err());
+    }
+}
\ No newline at end of file
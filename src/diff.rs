@@ -6,7 +6,8 @@
 // 3 - Hunks:    Selective hunks, ranked by importance
 // 4 - Semantic: JSON IR with scored hunks (token-efficient)
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
 /// Estimated tokens ≈ chars / 3.5 for code (conservative)
 const CHARS_PER_TOKEN: f32 = 3.5;
@@ -59,6 +60,73 @@ const EXCLUDE_PATTERNS: &[&str] = &[
     "generated",
 ];
 
+/// Languages gitar can tell apart by extension, with their entry-point file names
+const LANGUAGES: &[Language] = &[
+    Language { name: "rust", extensions: &[".rs"], entry_points: &["main.rs", "lib.rs"] },
+    Language { name: "python", extensions: &[".py"], entry_points: &["__main__.py", "__init__.py", "main.py"] },
+    Language { name: "typescript", extensions: &[".ts", ".tsx"], entry_points: &["index.ts", "index.tsx"] },
+    Language { name: "javascript", extensions: &[".js", ".jsx", ".mjs"], entry_points: &["index.js", "main.js"] },
+    Language { name: "go", extensions: &[".go"], entry_points: &["main.go"] },
+    Language { name: "java", extensions: &[".java"], entry_points: &["Main.java", "Application.java"] },
+];
+
+/// Added to files in the diff's primary language
+const PRIMARY_LANGUAGE_BOOST: i32 = 15;
+
+/// Entry points of the primary language rank with `main.rs`/`lib.rs`
+const ENTRY_POINT_PRIORITY: i32 = 100;
+
+/// Priority of files left out of the LLM context (lock files, build output, generated code)
+const EXCLUDED_PRIORITY: i32 = -100;
+
+/// `--alg` value that selects the algorithm from the diff size
+pub const ALG_AUTO: u8 = 0;
+
+/// `--alg` used when neither the CLI nor the `[diff]` config sets one (Semantic)
+pub const DEFAULT_ALG: u8 = 4;
+
+/// Auto mode uses Files up to this multiple of max_chars, Semantic beyond it
+pub const AUTO_FILES_MAX_RATIO: usize = 3;
+
+/// Above this many files, Full/Files escalate to Hunks (and to Semantic past twice as many)
+pub const DEFAULT_MAX_DIFF_FILES: usize = 50;
+
+/// Diff lines longer than this (minified bundles, data blobs) are cut in file chunks
+pub const DEFAULT_MAX_LINE_CHARS: usize = 2000;
+
+/// How diffs are fetched and shaped for the model, from the global flags and the config.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// `--context`: unified lines around each change
+    pub context: u32,
+    /// `--word-diff`: diffs come from `git diff --word-diff=porcelain`
+    pub word_diff: bool,
+    /// `max_diff_chars` (`diff --max-chars`): budget for the shaped diff
+    pub max_chars: usize,
+    /// `--changed-only`: semantic (alg 4) hunk previews keep only +/- lines
+    pub changed_only: bool,
+    /// `--max-files`: the Files algorithm includes at most this many files. 0 = no cap.
+    pub max_files: usize,
+    /// `max_diff_files`: more files than this escalate the algorithm. 0 = never.
+    pub max_diff_files: usize,
+    /// `max_diff_line_chars`: longer lines are cut in file chunks. 0 = keep them whole.
+    pub max_line_chars: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context: crate::git::DEFAULT_DIFF_CONTEXT,
+            word_diff: false,
+            max_chars: crate::config::DEFAULT_MAX_DIFF_CHARS,
+            changed_only: false,
+            max_files: 0,
+            max_diff_files: DEFAULT_MAX_DIFF_FILES,
+            max_line_chars: DEFAULT_MAX_LINE_CHARS,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DiffAlg {
     Full = 1,     // Complete git diff
@@ -78,6 +146,45 @@ impl DiffAlg {
         }
     }
 
+    /// `--alg 0`: pick by how far the raw diff is over the budget.
+    /// Fits -> Full, up to AUTO_FILES_MAX_RATIO x budget -> Files, larger -> Semantic.
+    pub fn auto(raw_len: usize, max_chars: usize) -> Self {
+        if raw_len <= max_chars {
+            Self::Full
+        } else if raw_len <= max_chars.saturating_mul(AUTO_FILES_MAX_RATIO) {
+            Self::Files
+        } else {
+            Self::Semantic
+        }
+    }
+
+    /// Resolve a CLI `--alg` value, where 0 means auto.
+    pub fn resolve(n: u8, raw_len: usize, max_chars: usize) -> Self {
+        if n == ALG_AUTO {
+            Self::auto(raw_len, max_chars)
+        } else {
+            Self::from_num(n)
+        }
+    }
+
+    /// Lift the algorithm when a diff touches too many files, whatever its size:
+    /// over `max_files` -> at least Hunks, over 2x -> Semantic. 0 disables.
+    pub fn escalate(self, files: usize, max_files: usize) -> Self {
+        if max_files == 0 || files <= max_files {
+            return self;
+        }
+        let floor = if files > max_files.saturating_mul(2) {
+            Self::Semantic
+        } else {
+            Self::Hunks
+        };
+        if self.num() >= floor.num() {
+            self
+        } else {
+            floor
+        }
+    }
+
     pub fn num(&self) -> u8 {
         *self as u8
     }
@@ -92,6 +199,24 @@ impl DiffAlg {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Language {
+    pub name: &'static str,
+    extensions: &'static [&'static str],
+    entry_points: &'static [&'static str],
+}
+
+impl Language {
+    fn matches(&self, path: &str) -> bool {
+        self.extensions.iter().any(|ext| path.ends_with(ext))
+    }
+
+    fn is_entry_point(&self, path: &str) -> bool {
+        let file = path.rsplit('/').next().unwrap_or(path);
+        self.entry_points.contains(&file)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FileChunk {
     pub path: String,
@@ -101,6 +226,14 @@ pub struct FileChunk {
     pub lines_removed: usize,
 }
 
+/// One line of `git diff --numstat`. Binary files (`-` columns) have no counts.
+#[derive(Debug, PartialEq)]
+pub struct NumstatEntry {
+    pub path: String,
+    pub added: Option<usize>,
+    pub removed: Option<usize>,
+}
+
 #[derive(Debug)]
 pub struct DiffStats {
     pub total_files: usize,
@@ -111,19 +244,31 @@ pub struct DiffStats {
     pub estimated_tokens: usize,
     pub truncated: bool,
     pub algorithm: DiffAlg,
+    /// Algorithm asked for, when the file count forced a different one
+    pub escalated_from: Option<DiffAlg>,
 }
 
 impl DiffStats {
-    pub fn display(&self) -> String {
-        let reduction_pct = if self.total_chars > 0 {
+    /// Share of the raw diff left out, in percent
+    pub fn reduction_pct(&self) -> f64 {
+        if self.total_chars > 0 {
             (1.0 - self.output_chars as f64 / self.total_chars as f64) * 100.0
         } else {
             0.0
+        }
+    }
+
+    pub fn display(&self) -> String {
+        let reduction_pct = self.reduction_pct();
+
+        let escalated = match self.escalated_from {
+            Some(from) => format!(" (escalated from {}: {} files)", from.num(), self.total_files),
+            None => String::new(),
         };
 
         format!(
             "╭─ Diff Stats ─────────────────────────────────╮\n\
-             │ Algorithm:  {} - {}\n\
+             │ Algorithm:  {} - {}{}\n\
              │ Files:      {}/{} included ({} excluded)\n\
              │ Chars:      {} → {} ({:.1}% reduction)\n\
              │ Est Tokens: ~{}\n\
@@ -131,6 +276,7 @@ impl DiffStats {
              ╰──────────────────────────────────────────────╯",
             self.algorithm.num(),
             self.algorithm.name(),
+            escalated,
             self.included_files,
             self.total_files,
             self.excluded_files,
@@ -143,13 +289,60 @@ impl DiffStats {
     }
 }
 
-/// Split raw diff into file chunks
-pub fn split_diff_by_file(raw_diff: &str) -> Vec<FileChunk> {
+/// Number of files in a raw diff
+pub fn count_diff_files(raw_diff: &str) -> usize {
+    raw_diff.lines().filter(|l| l.starts_with("diff --git")).count()
+}
+
+/// Resolve `--alg` for a diff, escalating on file count unless Full was asked for
+/// explicitly. Returns the algorithm and, if escalated, the one it replaced.
+pub fn pick_alg(n: u8, raw_diff: &str, opts: &DiffOptions) -> (DiffAlg, Option<DiffAlg>) {
+    let alg = DiffAlg::resolve(n, raw_diff.len(), opts.max_chars);
+    if n == DiffAlg::Full.num() {
+        return (alg, None);
+    }
+    let escalated = alg.escalate(count_diff_files(raw_diff), opts.max_diff_files);
+    (escalated, (escalated != alg).then_some(alg))
+}
+
+/// `git diff --word-diff=porcelain` output: inside hunks each changed word run is its
+/// own `+`/`-` line and a lone `~` ends a line of text.
+pub fn is_word_diff(raw_diff: &str) -> bool {
+    let mut in_hunk = false;
+    raw_diff.lines().any(|l| {
+        if l.starts_with("diff --git") {
+            in_hunk = false;
+        } else if l.starts_with("@@") {
+            in_hunk = true;
+        }
+        in_hunk && l == "~"
+    })
+}
+
+/// Cut `line` to `max_chars` characters, noting how much was dropped. 0 = no limit.
+fn truncate_long_line(line: &str, max_chars: usize) -> Cow<'_, str> {
+    if max_chars == 0 || line.len() <= max_chars {
+        return Cow::Borrowed(line);
+    }
+    match line.char_indices().nth(max_chars) {
+        Some((end, _)) => {
+            let dropped = line[end..].chars().count();
+            Cow::Owned(format!("{} [... {} chars truncated]", &line[..end], dropped))
+        }
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// Split raw diff into file chunks. Lines past `max_line_chars` are truncated (0 = no limit).
+pub fn split_diff_by_file(raw_diff: &str, max_line_chars: usize) -> Vec<FileChunk> {
     let mut chunks = Vec::new();
     let mut current_path = String::new();
     let mut current_content = String::new();
     let mut lines_added = 0usize;
     let mut lines_removed = 0usize;
+    // Word diffs count a text line once, however many word runs changed in it
+    let word_diff = is_word_diff(raw_diff);
+    let (mut line_added, mut line_removed) = (false, false);
 
     for line in raw_diff.lines() {
         if line.starts_with("diff --git") {
@@ -171,13 +364,21 @@ pub fn split_diff_by_file(raw_diff: &str) -> Vec<FileChunk> {
             lines_added = 0;
             lines_removed = 0;
         } else {
-            current_content.push_str(line);
+            current_content.push_str(&truncate_long_line(line, max_line_chars));
             current_content.push('\n');
 
-            if line.starts_with('+') && !line.starts_with("+++") {
-                lines_added += 1;
-            } else if line.starts_with('-') && !line.starts_with("---") {
-                lines_removed += 1;
+            let added = line.starts_with('+') && !line.starts_with("+++");
+            let removed = line.starts_with('-') && !line.starts_with("---");
+            if !word_diff {
+                lines_added += added as usize;
+                lines_removed += removed as usize;
+            } else if line == "~" {
+                lines_added += line_added as usize;
+                lines_removed += line_removed as usize;
+                (line_added, line_removed) = (false, false);
+            } else {
+                line_added |= added;
+                line_removed |= removed;
             }
         }
     }
@@ -197,26 +398,197 @@ pub fn split_diff_by_file(raw_diff: &str) -> Vec<FileChunk> {
     chunks
 }
 
+/// Paths in a raw diff that are not already excluded by name: the ones worth a
+/// `.gitattributes` lookup.
+pub fn selectable_paths(raw_diff: &str) -> Vec<String> {
+    raw_diff
+        .lines()
+        .filter(|l| l.starts_with("diff --git"))
+        .filter_map(|l| l.split(" b/").last())
+        .filter(|p| calculate_priority(p) > EXCLUDED_PRIORITY)
+        .map(String::from)
+        .collect()
+}
+
+/// Parse `git diff --numstat` output. Renames are keyed by their new path.
+pub fn parse_numstat(output: &str) -> Vec<NumstatEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.splitn(3, '\t');
+            let (added, removed, path) = (cols.next()?, cols.next()?, cols.next()?);
+            let count = |c: &str| if c == "-" { Ok(None) } else { c.parse().map(Some) };
+            Some(NumstatEntry {
+                path: numstat_path(path),
+                added: count(added).ok()?,
+                removed: count(removed).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// New path of a numstat path column: `old => new` or `src/{old => new}/file.rs`.
+fn numstat_path(col: &str) -> String {
+    if let (Some(open), Some(close)) = (col.find('{'), col.rfind('}')) {
+        if let Some((_, new)) = col[open + 1..close].split_once(" => ") {
+            // `{ => sub}` and `{sub => }` leave a doubled slash
+            return format!("{}{}{}", &col[..open], new, &col[close + 1..]).replace("//", "/");
+        }
+    }
+    match col.split_once(" => ") {
+        Some((_, new)) => new.to_string(),
+        None => col.to_string(),
+    }
+}
+
+/// Replace the patch-parsed line counts with numstat's. Entries are matched to chunks
+/// by path, in order (a combined staged + unstaged diff has each file up to twice).
+/// Binary entries and chunks numstat doesn't list keep their counts.
+pub fn apply_numstat(chunks: &mut [FileChunk], entries: &[NumstatEntry]) {
+    let mut by_path: HashMap<&str, Vec<&NumstatEntry>> = HashMap::new();
+    for e in entries.iter().rev() {
+        by_path.entry(e.path.as_str()).or_default().push(e);
+    }
+    for chunk in chunks.iter_mut() {
+        let Some(entry) = by_path.get_mut(chunk.path.as_str()).and_then(|v| v.pop()) else {
+            continue;
+        };
+        if let (Some(added), Some(removed)) = (entry.added, entry.removed) {
+            chunk.lines_added = added;
+            chunk.lines_removed = removed;
+        }
+    }
+}
+
+/// `split_diff_by_file`, ranked for selection: line counts from `--numstat` output when
+/// there is one, `generated` files excluded, then the primary language boosted.
+fn ranked_chunks(
+    raw_diff: &str,
+    numstat: Option<&str>,
+    max_line_chars: usize,
+    generated: &HashSet<String>,
+) -> Vec<FileChunk> {
+    let mut chunks = split_diff_by_file(raw_diff, max_line_chars);
+    if let Some(numstat) = numstat {
+        apply_numstat(&mut chunks, &parse_numstat(numstat));
+    }
+    exclude_generated(&mut chunks, generated);
+    boost_primary_language(&mut chunks);
+    chunks
+}
+
+/// Language with the most changed files; None without code files or on a tie.
+pub fn detect_primary_language<'a>(paths: impl IntoIterator<Item = &'a str>) -> Option<&'static Language> {
+    let mut counts = [0usize; LANGUAGES.len()];
+    for path in paths {
+        if let Some(i) = LANGUAGES.iter().position(|l| l.matches(path)) {
+            counts[i] += 1;
+        }
+    }
+    let best = *counts.iter().max()?;
+    let mut leaders = LANGUAGES.iter().zip(counts).filter(|(_, n)| *n == best && best > 0);
+    match (leaders.next(), leaders.next()) {
+        (Some((lang, _)), None) => Some(lang),
+        _ => None,
+    }
+}
+
+/// Rank the primary language's files above other code, and its entry points
+/// (`__main__.py`, `index.ts`, ...) like `main.rs`.
+fn boost_primary_language(chunks: &mut [FileChunk]) {
+    let included = chunks.iter().filter(|c| c.priority > EXCLUDED_PRIORITY);
+    let Some(lang) = detect_primary_language(included.map(|c| c.path.as_str())) else {
+        return;
+    };
+    for chunk in chunks.iter_mut() {
+        if chunk.priority <= EXCLUDED_PRIORITY || !lang.matches(&chunk.path) {
+            continue;
+        }
+        chunk.priority = if lang.is_entry_point(&chunk.path) {
+            chunk.priority.max(ENTRY_POINT_PRIORITY)
+        } else {
+            chunk.priority + PRIMARY_LANGUAGE_BOOST
+        };
+    }
+}
+
+/// Exclude `generated` files (`linguist-generated` in `.gitattributes`).
+fn exclude_generated(chunks: &mut [FileChunk], generated: &HashSet<String>) {
+    for chunk in chunks.iter_mut() {
+        if generated.contains(&chunk.path) {
+            chunk.priority = EXCLUDED_PRIORITY;
+        }
+    }
+}
+
+/// Group file chunks (in diff order) so each group's content fits `budget` chars.
+/// A single file larger than the budget gets a group of its own.
+pub fn partition_chunks(chunks: &[FileChunk], budget: usize) -> Vec<Vec<&FileChunk>> {
+    let mut groups: Vec<Vec<&FileChunk>> = Vec::new();
+    let mut current: Vec<&FileChunk> = Vec::new();
+    let mut size = 0usize;
+
+    for chunk in chunks {
+        let len = chunk.content.len();
+        if !current.is_empty() && size + len > budget {
+            groups.push(std::mem::take(&mut current));
+            size = 0;
+        }
+        current.push(chunk);
+        size += len;
+    }
+
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+/// Top-level directories too generic to be a scope
+const GENERIC_DIRS: &[&str] = &["src", "lib", "app", "pkg", "packages", "crates"];
+
+/// Scope hint from changed paths: the first non-generic directory shared by
+/// more than half of the files (`src/auth/login.rs` -> `auth`).
+pub fn dominant_scope(chunks: &[FileChunk]) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+
+    for chunk in chunks {
+        let dirs: Vec<&str> = chunk.path.split('/').collect();
+        let dirs = &dirs[..dirs.len().saturating_sub(1)];
+        let scope = dirs.iter().find(|d| !GENERIC_DIRS.contains(d));
+        if let Some(scope) = scope {
+            match counts.iter_mut().find(|(s, _)| s == scope) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((scope.to_string(), 1)),
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(_, n)| *n)
+        .filter(|(_, n)| n * 2 > chunks.len())
+        .map(|(s, _)| s)
+}
+
 fn calculate_priority(path: &str) -> i32 {
     // Check exclusions first
     for exclude in EXCLUDE_FILES {
         if path.ends_with(exclude) {
-            return -100;
+            return EXCLUDED_PRIORITY;
         }
     }
     for pattern in EXCLUDE_PATTERNS {
         if path.contains(pattern) {
-            return -100;
+            return EXCLUDED_PRIORITY;
         }
     }
 
     // Find best matching priority
     let mut best_score = 20; // default for unknown files
     for (pattern, score) in PRIORITY_SCORES {
-        if path.ends_with(pattern) || path.contains(pattern) {
-            if *score > best_score {
-                best_score = *score;
-            }
+        if (path.ends_with(pattern) || path.contains(pattern)) && *score > best_score {
+            best_score = *score;
         }
     }
 
@@ -225,8 +597,7 @@ fn calculate_priority(path: &str) -> i32 {
 
 /// Algorithm 1: Full - complete git diff output with optional truncation
 fn alg_full(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (String, DiffStats) {
-    let chunks = split_diff_by_file(raw_diff);
-    let total_files = chunks.len();
+    let total_files = count_diff_files(raw_diff);
     let total_chars = raw_diff.len();
 
     let mut output = String::new();
@@ -268,14 +639,23 @@ fn alg_full(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (Stri
         estimated_tokens: (output.len() as f32 / CHARS_PER_TOKEN) as usize,
         truncated,
         algorithm: DiffAlg::Full,
+        escalated_from: None,
     };
 
     (output, stats)
 }
 
-/// Algorithm 2: Files - Selective files, ranked by priority (default)
-fn alg_files(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (String, DiffStats) {
-    let mut chunks = split_diff_by_file(raw_diff);
+/// Algorithm 2: Files - Selective files, ranked by priority (default).
+/// `max_files` (0 = no cap) stops after that many files even with budget left.
+fn alg_files(
+    raw_diff: &str,
+    diff_stats: Option<&str>,
+    numstat: Option<&str>,
+    generated: &HashSet<String>,
+    opts: &DiffOptions,
+) -> (String, DiffStats) {
+    let (max_chars, max_files) = (opts.max_chars, opts.max_files);
+    let mut chunks = ranked_chunks(raw_diff, numstat, opts.max_line_chars, generated);
     let total_files = chunks.len();
     let total_chars = raw_diff.len();
 
@@ -312,10 +692,13 @@ fn alg_files(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (Str
     // Pack whole files until budget exhausted
     let mut included = 0usize;
     let mut excluded_names: Vec<String> = Vec::new();
+    let mut capped_names: Vec<String> = Vec::new();
     let mut truncated = false;
 
     for chunk in &chunks {
-        if output.len() + chunk.content.len() <= header_len + available {
+        if max_files > 0 && included >= max_files {
+            capped_names.push(chunk.path.clone());
+        } else if output.len() + chunk.content.len() <= header_len + available {
             output.push_str(&chunk.content);
             output.push('\n');
             included += 1;
@@ -332,6 +715,14 @@ fn alg_files(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (Str
             excluded_names.join(", ")
         ));
     }
+    if !capped_names.is_empty() {
+        output.push_str(&format!(
+            "\n[... {} files excluded by --max-files {}: {} ...]\n",
+            capped_names.len(),
+            max_files,
+            capped_names.join(", ")
+        ));
+    }
 
     let stats = DiffStats {
         total_files,
@@ -342,14 +733,21 @@ fn alg_files(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (Str
         estimated_tokens: (output.len() as f32 / CHARS_PER_TOKEN) as usize,
         truncated,
         algorithm: DiffAlg::Files,
+        escalated_from: None,
     };
 
     (output, stats)
 }
 
 /// Algorithm 3: Hunks - Selective hunks, ranked by importance
-fn alg_hunks(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (String, DiffStats) {
-    let chunks = split_diff_by_file(raw_diff);
+fn alg_hunks(
+    raw_diff: &str,
+    diff_stats: Option<&str>,
+    generated: &HashSet<String>,
+    opts: &DiffOptions,
+) -> (String, DiffStats) {
+    let max_chars = opts.max_chars;
+    let chunks = ranked_chunks(raw_diff, None, opts.max_line_chars, generated);
     let total_files = chunks.len();
     let total_chars = raw_diff.len();
 
@@ -423,6 +821,7 @@ fn alg_hunks(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (Str
         estimated_tokens: (output.len() as f32 / CHARS_PER_TOKEN) as usize,
         truncated,
         algorithm: DiffAlg::Hunks,
+        escalated_from: None,
     };
 
     (output, stats)
@@ -501,18 +900,23 @@ fn score_hunk(hunk: &str, file_priority: i32) -> f32 {
     score
 }
 
+/// `numstat` is `git diff --numstat` for the same diff, when the caller has it;
+/// `generated` lists its `linguist-generated` files.
 pub fn get_llm_diff_preview(
     raw_diff: &str,
     diff_stats: Option<&str>,
-    max_chars: usize,
+    numstat: Option<&str>,
+    generated: &HashSet<String>,
     alg: DiffAlg,
+    opts: &DiffOptions,
     include_header: bool,
 ) -> (String, DiffStats) {
+    let max_chars = opts.max_chars;
     let (shaped_diff, stats) = match alg {
         DiffAlg::Full => alg_full(raw_diff, diff_stats, max_chars),
-        DiffAlg::Files => alg_files(raw_diff, diff_stats, max_chars),
-        DiffAlg::Hunks => alg_hunks(raw_diff, diff_stats, max_chars),
-        DiffAlg::Semantic => alg_semantic(raw_diff, diff_stats, max_chars),
+        DiffAlg::Files => alg_files(raw_diff, diff_stats, numstat, generated, opts),
+        DiffAlg::Hunks => alg_hunks(raw_diff, diff_stats, generated, opts),
+        DiffAlg::Semantic => alg_semantic(raw_diff, diff_stats, numstat, generated, opts),
     };
 
     if include_header {
@@ -606,7 +1010,14 @@ fn summarize_files(chunks: &[FileChunk]) -> Vec<IrFile> {
     files
 }
 
-fn extract_ranked_hunks_for_ir(chunks: &[FileChunk], max_hunks: usize, preview_lines: usize) -> Vec<IrHunk> {
+/// With `changed_only`, previews skip context lines (and the @@ header, which the
+/// IR carries separately).
+fn extract_ranked_hunks_for_ir(
+    chunks: &[FileChunk],
+    max_hunks: usize,
+    preview_lines: usize,
+    changed_only: bool,
+) -> Vec<IrHunk> {
     let mut all: Vec<ScoredHunk> = Vec::new();
 
     for c in chunks {
@@ -635,21 +1046,27 @@ fn extract_ranked_hunks_for_ir(chunks: &[FileChunk], max_hunks: usize, preview_l
         let mut adds = 0usize;
         let mut dels = 0usize;
         let mut preview = String::new();
+        let mut shown = 0usize;
         let mut header = String::new();
 
         for (i, line) in h.content.lines().enumerate() {
             if i == 0 {
                 header = line.to_string(); // @@ header
             }
-            if line.starts_with('+') && !line.starts_with("+++") {
+            let changed = if line.starts_with('+') && !line.starts_with("+++") {
                 adds += 1;
+                true
             } else if line.starts_with('-') && !line.starts_with("---") {
                 dels += 1;
-            }
+                true
+            } else {
+                false
+            };
 
-            if i < preview_lines {
+            if shown < preview_lines && (changed || !changed_only) {
                 preview.push_str(line);
                 preview.push('\n');
+                shown += 1;
             }
         }
 
@@ -731,14 +1148,21 @@ fn build_ir_json(
         s.push_str(&json_escape(&h.preview));
         s.push_str("\"}");
     }
-    s.push_str("]");
+    s.push(']');
 
     s.push('}');
     s
 }
 
-fn alg_semantic(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (String, DiffStats) {
-    let chunks = split_diff_by_file(raw_diff);
+fn alg_semantic(
+    raw_diff: &str,
+    diff_stats: Option<&str>,
+    numstat: Option<&str>,
+    generated: &HashSet<String>,
+    opts: &DiffOptions,
+) -> (String, DiffStats) {
+    let max_chars = opts.max_chars;
+    let chunks = ranked_chunks(raw_diff, numstat, opts.max_line_chars, generated);
     let total_files = chunks.len();
     let total_chars = raw_diff.len();
 
@@ -751,7 +1175,7 @@ fn alg_semantic(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (
     let mut json: String;
 
     loop {
-        let hunks = extract_ranked_hunks_for_ir(&chunks, max_hunks, preview_lines);
+        let hunks = extract_ranked_hunks_for_ir(&chunks, max_hunks, preview_lines, opts.changed_only);
         json = build_ir_json(diff_stats, &files, &hunks, total_files, total_chars);
 
         if json.len() <= max_chars {
@@ -787,6 +1211,7 @@ fn alg_semantic(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (
         estimated_tokens: (json.len() as f32 / CHARS_PER_TOKEN) as usize,
         truncated,
         algorithm: DiffAlg::Semantic,
+        escalated_from: None,
     };
 
     (json, stats)
@@ -796,6 +1221,14 @@ fn alg_semantic(raw_diff: &str, diff_stats: Option<&str>, max_chars: usize) -> (
 mod tests {
     use super::*;
 
+    fn opts(max_chars: usize, max_files: usize) -> DiffOptions {
+        DiffOptions {
+            max_chars,
+            max_files,
+            ..Default::default()
+        }
+    }
+
     const SAMPLE_DIFF: &str = r#"diff --git a/src/main.rs b/src/main.rs
 index 1234567..abcdefg 100644
 --- a/src/main.rs
@@ -824,9 +1257,120 @@ index ccccccc..ddddddd 100644
  }
 "#;
 
+    const WORD_DIFF: &str = r#"diff --git a/README.md b/README.md
+index 1111111..2222222 100644
+--- a/README.md
++++ b/README.md
+@@ -1,3 +1,3 @@
+ The 
+-quick
++slow
+ brown 
+-fox
++dog
+~
+ jumps over
+~
+-the lazy dog.
+~
+"#;
+
+    #[test]
+    fn word_diff_detected_by_line_markers() {
+        assert!(is_word_diff(WORD_DIFF));
+        assert!(!is_word_diff(SAMPLE_DIFF));
+        // A `~` before any hunk is not a word-diff marker
+        assert!(!is_word_diff("diff --git a/x b/x\n~\n"));
+    }
+
+    #[test]
+    fn split_word_diff_counts_text_lines() {
+        let chunks = split_diff_by_file(WORD_DIFF, DEFAULT_MAX_LINE_CHARS);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].path, "README.md");
+        // Two word runs changed on the first line, one line removed entirely
+        assert_eq!(chunks[0].lines_added, 1);
+        assert_eq!(chunks[0].lines_removed, 2);
+        assert!(chunks[0].content.contains("+dog\n~\n"));
+    }
+
+    #[test]
+    fn split_truncates_long_single_lines() {
+        let minified = format!("+{}", "a;".repeat(100_000));
+        let raw = format!(
+            "diff --git a/web/bundle.js b/web/bundle.js\n--- a/web/bundle.js\n+++ b/web/bundle.js\n\
+             @@ -0,0 +1,2 @@\n{}\n+short\n",
+            minified
+        );
+        let chunks = split_diff_by_file(&raw, DEFAULT_MAX_LINE_CHARS);
+        assert_eq!(chunks[0].lines_added, 2);
+        assert!(chunks[0].content.len() < DEFAULT_MAX_LINE_CHARS + 200);
+        assert!(chunks[0].content.contains(" [... 198001 chars truncated]\n+short\n"));
+    }
+
+    #[test]
+    fn truncate_long_line_limits() {
+        assert_eq!(truncate_long_line("+abcdef", 4), "+abc [... 3 chars truncated]");
+        assert_eq!(truncate_long_line("+abc", 4), "+abc");
+        assert_eq!(truncate_long_line("+abcdef", 0), "+abcdef");
+        // Counts characters, not bytes
+        assert_eq!(truncate_long_line("+ééé", 4), "+ééé");
+        assert_eq!(truncate_long_line("+éééé", 3), "+éé [... 2 chars truncated]");
+    }
+
+    #[test]
+    fn auto_alg_selection_boundaries() {
+        assert_eq!(DiffAlg::auto(0, 1000), DiffAlg::Full);
+        assert_eq!(DiffAlg::auto(1000, 1000), DiffAlg::Full);
+        assert_eq!(DiffAlg::auto(1001, 1000), DiffAlg::Files);
+        assert_eq!(DiffAlg::auto(3000, 1000), DiffAlg::Files);
+        assert_eq!(DiffAlg::auto(3001, 1000), DiffAlg::Semantic);
+        assert_eq!(DiffAlg::auto(usize::MAX, usize::MAX / 2), DiffAlg::Files);
+    }
+
+    #[test]
+    fn resolve_alg_zero_is_auto() {
+        assert_eq!(DiffAlg::resolve(ALG_AUTO, 10, 100), DiffAlg::Full);
+        assert_eq!(DiffAlg::resolve(ALG_AUTO, 1000, 100), DiffAlg::Semantic);
+        assert_eq!(DiffAlg::resolve(3, 10, 100), DiffAlg::Hunks);
+    }
+
+    #[test]
+    fn escalate_by_file_count() {
+        // within the limit: unchanged
+        assert_eq!(DiffAlg::Files.escalate(50, 50), DiffAlg::Files);
+        assert_eq!(DiffAlg::Full.escalate(10, 50), DiffAlg::Full);
+        // over: at least Hunks
+        assert_eq!(DiffAlg::Files.escalate(51, 50), DiffAlg::Hunks);
+        assert_eq!(DiffAlg::Full.escalate(100, 50), DiffAlg::Hunks);
+        assert_eq!(DiffAlg::Hunks.escalate(100, 50), DiffAlg::Hunks);
+        // over twice the limit: Semantic
+        assert_eq!(DiffAlg::Files.escalate(101, 50), DiffAlg::Semantic);
+        assert_eq!(DiffAlg::Hunks.escalate(300, 50), DiffAlg::Semantic);
+        // never downgrades, 0 disables
+        assert_eq!(DiffAlg::Semantic.escalate(51, 50), DiffAlg::Semantic);
+        assert_eq!(DiffAlg::Files.escalate(1000, 0), DiffAlg::Files);
+    }
+
+    #[test]
+    fn pick_alg_escalates_small_many_file_diffs() {
+        let raw: String = (0..60).map(|i| format!("diff --git a/f{i} b/f{i}\n+x\n")).collect();
+        assert_eq!(count_diff_files(&raw), 60);
+
+        // fits in max_chars, still escalated
+        assert_eq!(pick_alg(2, &raw, &opts(100_000, 0)), (DiffAlg::Hunks, Some(DiffAlg::Files)));
+        assert_eq!(pick_alg(ALG_AUTO, &raw, &opts(100_000, 0)), (DiffAlg::Hunks, Some(DiffAlg::Full)));
+        // explicit Full is respected
+        assert_eq!(pick_alg(1, &raw, &opts(100_000, 0)), (DiffAlg::Full, None));
+        assert_eq!(pick_alg(2, SAMPLE_DIFF, &opts(100_000, 0)), (DiffAlg::Files, None));
+        // max_diff_files = 0 turns escalation off
+        let no_escalation = DiffOptions { max_diff_files: 0, ..opts(100_000, 0) };
+        assert_eq!(pick_alg(2, &raw, &no_escalation), (DiffAlg::Files, None));
+    }
+
     #[test]
     fn test_split_diff() {
-        let chunks = split_diff_by_file(SAMPLE_DIFF);
+        let chunks = split_diff_by_file(SAMPLE_DIFF, DEFAULT_MAX_LINE_CHARS);
         assert_eq!(chunks.len(), 3);
         assert_eq!(chunks[0].path, "src/main.rs");
         assert_eq!(chunks[1].path, "Cargo.lock");
@@ -840,6 +1384,39 @@ index ccccccc..ddddddd 100644
         assert!(calculate_priority("Cargo.lock") < 0); // excluded
     }
 
+    #[test]
+    fn detect_primary_language_by_file_count() {
+        let python = ["app/__main__.py", "app/api.py", "app/models.py", "build.rs", "README.md"];
+        assert_eq!(detect_primary_language(python).map(|l| l.name), Some("python"));
+
+        let ts = ["web/src/index.ts", "web/src/App.tsx", "server/main.go"];
+        assert_eq!(detect_primary_language(ts).map(|l| l.name), Some("typescript"));
+
+        assert!(detect_primary_language(["src/lib.rs", "tools/gen.py"]).is_none());
+        assert!(detect_primary_language(["README.md", "docs/guide.md"]).is_none());
+        assert!(detect_primary_language([]).is_none());
+    }
+
+    #[test]
+    fn primary_language_boosts_files_and_entry_points() {
+        let mut chunks = vec![
+            chunk("app/__main__.py", 1),
+            chunk("app/api.py", 1),
+            chunk("app/util.py", 1),
+            chunk("native/src/ext.rs", 1),
+            chunk("poetry.lock", 1),
+        ];
+        for c in chunks.iter_mut() {
+            c.priority = calculate_priority(&c.path);
+        }
+        boost_primary_language(&mut chunks);
+
+        assert_eq!(chunks[0].priority, ENTRY_POINT_PRIORITY);
+        assert!(chunks[1].priority > chunks[3].priority);
+        assert_eq!(chunks[3].priority, calculate_priority("native/src/ext.rs"));
+        assert_eq!(chunks[4].priority, EXCLUDED_PRIORITY);
+    }
+
     #[test]
     fn test_alg_from_num() {
         assert_eq!(DiffAlg::from_num(1), DiffAlg::Full);
@@ -870,25 +1447,103 @@ index ccccccc..ddddddd 100644
 
     #[test]
     fn test_files_excludes_lock_files() {
-        let (output, stats) = alg_files(SAMPLE_DIFF, None, 10_000);
+        let (output, stats) = alg_files(SAMPLE_DIFF, None, None, &HashSet::new(), &opts(10_000, 0));
         assert!(!output.contains("Cargo.lock"));
         assert_eq!(stats.algorithm, DiffAlg::Files);
     }
 
+    #[test]
+    fn test_files_max_files_caps_included() {
+        // src/main.rs and src/lib.rs fit the budget; the cap keeps only the first
+        let (output, stats) = alg_files(SAMPLE_DIFF, None, None, &HashSet::new(), &opts(10_000, 1));
+        assert_eq!(stats.included_files, 1);
+        assert_eq!(stats.excluded_files, 2);
+        assert!(!stats.truncated);
+        let patches = output.split("=== patches ===").nth(1).unwrap();
+        assert_eq!(patches.matches("diff --git").count(), 1);
+        assert!(output.contains("[... 1 files excluded by --max-files 1: "));
+
+        let (_, stats) = alg_files(SAMPLE_DIFF, None, None, &HashSet::new(), &opts(10_000, 5));
+        assert_eq!(stats.included_files, 2);
+    }
+
     #[test]
     fn test_hunks_excludes_lock_files() {
-        let (output, stats) = alg_hunks(SAMPLE_DIFF, None, 10000);
+        let (output, stats) = alg_hunks(SAMPLE_DIFF, None, &HashSet::new(), &opts(10_000, 0));
         assert!(!output.contains("Cargo.lock"));
         assert_eq!(stats.algorithm, DiffAlg::Hunks);
     }
 
     #[test]
     fn test_semantic_builds_json() {
-        let (output, stats) = alg_semantic(SAMPLE_DIFF, Some("fake stat"), 10000);
+        let (output, stats) =
+            alg_semantic(SAMPLE_DIFF, Some("fake stat"), None, &HashSet::new(), &opts(10_000, 0));
         assert!(output.starts_with('{') && output.ends_with('}'));
         assert_eq!(stats.algorithm, DiffAlg::Semantic);
     }
 
+    fn entry(path: &str, added: Option<usize>, removed: Option<usize>) -> NumstatEntry {
+        NumstatEntry { path: path.into(), added, removed }
+    }
+
+    #[test]
+    fn parse_numstat_counts_renames_and_binary() {
+        let out = "3\t1\tsrc/lib.rs\n\
+                   0\t0\told.rs => new.rs\n\
+                   2\t2\tsrc/{api => http}/mod.rs\n\
+                   1\t0\tsrc/{ => util}/fmt.rs\n\
+                   -\t-\tassets/logo.png\n\
+                   garbage line\n";
+        assert_eq!(
+            parse_numstat(out),
+            vec![
+                entry("src/lib.rs", Some(3), Some(1)),
+                entry("new.rs", Some(0), Some(0)),
+                entry("src/http/mod.rs", Some(2), Some(2)),
+                entry("src/util/fmt.rs", Some(1), Some(0)),
+                entry("assets/logo.png", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_numstat_overrides_counts_by_path() {
+        let raw = "diff --git a/a.rs b/a.rs\n+x\ndiff --git a/logo.png b/logo.png\nBinary files differ\n\
+                   diff --git a/c.rs b/c.rs\n-y\ndiff --git a/a.rs b/a.rs\n+z\n";
+        let mut chunks = split_diff_by_file(raw, DEFAULT_MAX_LINE_CHARS);
+        let entries = [
+            entry("a.rs", Some(40), Some(2)),
+            entry("logo.png", None, None),
+            entry("a.rs", Some(5), Some(0)),
+        ];
+        apply_numstat(&mut chunks, &entries);
+        let counts: Vec<_> = chunks.iter().map(|c| (c.lines_added, c.lines_removed)).collect();
+        assert_eq!(counts, [(40, 2), (0, 0), (0, 1), (5, 0)]);
+    }
+
+    #[test]
+    fn files_header_uses_numstat_counts() {
+        let numstat = Some("9\t4\tsrc/lib.rs\n");
+        let (output, _) = alg_files(SAMPLE_DIFF, None, numstat, &HashSet::new(), &opts(10_000, 0));
+        assert!(output.contains("src/lib.rs (+9/-4)"));
+    }
+
+    #[test]
+    fn test_ir_preview_changed_only() {
+        let chunks = split_diff_by_file(SAMPLE_DIFF, DEFAULT_MAX_LINE_CHARS);
+
+        let full = extract_ranked_hunks_for_ir(&chunks, 10, 25, false);
+        assert!(full.iter().any(|h| h.preview.lines().any(|l| l.starts_with(' ') || l.starts_with("@@"))));
+
+        let hunks = extract_ranked_hunks_for_ir(&chunks, 10, 25, true);
+        assert!(!hunks.is_empty());
+        for h in &hunks {
+            assert!(h.header.starts_with("@@"));
+            assert!(h.preview.lines().all(|l| l.starts_with('+') || l.starts_with('-')), "{}", h.preview);
+            assert_eq!(h.preview.lines().count(), h.adds + h.dels);
+        }
+    }
+
     #[test]
     fn test_alg_names() {
         assert_eq!(DiffAlg::Full.name(), "Full Diff");
@@ -897,6 +1552,95 @@ index ccccccc..ddddddd 100644
         assert_eq!(DiffAlg::Semantic.name(), "Semantic JSON");
     }
 
+    fn chunk(path: &str, len: usize) -> FileChunk {
+        FileChunk {
+            path: path.into(),
+            content: "x".repeat(len),
+            priority: 0,
+            lines_added: 0,
+            lines_removed: 0,
+        }
+    }
+
+    #[test]
+    fn test_exclude_generated_marks_flagged_files() {
+        let mut chunks = vec![chunk("src/api.rs", 1), chunk("gen/api.pb.rs", 1), chunk("Cargo.lock", 1)];
+        for c in chunks.iter_mut() {
+            c.priority = calculate_priority(&c.path);
+        }
+        exclude_generated(&mut chunks, &HashSet::from(["gen/api.pb.rs".to_string()]));
+        assert!(chunks[0].priority > 0);
+        assert_eq!(chunks[1].priority, EXCLUDED_PRIORITY);
+        assert_eq!(chunks[2].priority, EXCLUDED_PRIORITY);
+    }
+
+    #[test]
+    fn selectable_paths_skip_excluded_files() {
+        // Already-excluded files are not looked up
+        assert_eq!(selectable_paths(SAMPLE_DIFF), ["src/main.rs", "src/lib.rs"]);
+    }
+
+    fn group_paths(groups: &[Vec<&FileChunk>]) -> Vec<Vec<String>> {
+        groups
+            .iter()
+            .map(|g| g.iter().map(|c| c.path.clone()).collect())
+            .collect()
+    }
+
+    fn scope_of(paths: &[&str]) -> Option<String> {
+        let chunks: Vec<FileChunk> = paths.iter().map(|p| chunk(p, 1)).collect();
+        dominant_scope(&chunks)
+    }
+
+    #[test]
+    fn test_dominant_scope_common_dir() {
+        assert_eq!(scope_of(&["src/auth/login.rs", "src/auth/token.rs"]), Some("auth".into()));
+        assert_eq!(scope_of(&["src/auth/oauth/google.rs"]), Some("auth".into()));
+        assert_eq!(scope_of(&["docs/guide.md", "docs/api.md"]), Some("docs".into()));
+        assert_eq!(scope_of(&["crates/core/src/lib.rs"]), Some("core".into()));
+    }
+
+    #[test]
+    fn test_dominant_scope_majority_wins() {
+        let paths = ["src/auth/a.rs", "src/auth/b.rs", "README.md"];
+        assert_eq!(scope_of(&paths), Some("auth".into()));
+    }
+
+    #[test]
+    fn test_dominant_scope_none_when_split_or_flat() {
+        assert_eq!(scope_of(&["src/auth/a.rs", "src/db/b.rs"]), None);
+        assert_eq!(scope_of(&["src/main.rs", "src/lib.rs"]), None);
+        assert_eq!(scope_of(&["README.md"]), None);
+        assert_eq!(scope_of(&[]), None);
+    }
+
+    #[test]
+    fn test_partition_chunks_fills_groups_in_order() {
+        let chunks = vec![chunk("a", 40), chunk("b", 50), chunk("c", 30), chunk("d", 80)];
+        let groups = partition_chunks(&chunks, 100);
+        assert_eq!(group_paths(&groups), vec![vec!["a", "b"], vec!["c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn test_partition_chunks_oversized_file_alone() {
+        let chunks = vec![chunk("a", 10), chunk("huge", 500), chunk("b", 10)];
+        let groups = partition_chunks(&chunks, 100);
+        assert_eq!(group_paths(&groups), vec![vec!["a"], vec!["huge"], vec!["b"]]);
+    }
+
+    #[test]
+    fn test_partition_chunks_everything_fits() {
+        let chunks = vec![chunk("a", 10), chunk("b", 10), chunk("c", 10)];
+        let groups = partition_chunks(&chunks, 30);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 3);
+    }
+
+    #[test]
+    fn test_partition_chunks_empty() {
+        assert!(partition_chunks(&[], 100).is_empty());
+    }
+
     #[test]
     fn test_diff_stats_display() {
         let stats = DiffStats {
@@ -908,10 +1652,18 @@ index ccccccc..ddddddd 100644
             estimated_tokens: 142,
             truncated: false,
             algorithm: DiffAlg::Files,
+            escalated_from: None,
         };
         let display = stats.display();
-        assert!(display.contains("2 - Selective Files"));
+        assert!(display.contains("2 - Selective Files\n"));
         assert!(display.contains("3/5 included"));
         assert!(display.contains("50.0% reduction"));
+
+        let stats = DiffStats {
+            algorithm: DiffAlg::Hunks,
+            escalated_from: Some(DiffAlg::Files),
+            ..stats
+        };
+        assert!(stats.display().contains("3 - Selective Hunks (escalated from 2: 5 files)"));
     }
 }
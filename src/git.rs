@@ -1,11 +1,21 @@
 // src/git.rs
-use anyhow::Result;
-use std::path::PathBuf;
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::diff::DiffOptions;
 
 // =============================================================================
 // EXCLUDE PATTERNS
 // =============================================================================
+/// Default lines of context around each change (`git diff --unified`)
+pub const DEFAULT_DIFF_CONTEXT: u32 = 3;
+
+const WORD_DIFF_ARG: &str = "--word-diff=porcelain";
+
 pub const EXCLUDE_PATTERNS: &[&str] = &[
     ":(exclude)*.lock",
     ":(exclude)package-lock.json",
@@ -29,23 +39,105 @@ pub struct CommitInfo {
     pub author: String,
     pub date: String,
     pub message: String,
+    /// Commit body (`%b`), only populated when requested
+    pub body: String,
+}
+
+impl CommitInfo {
+    /// Subject plus body, when the body was fetched
+    pub fn full_message(&self) -> String {
+        if self.body.is_empty() {
+            self.message.clone()
+        } else {
+            format!("{}\n\n{}", self.message, self.body)
+        }
+    }
 }
 
+/// Log format with unit/record separators, so bodies can contain newlines and pipes
+const LOG_FORMAT_WITH_BODY: &str = "--pretty=format:%H%x1f%an%x1f%ad%x1f%s%x1f%b%x1e";
+
 // =============================================================================
 // GIT UTILITIES
 // =============================================================================
+/// Overrides so `color.ui=always` (or a per-command `color.*`) in the user's config
+/// can't put ANSI escapes into captured output
+const NO_COLOR_ARGS: &[&str] = &[
+    "-c", "color.ui=false",
+    "-c", "color.diff=false",
+    "-c", "color.status=false",
+    "-c", "color.branch=false",
+];
+
+/// Print non-ASCII paths as they are instead of as quoted octal escapes (`"caf\303\251.rs"`)
+const QUOTEPATH_ARGS: &[&str] = &["-c", "core.quotepath=false"];
+
+/// `git` with color forced off, ready for `args`.
+fn git_command() -> Command {
+    let mut cmd = Command::new("git");
+    cmd.args(NO_COLOR_ARGS).args(QUOTEPATH_ARGS);
+    #[cfg(test)]
+    TEST_REPO.with(|repo| {
+        if let Some(dir) = repo.borrow().as_ref() {
+            cmd.current_dir(dir);
+        }
+    });
+    cmd
+}
+
+static WARNED_INVALID_UTF8: AtomicBool = AtomicBool::new(false);
+
+/// Decode git output, replacing invalid UTF-8 (e.g. Latin-1 file names or content)
+/// with U+FFFD. Returns the text and the number of invalid sequences replaced.
+pub fn decode_git_output(bytes: &[u8]) -> (String, usize) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut invalid = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            invalid += 1;
+        }
+    }
+    (text, invalid)
+}
+
+/// Warning for output that had invalid UTF-8
+fn invalid_utf8_warning(args: &[&str], invalid: usize) -> String {
+    format!(
+        "Warning: `git {}` output is not valid UTF-8 ({} sequence(s) replaced with U+FFFD).\n\
+         Paths or diff lines in another encoding (e.g. Latin-1) will look garbled to the model.",
+        args.first().copied().unwrap_or_default(),
+        invalid
+    )
+}
+
+/// Decoded stdout; the first lossy decode in a run prints a warning.
+fn stdout_text(args: &[&str], stdout: &[u8]) -> String {
+    let (text, invalid) = decode_git_output(stdout);
+    if invalid > 0 && !WARNED_INVALID_UTF8.swap(true, Ordering::Relaxed) {
+        eprintln!("{}", crate::color::yellow(&invalid_utf8_warning(args, invalid)));
+    }
+    text
+}
+
 pub fn run_git(args: &[&str]) -> Result<String> {
-    let output = Command::new("git")
+    let output = git_command()
         .args(args)
         .output()
         .map_err(|e| anyhow::anyhow!("Failed to execute git: {}", e))?;
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    Ok(stdout_text(args, &output.stdout))
 }
 
 pub fn run_git_status(args: &[&str]) -> (String, String, bool) {
-    match Command::new("git").args(args).output() {
+    run_git_status_env(args, &[])
+}
+
+/// `run_git_status` with extra environment variables, e.g. `GIT_SEQUENCE_EDITOR`.
+pub fn run_git_status_env(args: &[&str], envs: &[(&str, &str)]) -> (String, String, bool) {
+    match git_command().args(args).envs(envs.iter().copied()).output() {
         Ok(o) => (
-            String::from_utf8_lossy(&o.stdout).to_string(),
+            stdout_text(args, &o.stdout),
             String::from_utf8_lossy(&o.stderr).to_string(),
             o.status.success(),
         ),
@@ -53,6 +145,18 @@ pub fn run_git_status(args: &[&str]) -> (String, String, bool) {
     }
 }
 
+pub fn is_git_installed() -> bool {
+    binary_available("git")
+}
+
+fn binary_available(bin: &str) -> bool {
+    Command::new(bin)
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
 pub fn is_git_repo() -> bool {
     Command::new("git")
         .args(["rev-parse", "--git-dir"])
@@ -73,6 +177,33 @@ pub fn get_git_dir() -> Option<PathBuf> {
     Some(PathBuf::from(path_str))
 }
 
+pub fn get_repo_root() -> Option<PathBuf> {
+    run_git(&["rev-parse", "--show-toplevel"])
+        .ok()
+        .map(|s| PathBuf::from(s.trim()))
+        .filter(|p| !p.as_os_str().is_empty())
+}
+
+/// `name` inside the repository's git directory (`git rev-parse --git-path`), made
+/// absolute. Unlike the shared temp dir, other local users can't write there.
+pub fn get_git_path(name: &str) -> Option<PathBuf> {
+    run_git(&["rev-parse", "--git-path", name])
+        .ok()
+        .map(|s| PathBuf::from(s.trim()))
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| std::env::current_dir().map(|cwd| cwd.join(&p)).unwrap_or(p))
+}
+
+/// Paths git still reports as unmerged (an unresolved merge, rebase or cherry-pick).
+pub fn get_unmerged_paths() -> Result<Vec<String>> {
+    Ok(run_git(&["diff", "--name-only", "--diff-filter=U"])?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
 pub fn get_current_branch() -> String {
     if let Ok(out) = run_git(&["branch", "--show-current"]) {
         let b = out.trim().to_string();
@@ -89,7 +220,41 @@ pub fn get_current_branch() -> String {
     "HEAD".to_string()
 }
 
+/// `git config --get KEY` (repo, global or system); None when unset or empty.
+pub fn get_git_config(key: &str) -> Option<String> {
+    run_git(&["config", "--get", key]).ok().and_then(|out| parse_git_config_value(&out))
+}
+
+fn parse_git_config_value(output: &str) -> Option<String> {
+    // A multi-valued key prints one value per line; git uses the last one
+    output.lines().map(str::trim).rfind(|v| !v.is_empty()).map(String::from)
+}
+
+static DEFAULT_BRANCH: OnceLock<String> = OnceLock::new();
+
+/// Default branch from origin's HEAD, falling back to local main/master. Cached per run.
 pub fn get_default_branch() -> String {
+    DEFAULT_BRANCH.get_or_init(detect_default_branch).clone()
+}
+
+fn detect_default_branch() -> String {
+    let remote = run_git(&["symbolic-ref", "refs/remotes/origin/HEAD"])
+        .ok()
+        .and_then(|s| parse_symbolic_ref_branch(&s))
+        .or_else(|| {
+            run_git(&["branch", "-r"])
+                .ok()
+                .and_then(|s| parse_remote_head_branch(&s))
+        });
+
+    if let Some(b) = remote {
+        // Prefer the local branch; otherwise compare against the remote-tracking ref
+        if run_git(&["rev-parse", "--verify", &b]).is_ok() {
+            return b;
+        }
+        return format!("origin/{}", b);
+    }
+
     for b in ["main", "master"] {
         if run_git(&["rev-parse", "--verify", b]).is_ok() {
             return b.into();
@@ -98,34 +263,81 @@ pub fn get_default_branch() -> String {
     "main".into()
 }
 
-pub fn get_commit_logs(
-    limit: Option<usize>,
-    since: Option<&str>,
-    until: Option<&str>,
-    range: Option<&str>,
-) -> Result<Vec<CommitInfo>> {
-    let mut args_vec: Vec<String> = vec![
-        "log".into(),
-        "--pretty=format:%H|%an|%ad|%s".into(),
-        "--date=iso".into(),
-    ];
+/// `refs/remotes/origin/develop` -> `develop`
+fn parse_symbolic_ref_branch(output: &str) -> Option<String> {
+    output
+        .trim()
+        .strip_prefix("refs/remotes/origin/")
+        .filter(|b| !b.is_empty())
+        .map(String::from)
+}
 
-    if let Some(n) = limit {
-        args_vec.push(format!("-n{}", n));
-    }
-    if let Some(s) = since {
-        args_vec.push(format!("--since={}", s));
-    }
-    if let Some(u) = until {
-        args_vec.push(format!("--until={}", u));
-    }
-    if let Some(r) = range {
-        args_vec.push(r.to_string());
+/// Find `origin/HEAD -> origin/develop` in `git branch -r` output.
+fn parse_remote_head_branch(output: &str) -> Option<String> {
+    output.lines().find_map(|l| {
+        let (head, target) = l.trim().split_once(" -> ")?;
+        if head != "origin/HEAD" {
+            return None;
+        }
+        target
+            .strip_prefix("origin/")
+            .filter(|b| !b.is_empty())
+            .map(String::from)
+    })
+}
+
+/// Paths marked `linguist-generated` in `.gitattributes`, in one `git check-attr` call.
+pub fn linguist_generated(paths: &[String]) -> HashSet<String> {
+    if paths.is_empty() {
+        return HashSet::new();
     }
+    // Diff paths are relative to the repo root, not the working directory
+    let root = get_repo_root().unwrap_or_else(|| PathBuf::from("."));
+    let root = root.to_string_lossy();
+    let mut args = vec!["-C", root.as_ref(), "check-attr", "linguist-generated", "--"];
+    args.extend(paths.iter().map(|p| p.as_str()));
+    run_git(&args)
+        .map(|out| parse_check_attr(&out))
+        .unwrap_or_default()
+}
+
+/// Paths whose attribute is set in `git check-attr` output (`<path>: <attr>: <value>`).
+fn parse_check_attr(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (rest, value) = l.rsplit_once(": ")?;
+            let (path, _attr) = rest.rsplit_once(": ")?;
+            matches!(value.trim(), "set" | "true").then(|| path.to_string())
+        })
+        .collect()
+}
+
+/// What `git log` lists. The default is every commit reachable from HEAD, subjects only.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery<'a> {
+    pub limit: Option<usize>,
+    pub since: Option<&'a str>,
+    pub until: Option<&'a str>,
+    pub range: Option<&'a str>,
+    /// Full messages, not just subjects
+    pub with_body: bool,
+    pub no_merges: bool,
+    /// Walk only the mainline, skipping commits brought in by merges
+    pub first_parent: bool,
+    /// Several patterns match any of them (git ORs them)
+    pub authors: &'a [String],
+}
 
+pub fn get_commit_logs(query: &LogQuery) -> Result<Vec<CommitInfo>> {
+    let args_vec = build_log_args(query);
     let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
     let output = run_git(&args)?;
 
+    if query.with_body {
+        return Ok(parse_commit_log_records(&output));
+    }
+
     Ok(output
         .lines()
         .filter(|l| !l.is_empty())
@@ -137,6 +349,7 @@ pub fn get_commit_logs(
                     author: p[1].into(),
                     date: p[2].into(),
                     message: p[3].into(),
+                    body: String::new(),
                 })
             } else {
                 None
@@ -145,20 +358,107 @@ pub fn get_commit_logs(
         .collect())
 }
 
-pub fn get_commit_diff(hash: &str, max_chars: usize) -> Result<Option<String>> {
+/// `git log` arguments for `query`.
+pub fn build_log_args(query: &LogQuery) -> Vec<String> {
+    let format = if query.with_body {
+        LOG_FORMAT_WITH_BODY
+    } else {
+        "--pretty=format:%H|%an|%ad|%s"
+    };
+    let mut args: Vec<String> = vec!["log".into(), format.into(), "--date=iso".into()];
+
+    if let Some(n) = query.limit {
+        args.push(format!("-n{}", n));
+    }
+    if let Some(s) = query.since {
+        args.push(format!("--since={}", s));
+    }
+    if let Some(u) = query.until {
+        args.push(format!("--until={}", u));
+    }
+    if query.no_merges {
+        args.push("--no-merges".into());
+    }
+    if query.first_parent {
+        args.push("--first-parent".into());
+    }
+    args.extend(author_args(query.authors));
+    if let Some(r) = query.range {
+        args.push(r.to_string());
+    }
+    args
+}
+
+fn author_args(authors: &[String]) -> impl Iterator<Item = String> + '_ {
+    authors.iter().map(|a| format!("--author={}", a))
+}
+
+/// Parse `LOG_FORMAT_WITH_BODY` output: records end with 0x1e, fields split by 0x1f.
+fn parse_commit_log_records(output: &str) -> Vec<CommitInfo> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let record = record.trim_start_matches(['\n', '\r']);
+            if record.is_empty() {
+                return None;
+            }
+            let p: Vec<&str> = record.splitn(5, '\x1f').collect();
+            if p.len() < 4 {
+                return None;
+            }
+            Some(CommitInfo {
+                hash: p[0].into(),
+                author: p[1].into(),
+                date: p[2].into(),
+                message: p[3].into(),
+                body: p.get(4).map(|b| b.trim().to_string()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Committer date of a ref as YYYY-MM-DD (None if the ref doesn't resolve).
+pub fn get_ref_date(reference: &str) -> Option<String> {
+    let out = run_git(&["log", "-1", "--format=%cs", reference, "--"]).ok()?;
+    let date = out.trim();
+    (!date.is_empty()).then(|| date.to_string())
+}
+
+/// Total commits matching the same filters as `get_commit_logs` (without a limit).
+pub fn count_commits(
+    since: Option<&str>,
+    until: Option<&str>,
+    range: Option<&str>,
+    no_merges: bool,
+    authors: &[String],
+) -> Result<usize> {
+    let mut args_vec: Vec<String> = vec!["rev-list".into(), "--count".into()];
+    if let Some(s) = since {
+        args_vec.push(format!("--since={}", s));
+    }
+    if let Some(u) = until {
+        args_vec.push(format!("--until={}", u));
+    }
+    if no_merges {
+        args_vec.push("--no-merges".into());
+    }
+    args_vec.extend(author_args(authors));
+    args_vec.push(range.unwrap_or("HEAD").to_string());
+
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    run_git(&args)?
+        .trim()
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Failed to count commits: {}", e))
+}
+
+pub fn get_commit_diff(hash: &str, max_chars: usize, diff: &DiffOptions) -> Result<Option<String>> {
     let parent_ref = format!("{}^", hash);
     let has_parent = run_git(&["rev-parse", &parent_ref]).is_ok();
 
-    let diff = if has_parent {
-        let diff_ref = format!("{}^!", hash);
-        let mut args = vec!["diff", &diff_ref, "--unified=3", "--", "."];
-        args.extend(EXCLUDE_PATTERNS);
-        run_git(&args)?
-    } else {
-        let mut args = vec!["diff-tree", "--patch", "--unified=3", "--root", hash, "--", "."];
-        args.extend(EXCLUDE_PATTERNS);
-        run_git(&args)?
-    };
+    let args_vec = build_commit_diff_args(hash, has_parent, diff.context, diff.word_diff);
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    let diff = run_git(&args)?;
 
     if diff.trim().is_empty() {
         return Ok(None);
@@ -166,16 +466,72 @@ pub fn get_commit_diff(hash: &str, max_chars: usize) -> Result<Option<String>> {
     Ok(Some(truncate_diff(diff, max_chars)))
 }
 
-pub fn get_diff(target: Option<&str>, staged: bool, max_chars: usize) -> Result<String> {
-    let mut args = vec!["diff", "--unified=3"];
+pub fn build_commit_diff_args(hash: &str, has_parent: bool, context: u32, word_diff: bool) -> Vec<String> {
+    let unified = format!("--unified={}", context);
+    let mut args: Vec<String> = if has_parent {
+        vec!["diff".into(), format!("{}^!", hash), unified]
+    } else {
+        vec!["diff-tree".into(), "--patch".into(), unified, "--root".into(), hash.into()]
+    };
+    if word_diff {
+        args.push(WORD_DIFF_ARG.into());
+    }
+    args.extend(["--".to_string(), ".".to_string()]);
+    args.extend(EXCLUDE_PATTERNS.iter().map(|p| p.to_string()));
+    args
+}
+
+pub fn get_diff(
+    target: Option<&str>,
+    staged: bool,
+    max_chars: usize,
+    diff: &DiffOptions,
+    paths: &[String],
+) -> Result<String> {
+    let args_vec = build_diff_args(target, staged, diff.context, diff.word_diff, paths);
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    Ok(truncate_diff(run_git(&args)?, max_chars))
+}
+
+/// Arguments for `get_diff`. Empty `paths` means the whole tree (`.`).
+pub fn build_diff_args(
+    target: Option<&str>,
+    staged: bool,
+    context: u32,
+    word_diff: bool,
+    paths: &[String],
+) -> Vec<String> {
+    let mut args = vec!["diff".to_string(), format!("--unified={}", context)];
+    if word_diff {
+        args.push(WORD_DIFF_ARG.into());
+    }
     if staged {
-        args.push("--cached");
+        args.push("--cached".into());
     } else if let Some(t) = target {
-        args.push(t);
+        args.push(t.into());
     }
-    args.extend(&["--", "."]);
-    args.extend(EXCLUDE_PATTERNS);
-    Ok(truncate_diff(run_git(&args)?, max_chars))
+    args.push("--".into());
+    if paths.is_empty() {
+        args.push(".".into());
+    } else {
+        args.extend(paths.iter().cloned());
+    }
+    args.extend(EXCLUDE_PATTERNS.iter().map(|p| p.to_string()));
+    args
+}
+
+/// `git diff --numstat` for the same target and paths as `get_diff`.
+pub fn get_numstat(target: Option<&str>, staged: bool, paths: &[String]) -> Result<String> {
+    let args_vec = build_numstat_args(target, staged, paths);
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    run_git(&args)
+}
+
+/// `build_diff_args` with `--numstat` in place of `--unified`.
+pub fn build_numstat_args(target: Option<&str>, staged: bool, paths: &[String]) -> Vec<String> {
+    let mut args = build_diff_args(target, staged, 0, false, paths);
+    args[1] = "--numstat".into();
+    args
 }
 
 pub fn get_diff_stats(target: Option<&str>, staged: bool) -> Result<String> {
@@ -188,6 +544,129 @@ pub fn get_diff_stats(target: Option<&str>, staged: bool) -> Result<String> {
     run_git(&args)
 }
 
+/// Arguments for `git diff --no-index` between two paths; `format` is e.g. `--unified=3` or `--stat`.
+pub fn build_no_index_args(old: &str, new: &str, format: &str) -> Vec<String> {
+    ["diff", "--no-index", format, "--", old, new]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Diff two files (no repo needed).
+pub fn get_no_index_diff(old: &str, new: &str, context: u32) -> Result<String> {
+    run_no_index(&build_no_index_args(old, new, &format!("--unified={}", context)))
+}
+
+/// git exits 1 when the files differ, which is not an error here. A failure without a
+/// diff is (a missing file exits 1 or 128 depending on the git version): git's message.
+fn run_no_index(args_vec: &[String]) -> Result<String> {
+    let args: Vec<&str> = args_vec.iter().map(|s| s.as_str()).collect();
+    let (out, err, ok) = run_git_status(&args);
+    if !ok && out.is_empty() {
+        bail!("{}", err.trim());
+    }
+    Ok(out)
+}
+
+/// `run_git_status_env` with git's stderr as the error when it fails.
+fn run_git_checked(args: &[&str], envs: &[(&str, &str)]) -> Result<String> {
+    let (out, err, ok) = run_git_status_env(args, envs);
+    if !ok {
+        bail!("git failed: {}", err.trim());
+    }
+    Ok(out)
+}
+
+/// Untracked files in the whole repository (from any directory `dir` in it), minus
+/// ignored ones, as paths from the top like `git diff` prints them.
+pub fn get_untracked_files(dir: &Path) -> Result<Vec<String>> {
+    let d = dir.to_string_lossy();
+    let args = ["-C", &d, "ls-files", "--others", "--exclude-standard", "--full-name", "-z", ":/"];
+    let out = run_git_checked(&args, &[])?;
+    Ok(out.split('\0').filter(|l| !l.is_empty()).map(String::from).collect())
+}
+
+/// New-file diffs for untracked files, so they can be described without staging anything.
+/// One `git diff` against a scratch index where they are only added with `--intent-to-add`.
+pub fn get_untracked_diff(dir: &Path, context: u32) -> Result<String> {
+    let files = get_untracked_files(dir)?;
+    if files.is_empty() {
+        return Ok(String::new());
+    }
+    let d = dir.to_string_lossy();
+    let index = run_git_checked(&["-C", &d, "rev-parse", "--git-path", "GITAR_UNTRACKED_INDEX"], &[])?;
+    let index = dir.join(index.trim());
+    let envs = [("GIT_INDEX_FILE", index.to_str().unwrap_or_default())];
+    let pathspecs: Vec<String> = files.iter().map(|f| format!(":(top,literal){}", f)).collect();
+    let unified = format!("--unified={}", context);
+
+    let mut add = vec!["-C", &d, "add", "--intent-to-add", "--"];
+    add.extend(pathspecs.iter().map(String::as_str));
+    let mut diff = vec!["-C", &d, "diff", &unified, "--"];
+    diff.extend(pathspecs.iter().map(String::as_str));
+    let result = run_git_checked(&add, &envs).and_then(|_| run_git_checked(&diff, &envs));
+    std::fs::remove_file(&index).ok();
+    result
+}
+
+pub fn get_no_index_stats(old: &str, new: &str) -> Result<String> {
+    run_no_index(&build_no_index_args(old, new, "--stat"))
+}
+
+/// `Signed-off-by` trailer for the configured user, as `git commit -s` would write it.
+pub fn get_signoff_line() -> Option<String> {
+    let name = run_git(&["config", "--get", "user.name"]).ok()?;
+    let email = run_git(&["config", "--get", "user.email"]).ok()?;
+    let (name, email) = (name.trim(), email.trim());
+    if name.is_empty() || email.is_empty() {
+        return None;
+    }
+    Some(format!("Signed-off-by: {} <{}>", name, email))
+}
+
+/// Comment character git uses in commit message files (`core.commentChar`).
+pub fn get_comment_char() -> char {
+    run_git(&["config", "--get", "core.commentChar"])
+        .ok()
+        .and_then(|s| parse_comment_char(&s))
+        .unwrap_or('#')
+}
+
+fn parse_comment_char(value: &str) -> Option<char> {
+    let v = value.trim();
+    if v.is_empty() || v == "auto" {
+        return None;
+    }
+    v.chars().next()
+}
+
+/// Put the generated message on top of an existing commit message file,
+/// keeping git's comment lines (and everything below the scissors line).
+pub fn merge_commit_message(generated: &str, existing: &str, comment_char: char) -> String {
+    let scissors = format!("{} ------------------------ >8 ------------------------", comment_char);
+    let mut kept: Vec<&str> = Vec::new();
+    let mut lines = existing.lines();
+
+    while let Some(line) = lines.next() {
+        if line == scissors {
+            kept.push(line);
+            kept.extend(lines.by_ref());
+            break;
+        }
+        if line.starts_with(comment_char) {
+            kept.push(line);
+        }
+    }
+
+    let mut out = format!("{}\n", generated.trim());
+    if !kept.is_empty() {
+        out.push('\n');
+        out.push_str(&kept.join("\n"));
+        out.push('\n');
+    }
+    out
+}
+
 pub fn get_current_version() -> String {
     run_git(&["describe", "--tags", "--abbrev=0"])
         .map(|s| s.trim().to_string())
@@ -201,21 +680,49 @@ pub fn truncate_diff(diff: String, max: usize) -> String {
         return diff;
     }
     let mut t = diff[..max].to_string();
-    if let Some(p) = t.rfind("\ndiff --git") {
-        if p > max / 2 {
-            t.truncate(p);
-        }
+    // Prefer a file boundary, then a hunk header, so the model never sees a partial hunk
+    let cut = t
+        .rfind("\ndiff --git")
+        .filter(|&p| p > max / 2)
+        .or_else(|| t.rfind("\n@@ ").filter(|&p| p > max / 2));
+    if let Some(p) = cut {
+        t.truncate(p);
     }
     t.push_str("\n\n[... truncated ...]");
     t
 }
 
+/// What `get_current_branch` reports when HEAD is detached (e.g. CI checkouts)
+pub const DETACHED_HEAD: &str = "HEAD";
+
+pub fn get_merge_base(a: &str, b: &str) -> Option<String> {
+    run_git(&["merge-base", a, b])
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
 pub fn build_range(from: Option<&str>, to: Option<&str>, base_branch: &str) -> Option<String> {
+    range_with(from, to, base_branch, get_current_branch, || {
+        get_merge_base(base_branch, to.unwrap_or("HEAD"))
+    })
+}
+
+/// `build_range` with the branch and merge-base lookups injected.
+fn range_with(
+    from: Option<&str>,
+    to: Option<&str>,
+    base_branch: &str,
+    current_branch: impl FnOnce() -> String,
+    merge_base: impl FnOnce() -> Option<String>,
+) -> Option<String> {
     let end = to.unwrap_or("HEAD");
     from.map(|r| format!("{}..{}", r, end))
         .or_else(|| {
-            let branch = get_current_branch();
-            if branch != base_branch {
+            let branch = current_branch();
+            if branch == DETACHED_HEAD {
+                merge_base().map(|mb| format!("{}..{}", mb, end))
+            } else if branch != base_branch {
                 Some(format!("{}..{}", base_branch, if to.is_some() { end } else { &branch }))
             } else {
                 None
@@ -224,25 +731,113 @@ pub fn build_range(from: Option<&str>, to: Option<&str>, base_branch: &str) -> O
 }
 
 pub fn build_diff_target(from: Option<&str>, to: Option<&str>, base_branch: &str) -> String {
+    diff_target_with(
+        from,
+        to,
+        base_branch,
+        get_current_branch,
+        || get_merge_base(base_branch, to.unwrap_or("HEAD")),
+        get_current_version,
+    )
+}
+
+/// `build_diff_target` with the branch, merge-base and tag lookups injected.
+fn diff_target_with(
+    from: Option<&str>,
+    to: Option<&str>,
+    base_branch: &str,
+    current_branch: impl FnOnce() -> String,
+    merge_base: impl FnOnce() -> Option<String>,
+    current_version: impl FnOnce() -> String,
+) -> String {
     let end = to.unwrap_or("HEAD");
+    let since_tag = |version: String| {
+        if version != "0.0.0" {
+            format!("{}..{}", version, end)
+        } else {
+            String::new()
+        }
+    };
+
     match from {
         Some(r) => format!("{}..{}", r, end),
         None => {
-            let branch = get_current_branch();
-            if branch != base_branch {
+            let branch = current_branch();
+            if branch == DETACHED_HEAD {
+                match merge_base() {
+                    Some(mb) => format!("{}..{}", mb, end),
+                    None => since_tag(current_version()),
+                }
+            } else if branch != base_branch {
                 format!("{}...{}", base_branch, if to.is_some() { end } else { &branch })
             } else {
-                let tag = get_current_version();
-                if tag != "0.0.0" {
-                    format!("{}..{}", tag, end)
-                } else {
-                    String::new()
-                }
+                since_tag(current_version())
             }
         }
     }
 }
 
+// =============================================================================
+// TEST SUPPORT
+// =============================================================================
+#[cfg(test)]
+thread_local! {
+    /// Where `git_command` runs on this test thread (set by `temp_repo`), so tests
+    /// never change the shared process cwd
+    static TEST_REPO: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// A throwaway repository on `main` that git calls on this test thread run in; removed on drop.
+#[cfg(test)]
+pub(crate) struct TempRepo {
+    pub(crate) dir: PathBuf,
+}
+
+#[cfg(test)]
+pub(crate) fn temp_repo(name: &str) -> TempRepo {
+    let dir = std::env::temp_dir().join(format!("gitar_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    TEST_REPO.with(|repo| *repo.borrow_mut() = Some(dir.clone()));
+    let repo = TempRepo { dir };
+    repo.git(&["init", "-q", "-b", "main"]);
+    repo.git(&["config", "user.name", "t"]);
+    repo.git(&["config", "user.email", "t@t"]);
+    repo.git(&["config", "commit.gpgsign", "false"]);
+    repo
+}
+
+#[cfg(test)]
+impl TempRepo {
+    pub(crate) fn git(&self, args: &[&str]) -> String {
+        let (out, err, ok) = run_git_status(args);
+        assert!(ok, "git {:?}: {}", args, err);
+        out
+    }
+
+    pub(crate) fn write(&self, path: &str, contents: &str) {
+        let path = self.dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    /// Write `path` and commit it with `message`; the new commit's hash.
+    pub(crate) fn commit(&self, path: &str, contents: &str, message: &str) -> String {
+        self.write(path, contents);
+        self.git(&["add", "--", path]);
+        self.git(&["commit", "-q", "-m", message]);
+        self.git(&["rev-parse", "HEAD"]).trim().to_string()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempRepo {
+    fn drop(&mut self) {
+        TEST_REPO.with(|repo| *repo.borrow_mut() = None);
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
 // =============================================================================
 // MODULE TESTS
 // =============================================================================
@@ -250,6 +845,15 @@ pub fn build_diff_target(from: Option<&str>, to: Option<&str>, base_branch: &str
 mod tests {
     use super::*;
 
+    #[test]
+    fn git_config_value_parsing() {
+        assert_eq!(parse_git_config_value("gpt-4o\n"), Some("gpt-4o".into()));
+        assert_eq!(parse_git_config_value("  groq  \n"), Some("groq".into()));
+        assert_eq!(parse_git_config_value("openai\nclaude\n"), Some("claude".into()));
+        assert_eq!(parse_git_config_value(""), None);
+        assert_eq!(parse_git_config_value(" \n"), None);
+    }
+
     #[test]
     fn truncate_diff_short_unchanged() {
         let diff = "short diff content".to_string();
@@ -315,6 +919,320 @@ mod tests {
         assert!(result.contains("[... truncated ...]"));
     }
 
+    #[test]
+    fn truncate_diff_single_file_cuts_at_hunk_header() {
+        let hunk = |n: usize| format!("@@ -{n},3 +{n},3 @@\n-old {n}\n+new {n}\n{}\n", " ctx".repeat(10));
+        let diff = format!(
+            "diff --git a/big.rs b/big.rs\n--- a/big.rs\n+++ b/big.rs\n{}{}{}{}",
+            hunk(1),
+            hunk(20),
+            hunk(40),
+            hunk(60)
+        );
+        let max = diff.find("@@ -60").unwrap() + 20;
+        let result = truncate_diff(diff, max);
+
+        let body = result.strip_suffix("\n\n[... truncated ...]").unwrap();
+        assert!(body.ends_with(&" ctx".repeat(10)));
+        assert!(body.contains("+new 40"));
+        assert!(!body.contains("@@ -60"));
+    }
+
+    #[test]
+    fn truncate_diff_file_boundary_beats_hunk_header() {
+        let diff = format!(
+            "diff --git a/a.rs b/a.rs\n@@ -1 +1 @@\n{}\ndiff --git a/b.rs b/b.rs\n@@ -1 +1 @@\n{}",
+            "+a".repeat(50),
+            "+b".repeat(100)
+        );
+        let max = diff.find("diff --git a/b.rs").unwrap() + 40;
+        let result = truncate_diff(diff, max);
+        assert!(!result.contains("b.rs"));
+        assert!(result.contains("+a+a"));
+    }
+
+    #[test]
+    fn parse_comment_char_values() {
+        assert_eq!(parse_comment_char(";\n"), Some(';'));
+        assert_eq!(parse_comment_char("auto\n"), None);
+        assert_eq!(parse_comment_char(""), None);
+    }
+
+    #[test]
+    fn merge_commit_message_keeps_comment_lines() {
+        let existing = "\n# Please enter the commit message for your changes.\n# On branch main\n#\n";
+        let merged = merge_commit_message("Add parser\n", existing, '#');
+        assert_eq!(
+            merged,
+            "Add parser\n\n# Please enter the commit message for your changes.\n# On branch main\n#\n"
+        );
+    }
+
+    #[test]
+    fn merge_commit_message_drops_non_comment_lines() {
+        let existing = "old text\n; comment\n# not a comment here\n";
+        let merged = merge_commit_message("New msg", existing, ';');
+        assert_eq!(merged, "New msg\n\n; comment\n");
+    }
+
+    #[test]
+    fn merge_commit_message_keeps_verbose_diff_below_scissors() {
+        let existing = "\n# Comment\n# ------------------------ >8 ------------------------\ndiff --git a/x b/x\n+added\n";
+        let merged = merge_commit_message("Msg", existing, '#');
+        assert!(merged.starts_with("Msg\n\n# Comment\n"));
+        assert!(merged.ends_with("diff --git a/x b/x\n+added\n"));
+    }
+
+    #[test]
+    fn merge_commit_message_empty_file() {
+        assert_eq!(merge_commit_message("Msg", "", '#'), "Msg\n");
+    }
+
+    #[test]
+    fn parse_symbolic_ref_branch_variants() {
+        assert_eq!(parse_symbolic_ref_branch("refs/remotes/origin/develop\n"), Some("develop".into()));
+        assert_eq!(parse_symbolic_ref_branch("refs/remotes/origin/release/2.x"), Some("release/2.x".into()));
+        assert_eq!(parse_symbolic_ref_branch("refs/remotes/origin/"), None);
+        assert_eq!(parse_symbolic_ref_branch(""), None);
+    }
+
+    #[test]
+    fn parse_remote_head_branch_from_branch_list() {
+        let out = "  origin/HEAD -> origin/develop\n  origin/develop\n  origin/main\n";
+        assert_eq!(parse_remote_head_branch(out), Some("develop".into()));
+        assert_eq!(parse_remote_head_branch("  origin/main\n  origin/feature\n"), None);
+        assert_eq!(parse_remote_head_branch("  upstream/HEAD -> upstream/trunk\n"), None);
+    }
+
+    #[test]
+    fn parse_check_attr_set_values() {
+        let out = "gen/api.rs: linguist-generated: true\n\
+                   src/main.rs: linguist-generated: unspecified\n\
+                   schema.pb.go: linguist-generated: set\n\
+                   vendor/x.js: linguist-generated: false\n";
+        let flagged = parse_check_attr(out);
+        assert_eq!(flagged.len(), 2);
+        assert!(flagged.contains("gen/api.rs"));
+        assert!(flagged.contains("schema.pb.go"));
+    }
+
+    #[test]
+    fn parse_check_attr_path_with_colon_and_junk() {
+        let out = "docs/a: b.md: linguist-generated: true\nnot an attr line\n\n";
+        let flagged = parse_check_attr(out);
+        assert_eq!(flagged.len(), 1);
+        assert!(flagged.contains("docs/a: b.md"));
+    }
+
+    #[test]
+    fn build_log_args_no_merges_present() {
+        let args = build_log_args(&LogQuery {
+            limit: Some(5),
+            range: Some("v1..HEAD"),
+            no_merges: true,
+            ..Default::default()
+        });
+        assert!(args.contains(&"--no-merges".to_string()));
+        assert!(!args.contains(&"--first-parent".to_string()));
+        assert_eq!(args.last().unwrap(), "v1..HEAD");
+    }
+
+    #[test]
+    fn build_log_args_first_parent_present() {
+        let args = build_log_args(&LogQuery {
+            range: Some("main..feature"),
+            first_parent: true,
+            ..Default::default()
+        });
+        assert!(args.contains(&"--first-parent".to_string()));
+        assert_eq!(args.last().unwrap(), "main..feature");
+    }
+
+    #[test]
+    fn get_commit_logs_honors_limit() {
+        let repo = temp_repo("log_limit");
+        for i in 0..5 {
+            repo.commit("f.txt", &i.to_string(), &format!("Commit {}", i));
+        }
+
+        let commits = get_commit_logs(&LogQuery { limit: Some(3), ..Default::default() }).unwrap();
+        let subjects: Vec<&str> = commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(subjects, ["Commit 4", "Commit 3", "Commit 2"]);
+    }
+
+    #[test]
+    fn build_log_args_no_merges_absent() {
+        let args = build_log_args(&LogQuery {
+            limit: Some(5),
+            since: Some("2024-01-01"),
+            ..Default::default()
+        });
+        assert!(!args.contains(&"--no-merges".to_string()));
+        assert!(args.contains(&"-n5".to_string()));
+        assert!(args.contains(&"--since=2024-01-01".to_string()));
+    }
+
+    #[test]
+    fn build_log_args_single_author() {
+        let authors = ["ana@example.com".to_string()];
+        let args = build_log_args(&LogQuery {
+            range: Some("v1..HEAD"),
+            authors: &authors,
+            ..Default::default()
+        });
+        assert!(args.contains(&"--author=ana@example.com".to_string()));
+        assert_eq!(args.last().unwrap(), "v1..HEAD");
+    }
+
+    #[test]
+    fn build_log_args_multiple_authors() {
+        let authors = vec!["Ana".to_string(), "Ben Li".to_string()];
+        let args = build_log_args(&LogQuery {
+            limit: Some(5),
+            no_merges: true,
+            authors: &authors,
+            ..Default::default()
+        });
+        let author_args: Vec<&String> = args.iter().filter(|a| a.starts_with("--author=")).collect();
+        assert_eq!(author_args, ["--author=Ana", "--author=Ben Li"]);
+        assert!(!args.iter().any(|a| a.starts_with("--author") && !a.contains('=')));
+    }
+
+    #[test]
+    fn build_diff_args_default_context() {
+        let args = build_diff_args(None, true, DEFAULT_DIFF_CONTEXT, false, &[]);
+        assert_eq!(&args[..3], ["diff", "--unified=3", "--cached"]);
+        assert!(args.contains(&":(exclude)*.lock".to_string()));
+    }
+
+    #[test]
+    fn build_diff_args_custom_context_and_target() {
+        let args = build_diff_args(Some("main..HEAD"), false, 8, false, &[]);
+        assert_eq!(&args[..3], ["diff", "--unified=8", "main..HEAD"]);
+        let args = build_diff_args(None, false, 0, false, &[]);
+        assert_eq!(&args[..3], ["diff", "--unified=0", "--"]);
+    }
+
+    #[test]
+    fn build_diff_args_staged_with_pathspec() {
+        let paths = vec!["src/api".to_string(), "docs/*.md".to_string()];
+        let args = build_diff_args(None, true, 3, false, &paths);
+        let sep = args.iter().position(|a| a == "--").unwrap();
+        assert_eq!(&args[..3], ["diff", "--unified=3", "--cached"]);
+        assert_eq!(&args[sep + 1..sep + 3], ["src/api", "docs/*.md"]);
+        assert!(!args.contains(&".".to_string()));
+        assert!(args.contains(&":(exclude)*.lock".to_string()));
+    }
+
+    #[test]
+    fn build_numstat_args_mirror_diff_args() {
+        let paths = vec!["src".to_string()];
+        let args = build_numstat_args(Some("main..HEAD"), false, &paths);
+        assert_eq!(&args[..5], ["diff", "--numstat", "main..HEAD", "--", "src"]);
+        assert!(args.contains(&":(exclude)*.lock".to_string()));
+    }
+
+    #[test]
+    fn decode_git_output_counts_invalid_sequences() {
+        assert_eq!(decode_git_output("café.rs\n".as_bytes()), ("café.rs\n".to_string(), 0));
+        // Latin-1 "café.rs" and "señal": each \xe9 / \xf1 is one invalid sequence
+        let (text, invalid) = decode_git_output(b"+caf\xe9.rs\n-se\xf1al\n");
+        assert_eq!(text, "+caf\u{FFFD}.rs\n-se\u{FFFD}al\n");
+        assert_eq!(invalid, 2);
+        let warning = invalid_utf8_warning(&["diff", "--cached"], invalid);
+        assert!(warning.starts_with("Warning: `git diff` output is not valid UTF-8 (2 sequence(s)"));
+    }
+
+    #[test]
+    fn git_command_disables_color() {
+        let cmd = git_command();
+        let args: Vec<&str> = cmd.get_args().map(|a| a.to_str().unwrap()).collect();
+        assert_eq!(cmd.get_program(), "git");
+        assert!(args.windows(2).any(|w| w == ["-c", "core.quotepath=false"]));
+        assert!(args.windows(2).any(|w| w == ["-c", "color.ui=false"]));
+        assert!(args.windows(2).any(|w| w == ["-c", "color.diff=false"]));
+    }
+
+    #[test]
+    fn run_git_ignores_color_ui_always_in_repo_config() {
+        let repo = temp_repo("color");
+        repo.write("old.txt", "a\nb\n");
+        repo.write("new.txt", "a\nc\n");
+        repo.git(&["config", "color.ui", "always"]);
+
+        let out = run_git(&["diff", "--no-index", "old.txt", "new.txt"]).unwrap();
+        assert!(out.contains("+c"));
+        assert!(!out.contains('\x1b'));
+    }
+
+    #[test]
+    fn untracked_file_diffs_as_new_file() {
+        let repo = temp_repo("untracked");
+        repo.write("new.rs", "fn main() {}\n");
+        repo.write("sub/inner.rs", "mod x;\n");
+        repo.write(".gitignore", "*.log\n");
+        repo.write("debug.log", "noise\n");
+
+        // From a subdirectory: still the whole repo, with paths from the top
+        let files = get_untracked_files(&repo.dir.join("sub")).unwrap();
+        let out = get_untracked_diff(&repo.dir.join("sub"), 3).unwrap();
+        let status = repo.git(&["status", "--porcelain"]);
+
+        assert_eq!(files, [".gitignore", "new.rs", "sub/inner.rs"]);
+        assert!(out.contains("diff --git a/new.rs b/new.rs\nnew file mode"));
+        assert!(out.contains("+fn main() {}"));
+        assert!(out.contains("diff --git a/sub/inner.rs b/sub/inner.rs"));
+        assert!(!out.contains("debug.log"));
+        // Nothing was staged
+        assert!(status.lines().all(|l| l.starts_with("??")));
+        assert!(!repo.dir.join(".git/GITAR_UNTRACKED_INDEX").exists());
+        assert!(get_untracked_files(&repo.dir.join("missing")).is_err());
+    }
+
+    #[test]
+    fn no_index_diff_errors_on_missing_file() {
+        let dir = std::env::temp_dir().join(format!("gitar_no_index_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (old, new) = (dir.join("old.txt"), dir.join("new.txt"));
+        std::fs::write(&old, "a\nb\n").unwrap();
+        std::fs::write(&new, "a\nc\n").unwrap();
+        let (old, new) = (old.to_str().unwrap(), new.to_str().unwrap());
+
+        let differ = get_no_index_diff(old, new, 3);
+        let same = get_no_index_diff(old, old, 3);
+        let missing = get_no_index_diff(old, &format!("{}.gone", new), 3);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(differ.unwrap().contains("+c"));
+        assert_eq!(same.unwrap(), "");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn build_no_index_args_orders_paths_after_separator() {
+        let args = build_no_index_args("old.txt", "-new.txt", "--unified=5");
+        assert_eq!(args, ["diff", "--no-index", "--unified=5", "--", "old.txt", "-new.txt"]);
+        let args = build_no_index_args("a", "b", "--stat");
+        assert_eq!(&args[..3], ["diff", "--no-index", "--stat"]);
+    }
+
+    #[test]
+    fn word_diff_uses_porcelain_format() {
+        let args = build_diff_args(None, true, 3, true, &[]);
+        assert_eq!(&args[..4], ["diff", "--unified=3", "--word-diff=porcelain", "--cached"]);
+        let args = build_commit_diff_args("abc123", true, 3, true);
+        assert_eq!(&args[..4], ["diff", "abc123^!", "--unified=3", "--word-diff=porcelain"]);
+        assert!(!build_diff_args(None, true, 3, false, &[]).iter().any(|a| a.starts_with("--word-diff")));
+    }
+
+    #[test]
+    fn build_commit_diff_args_uses_context() {
+        let args = build_commit_diff_args("abc123", true, 0, false);
+        assert_eq!(&args[..3], ["diff", "abc123^!", "--unified=0"]);
+        let args = build_commit_diff_args("abc123", false, 5, false);
+        assert_eq!(&args[..5], ["diff-tree", "--patch", "--unified=5", "--root", "abc123"]);
+    }
+
     #[test]
     fn build_range_with_ref() {
         let result = build_range(Some("v1.0.0"), None, "main");
@@ -339,6 +1257,50 @@ mod tests {
         assert!(result.is_some() || result.is_none());
     }
 
+    #[test]
+    fn range_on_feature_branch_uses_base() {
+        let r = range_with(None, None, "main", || "feat/x".into(), || panic!("no merge-base"));
+        assert_eq!(r, Some("main..feat/x".to_string()));
+        let r = range_with(None, None, "main", || "main".into(), || None);
+        assert_eq!(r, None);
+    }
+
+    #[test]
+    fn range_on_detached_head_uses_merge_base() {
+        let r = range_with(None, None, "main", || "HEAD".into(), || Some("abc123".into()));
+        assert_eq!(r, Some("abc123..HEAD".to_string()));
+        let r = range_with(None, Some("v2"), "main", || "HEAD".into(), || Some("abc123".into()));
+        assert_eq!(r, Some("abc123..v2".to_string()));
+    }
+
+    #[test]
+    fn range_on_detached_head_without_merge_base() {
+        let r = range_with(None, None, "main", || "HEAD".into(), || None);
+        assert_eq!(r, None);
+    }
+
+    #[test]
+    fn diff_target_on_detached_head_uses_merge_base() {
+        let t = diff_target_with(None, None, "main", || "HEAD".into(), || Some("abc123".into()), || {
+            panic!("no tag lookup")
+        });
+        assert_eq!(t, "abc123..HEAD");
+    }
+
+    #[test]
+    fn diff_target_on_detached_head_falls_back_to_tag() {
+        let t = diff_target_with(None, None, "main", || "HEAD".into(), || None, || "1.2.0".into());
+        assert_eq!(t, "1.2.0..HEAD");
+        let t = diff_target_with(None, None, "main", || "HEAD".into(), || None, || "0.0.0".into());
+        assert_eq!(t, "");
+    }
+
+    #[test]
+    fn diff_target_on_feature_branch_uses_three_dots() {
+        let t = diff_target_with(None, None, "main", || "feat/x".into(), || None, || "0.0.0".into());
+        assert_eq!(t, "main...feat/x");
+    }
+
     #[test]
     fn build_diff_target_with_ref() {
         let result = build_diff_target(Some("v1.0.0"), None, "main");
@@ -364,6 +1326,7 @@ mod tests {
             author: "John Doe".into(),
             date: "2024-01-15 10:30:00 +0000".into(),
             message: "Fix bug in parser".into(),
+            body: String::new(),
         };
         assert_eq!(info.hash, "abc123def456");
         assert_eq!(info.author, "John Doe");
@@ -381,11 +1344,47 @@ mod tests {
             author: parts[1].into(),
             date: parts[2].into(),
             message: parts[3].into(),
+            body: String::new(),
         };
         assert_eq!(info.hash, "abc123def");
         assert_eq!(info.author, "John Doe");
     }
 
+    #[test]
+    fn parse_commit_log_records_with_multiline_body() {
+        let output = "abc123\x1fJohn Doe\x1f2024-01-15\x1fFix parser\x1fHandle empty input.\n\nAlso | pipes.\n\x1e\ndef456\x1fJane\x1f2024-01-16\x1fAdd docs\x1f\x1e";
+        let commits = parse_commit_log_records(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "abc123");
+        assert_eq!(commits[0].author, "John Doe");
+        assert_eq!(commits[0].message, "Fix parser");
+        assert_eq!(commits[0].body, "Handle empty input.\n\nAlso | pipes.");
+        assert_eq!(commits[1].hash, "def456");
+        assert_eq!(commits[1].message, "Add docs");
+        assert!(commits[1].body.is_empty());
+    }
+
+    #[test]
+    fn parse_commit_log_records_skips_incomplete() {
+        let commits = parse_commit_log_records("abc\x1fAuthor\x1e\n");
+        assert!(commits.is_empty());
+        assert!(parse_commit_log_records("").is_empty());
+    }
+
+    #[test]
+    fn commit_info_full_message() {
+        let mut info = CommitInfo {
+            hash: "abc".into(),
+            author: "A".into(),
+            date: "d".into(),
+            message: "Subject".into(),
+            body: String::new(),
+        };
+        assert_eq!(info.full_message(), "Subject");
+        info.body = "Body line".into();
+        assert_eq!(info.full_message(), "Subject\n\nBody line");
+    }
+
     #[test]
     fn parse_commit_log_with_pipe_in_message() {
         let line = "abc123|Author|2024-01-15|Message with | pipe | chars";
@@ -484,6 +1483,16 @@ mod tests {
         assert!(stdout.is_empty() || stderr.contains("git"));
     }
 
+    #[test]
+    fn is_git_installed_detects_git() {
+        assert!(is_git_installed());
+    }
+
+    #[test]
+    fn binary_available_rejects_missing_binary() {
+        assert!(!binary_available("gitar-no-such-binary-xyz"));
+    }
+
     #[test]
     fn is_git_repo_detects_repo() {
         let result = is_git_repo();
@@ -1,6 +1,7 @@
 // src/main.rs
 mod cli;
 mod client;
+mod color;
 mod commands;
 mod config;
 mod diff;
@@ -12,32 +13,109 @@ mod types;
 use anyhow::{bail, Result};
 use clap::Parser;
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, ConfigCommands};
 use client::LlmClient;
 use commands::*;
-use config::{Config, ResolvedConfig};
-use git::{get_default_branch, is_git_repo};
+use config::{CliOverrides, Config, ResolvedConfig, DEFAULT_MODELS_CACHE_TTL_SECS};
+use diff::DiffOptions;
+use git::{get_default_branch, is_git_installed, is_git_repo};
+use providers::ReasoningEffort;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
 
     let cli = Cli::parse();
+    color::init(cli.no_color);
     let file_config = Config::load();
 
     // Handle commands that don't need git or LLM client
     match &cli.command {
-        Commands::Init => return cmd_init(&cli, &file_config),
-        Commands::Config => return cmd_config(),
-        Commands::Hook { command } => return cmd_hook(command.clone()),
+        Commands::Init { interactive } => {
+            if *interactive || !init_has_flags(&cli) {
+                return cmd_init_wizard(&file_config, cli.offline).await;
+            }
+            return cmd_init(&cli, &file_config);
+        }
+        Commands::Config { command: None } => return cmd_config(),
+        Commands::Completions { shell } => return cmd_completions(*shell),
+        Commands::Models { cached: true, .. } => {
+            return cmd_models_cached(&resolve_config(&cli, &file_config)?.base_url);
+        }
+        Commands::Doctor => return cmd_doctor(resolve_config(&cli, &file_config), cli.offline).await,
+        Commands::Config {
+            command: Some(ConfigCommands::Validate { ping }),
+        } => {
+            let config = resolve_config(&cli, &file_config)?;
+            let client = LlmClient::new(&config)?.with_offline(cli.offline);
+            return cmd_config_validate(&config, &client, *ping).await;
+        }
         _ => {}
     }
 
+    if !is_git_installed() {
+        bail!("git executable not found in PATH");
+    }
+
+    if let Commands::Hook { command } = &cli.command {
+        return cmd_hook(command.clone());
+    }
+
+    let reasoning_effort = cli.reasoning_effort.as_deref().and_then(ReasoningEffort::from_name);
+
+    // explain --files diffs two paths with --no-index, so it works outside a repo
+    if let Commands::Explain {
+        files: Some(files),
+        audience,
+        diff_only,
+        risk_only,
+        format,
+        context_file,
+        alg,
+        ..
+    } = &cli.command
+    {
+        let config = resolve_config(&cli, &file_config)?;
+        let client = LlmClient::new(&config)?
+            .with_seed(cli.seed)
+            .with_reasoning_effort(reasoning_effort)
+            .with_offline(cli.offline)
+            .with_instruct(cli.instruct.clone());
+        let result = cmd_explain_files(
+            &client,
+            &files[0],
+            &files[1],
+            audience.clone(),
+            *diff_only,
+            *risk_only,
+            ExplainFormat::from_name(format),
+            context_file.clone(),
+            config.stream,
+            file_config.alg_for("explain", *alg),
+            &command_options(&cli, &file_config, config.max_diff_chars),
+        )
+        .await;
+        print_usage(cli.show_usage, &client);
+        exit_for_risk(result?);
+        return Ok(());
+    }
+
     // All other commands require a git repo
     if !is_git_repo() {
         bail!("Not a git repository");
     }
 
+    // Handle lint command (doesn't need LLM client)
+    if let Commands::Lint {
+        from,
+        to,
+        limit,
+        max_length,
+    } = &cli.command
+    {
+        return cmd_lint(from.clone(), to.clone(), *limit, *max_length);
+    }
+
     // Handle diff command (doesn't need LLM client)
     if let Commands::Diff {
         target,
@@ -47,67 +125,115 @@ async fn main() -> Result<()> {
         stats,
         stats_only,
         compare,
+        algo_bench,
     } = &cli.command
     {
         return cmd_diff(
             target.clone(),
             *staged,
-            *max_chars,
             *alg,
             *stats,
             *stats_only,
             *compare,
+            *algo_bench,
+            &command_options(&cli, &file_config, *max_chars).diff,
         );
     }
 
     // Build config and LLM client for remaining commands
-    let config = ResolvedConfig::new(
-        cli.api_key.as_ref(),
-        cli.model.as_ref(),
-        cli.max_tokens,
-        cli.temperature,
-        cli.base_url.as_ref(),
-        cli.provider.as_ref(),
-        cli.base_branch.as_ref(),
-        if cli.stream { Some(true) } else { None },
-        &file_config,
-        get_default_branch,
-    );
-    let client = LlmClient::new(&config)?;
+    let config = resolve_config(&cli, &file_config)?;
+    let opts = command_options(&cli, &file_config, config.max_diff_chars);
+
+    // Prompt preview builds prompts locally (doesn't need LLM client)
+    if let Commands::PromptPreview {
+        command,
+        reference,
+        staged,
+        alg,
+    } = &cli.command
+    {
+        return cmd_prompt_preview(
+            command,
+            reference.clone(),
+            *staged,
+            &config.base_branch,
+            cli.instruct.as_deref(),
+            file_config.alg_for(command, *alg),
+            &opts,
+        );
+    }
+
+    let client = LlmClient::new(&config)?
+        .with_seed(cli.seed)
+        .with_reasoning_effort(reasoning_effort)
+        .with_offline(cli.offline)
+        .with_instruct(cli.instruct.clone());
+
+    // Set by explain --risk-only
+    let mut risk = None;
 
     // Dispatch to command handlers
-    match cli.command {
+    let result = match cli.command {
         Commands::Commit {
             push,
+            set_upstream,
             all,
             tag,
             no_tag,
             write_to,
             silent,
+            signoff,
+            gpg_sign,
+            no_gpg_sign,
+            staged_only,
+            edit,
+            interactive,
+            interactive_split,
+            scope,
+            no_body,
+            ticket_from_branch,
             stream,
+            style_from_history,
             alg,
         } => {
             let do_stream = config.stream || stream;
+            let alg = file_config.commit_alg(alg, write_to.is_some());
+            let ticket_pattern = file_config.ticket_pattern.as_deref().unwrap_or(DEFAULT_TICKET_PATTERN);
+            let ticket = ticket_from_branch.map(|w| (TicketPosition::from_name(&w), ticket_pattern));
             cmd_commit(
                 &client,
                 push,
+                set_upstream,
                 all,
                 tag && !no_tag,
                 write_to,
                 silent,
+                signoff,
+                file_config.sign_for(gpg_sign, no_gpg_sign),
+                staged_only,
+                interactive_split,
+                scope,
+                no_body,
+                ticket,
+                edit,
+                interactive,
                 do_stream,
+                style_from_history,
                 alg,
-                config.max_diff_chars,
+                &opts,
             )
-            .await?
+            .await
         }
 
-        Commands::Staged { alg } => {
-            cmd_staged(&client, config.stream, alg, config.max_diff_chars).await?
+        Commands::Staged { paths, alg } => {
+            let alg = file_config.alg_for("staged", alg);
+            cmd_staged(&client, paths, config.stream, alg, &opts)
+                .await
         }
 
-        Commands::Unstaged { alg } => {
-            cmd_unstaged(&client, config.stream, alg, config.max_diff_chars).await?
+        Commands::Unstaged { include_untracked, alg } => {
+            let alg = file_config.alg_for("unstaged", alg);
+            cmd_unstaged(&client, include_untracked, config.stream, alg, &opts).await
         }
 
         Commands::History {
@@ -117,6 +243,14 @@ async fn main() -> Result<()> {
             until,
             limit,
             delay,
+            with_body,
+            no_merges,
+            first_parent,
+            strip_ai_tag,
+            authors,
+            count_only,
+            timeout_per_commit,
+            max_failures,
             alg,
         } => {
             cmd_history(
@@ -127,17 +261,71 @@ async fn main() -> Result<()> {
                 until,
                 limit,
                 delay,
+                with_body,
+                no_merges,
+                first_parent,
+                strip_ai_tag,
+                authors,
+                count_only,
+                timeout_per_commit,
+                max_failures,
                 config.stream,
-                alg,
-                config.max_diff_chars,
+                file_config.alg_for("history", alg),
+                &opts,
             )
-            .await?
+            .await
+        }
+
+        Commands::Rewrite {
+            count,
+            force,
+            no_tag,
+            gpg_sign,
+            no_gpg_sign,
+            alg,
+        } => {
+            cmd_rewrite(
+                &client,
+                count,
+                force,
+                !no_tag,
+                file_config.sign_for(gpg_sign, no_gpg_sign),
+                file_config.alg_for("rewrite", alg),
+                &opts,
+            )
+            .await
+        }
+
+        Commands::Reword {
+            commit,
+            force,
+            no_tag,
+            gpg_sign,
+            no_gpg_sign,
+            alg,
+        } => {
+            cmd_reword(
+                &client,
+                commit,
+                force,
+                !no_tag,
+                file_config.sign_for(gpg_sign, no_gpg_sign),
+                file_config.alg_for("reword", alg),
+                &opts,
+            )
+            .await
         }
 
         Commands::Pr {
             base,
             to,
             staged,
+            max_commits_diff,
+            template,
+            map_reduce,
+            no_stats,
+            compare_commits,
+            context_file,
             alg,
         } => {
             cmd_pr(
@@ -146,33 +334,53 @@ async fn main() -> Result<()> {
                 to,
                 &config.base_branch,
                 staged,
+                max_commits_diff,
+                template,
+                map_reduce,
+                no_stats,
+                compare_commits,
+                context_file,
                 config.stream,
-                alg,
-                config.max_diff_chars,
+                file_config.alg_for("pr", alg),
+                &opts,
             )
-            .await?
+            .await
         }
 
         Commands::Changelog {
             from,
+            ranges,
             to,
             since,
             until,
             limit,
+            include_merges,
+            strip_ai_tag,
+            authors,
+            group_by,
+            format,
+            count_only,
             alg,
         } => {
             cmd_changelog(
                 &client,
                 from,
+                ranges,
                 to,
                 since,
                 until,
                 limit,
+                include_merges,
+                strip_ai_tag,
+                authors,
+                group_by == "author",
+                count_only,
+                ChangelogFormat::from_name(&format),
                 config.stream,
-                alg,
-                config.max_diff_chars,
+                file_config.alg_for("changelog", alg),
+                &opts,
             )
-            .await?
+            .await
         }
 
         Commands::Explain {
@@ -181,7 +389,14 @@ async fn main() -> Result<()> {
             since,
             until,
             staged,
+            audience,
+            commit_ready,
+            diff_only,
+            risk_only,
+            format,
+            context_file,
             alg,
+            ..
         } => {
             cmd_explain(
                 &client,
@@ -191,17 +406,25 @@ async fn main() -> Result<()> {
                 until,
                 &config.base_branch,
                 staged,
+                audience,
+                commit_ready,
+                diff_only,
+                risk_only,
+                ExplainFormat::from_name(&format),
+                context_file,
                 config.stream,
-                alg,
-                config.max_diff_chars,
+                file_config.alg_for("explain", alg),
+                &opts,
             )
-            .await?
+            .await
+            .map(|level| risk = level)
         }
 
         Commands::Version {
             base,
             to,
             current,
+            json,
             alg,
         } => {
             cmd_version(
@@ -210,20 +433,82 @@ async fn main() -> Result<()> {
                 to,
                 &config.base_branch,
                 current,
+                json,
                 config.stream,
-                alg,
-                config.max_diff_chars,
+                file_config.alg_for("version", alg),
+                &opts,
             )
-            .await?
+            .await
         }
 
-        Commands::Models => cmd_models(&client).await?,
+        Commands::Models { refresh, .. } => {
+            let ttl = file_config.models_cache_ttl_secs.unwrap_or(DEFAULT_MODELS_CACHE_TTL_SECS);
+            cmd_models(&client, refresh, ttl, opts.quiet).await
+        }
 
         // Already handled above
-        Commands::Init | Commands::Config | Commands::Hook { .. } | Commands::Diff { .. } => {
-            unreachable!()
-        }
-    }
+        Commands::Init { .. }
+        | Commands::Config { .. }
+        | Commands::Completions { .. }
+        | Commands::Doctor
+        | Commands::Hook { .. }
+        | Commands::Diff { .. }
+        | Commands::Lint { .. }
+        | Commands::PromptPreview { .. } => unreachable!(),
+    };
 
+    // Tokens already spent count even when the command then fails
+    print_usage(cli.show_usage, &client);
+    result?;
+    exit_for_risk(risk);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `explain --risk-only`: exit with the level's code, after everything else is printed
+fn exit_for_risk(risk: Option<RiskLevel>) {
+    if let Some(code) = risk.map(RiskLevel::exit_code).filter(|&code| code != 0) {
+        std::process::exit(code);
+    }
+}
+
+/// `--show-usage`: tokens summed over the command's requests, on stderr
+fn print_usage(show: bool, client: &LlmClient) {
+    if show {
+        eprintln!("{}", client.usage_line());
+    }
+}
+
+/// Global flags and config settings the commands share
+fn command_options(cli: &Cli, file_config: &Config, max_diff_chars: usize) -> CommandOptions {
+    CommandOptions {
+        quiet: cli.quiet || cli.command.machine_output(),
+        emoji: cli.emoji || file_config.emoji.unwrap_or(false),
+        subject_max_len: file_config.subject_max_len.unwrap_or(0),
+        diff: DiffOptions {
+            context: cli.context,
+            word_diff: cli.word_diff,
+            max_chars: max_diff_chars,
+            changed_only: cli.changed_only,
+            max_files: cli.max_files.unwrap_or(0),
+            max_diff_files: file_config.max_diff_files.unwrap_or(diff::DEFAULT_MAX_DIFF_FILES),
+            max_line_chars: file_config.max_diff_line_chars.unwrap_or(diff::DEFAULT_MAX_LINE_CHARS),
+        },
+    }
+}
+
+/// Merge CLI flags, GITAR_* env vars and the config file
+fn resolve_config(cli: &Cli, file_config: &Config) -> Result<ResolvedConfig> {
+    let overrides = CliOverrides {
+        api_key: cli.api_key.clone(),
+        model: cli.model.clone(),
+        max_tokens: cli.max_tokens,
+        temperature: cli.temperature,
+        base_url: cli.base_url.clone(),
+        provider: cli.provider.clone(),
+        base_branch: cli.base_branch.clone(),
+        stream: if cli.stream { Some(true) } else { None },
+        api_key_file: cli.api_key_file.clone(),
+        command: Some(cli.command.name().to_string()),
+    };
+    ResolvedConfig::new(&overrides, file_config, get_default_branch)
+}
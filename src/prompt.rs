@@ -51,11 +51,46 @@ Examples:
 "Refactor database queries for connection pooling"
 "#;
 
+/// `commit --no-body`: appended to the commit system prompt
+pub const COMMIT_NO_BODY_PROMPT: &str = "\nWrite only the subject line: no body, no bullet points.\n";
+
+/// `--emoji`: the commit prompt with a gitmoji prefix instead of the ASCII-only rule
+pub const COMMIT_EMOJI_SYSTEM_PROMPT: &str = r#"You generate clear and informative gitmoji-style Git commit messages from diffs.
+
+Format: <gitmoji> <type>: <description>
+
+Gitmoji:
+:sparkles: feat - new feature
+:bug: fix - bug fix
+:recycle: refactor - code restructuring without behavior change
+:memo: docs - documentation
+:art: style - formatting, no logic change
+:white_check_mark: test - adding or updating tests
+:wrench: chore - build, dependencies, config
+:zap: perf - performance improvement
+
+Rules:
+1. Focus on PURPOSE, not file listings
+2. Ignore build/minified files
+3. No markdown. Use the :shortcode: form of the gitmoji. Do not use empty lines between lines.
+4. Be specific
+
+Examples:
+":sparkles: feat: Add user authentication with OAuth2 support"
+":bug: fix: Fix payment timeout with retry logic"
+":recycle: refactor: Refactor database queries for connection pooling"
+"#;
+
+pub const COMMIT_STYLE_PROMPT: &str = r#"
+Match the style (tone, casing, prefixes, length) of these recent commits from this repository:
+{examples}
+"#;
+
 pub const COMMIT_USER_PROMPT: &str = r#"Generate a commit message in a single-line.
 ```
 {diff}
 ```
-Respond with ONLY the commit message. (single-line)"#;
+{scope_hint}Respond with ONLY the commit message. (single-line)"#;
 
 pub const PR_SYSTEM_PROMPT: &str = r#"Write a PR description.
 
@@ -95,6 +130,48 @@ pub const PR_USER_PROMPT: &str = r#"Generate PR description.
 ```
 "#;
 
+pub const PR_TEMPLATE_SYSTEM_PROMPT: &str = r#"Write a PR description by filling in the repository's PR template.
+
+Use plain ASCII characters only. Do not use emojis or Unicode symbols.
+
+Keep every heading of the template, in order, and fill each section from the diff and commits.
+Replace placeholder text and HTML comments with real content.
+Leave checkboxes unchecked unless the changes clearly satisfy them.
+Write "N/A" for sections that do not apply.
+
+Template:
+{template}"#;
+
+pub const PR_GROUP_SYSTEM_PROMPT: &str = r#"Summarize one part of a large pull request.
+
+Use plain ASCII characters only. Do not use emojis or Unicode symbols.
+
+List the notable changes in these files as short bullets (what and why).
+Mention breaking changes or risks explicitly. No headings, no preamble."#;
+
+pub const PR_GROUP_USER_PROMPT: &str = r#"Summarize part {part} of {parts} of branch {branch}.
+
+**Diff:**
+```
+{diff}
+```
+"#;
+
+pub const PR_REDUCE_USER_PROMPT: &str = r#"Generate PR description.
+
+The diff was too large to send at once; these are summaries of each part.
+
+**Branch:** {branch}
+**Commits:**
+{commits}
+
+**Stats:**
+{stats}
+
+**Summaries:**
+{summaries}
+"#;
+
 pub const CHANGELOG_SYSTEM_PROMPT: &str = r#"Create release notes.
 
 Use plain ASCII characters only. Do not use emojis or Unicode symbols.
@@ -109,6 +186,18 @@ Format:
 
 Group related changes, omit empty sections."#;
 
+pub const CHANGELOG_BY_AUTHOR_SYSTEM_PROMPT: &str = r#"Create release notes grouped by contributor.
+
+Use plain ASCII characters only. Do not use emojis or Unicode symbols.
+
+Format:
+# Release Notes
+## <Author>
+- Change
+
+Keep the author grouping given in the commit list, one section per author in the same order.
+Summarize each author's changes; do not regroup them by type."#;
+
 pub const CHANGELOG_USER_PROMPT: &str = r#"Generate release notes.
 
 **Range:** {range}
@@ -117,8 +206,8 @@ pub const CHANGELOG_USER_PROMPT: &str = r#"Generate release notes.
 **Commits:**
 {commits}"#;
 
-pub const EXPLAIN_SYSTEM_PROMPT: &str = r#"Explain code changes to non-technical stakeholders.
-No jargon, focus on user impact, be brief.
+pub const EXPLAIN_SYSTEM_PROMPT: &str = r#"Explain code changes to {audience}.
+{focus}
 
 Use plain ASCII characters only. Do not use emojis or Unicode symbols.
 
@@ -135,7 +224,25 @@ Low/Medium/High
 ## Actions
 - QA needed"#;
 
-pub const EXPLAIN_USER_PROMPT: &str = r#"Explain for non-technical person.
+pub const EXPLAIN_USER_PROMPT: &str = r#"Explain for {audience}.
+
+**Stats:**
+{stats}
+
+**Diff:**
+```
+{diff}
+```"#;
+
+/// `explain --risk-only`: just the risk line, for triage and scripts
+pub const EXPLAIN_RISK_SYSTEM_PROMPT: &str = r#"Assess the risk of code changes.
+
+Reply with exactly one line and nothing else:
+Risk Level: <Low|Medium|High> - <one-sentence justification>
+
+Use plain ASCII characters only."#;
+
+pub const EXPLAIN_RISK_USER_PROMPT: &str = r#"Assess the risk of {range}.
 
 **Stats:**
 {stats}
@@ -145,6 +252,78 @@ pub const EXPLAIN_USER_PROMPT: &str = r#"Explain for non-technical person.
 {diff}
 ```"#;
 
+/// Appended to every system prompt with `--instruct`
+pub const INSTRUCT_PROMPT: &str = "\n\nAdditional instructions for this request:\n{instruct}";
+
+/// `system` with the `--instruct` text appended (unchanged without one).
+pub fn add_instruction(system: &str, instruct: Option<&str>) -> String {
+    match instruct.map(str::trim).filter(|i| !i.is_empty()) {
+        Some(i) => format!("{}{}", system.trim_end(), INSTRUCT_PROMPT.replace("{instruct}", i)),
+        None => system.to_string(),
+    }
+}
+
+/// Appended to the `pr` / `explain` user prompt with `--context-file`
+pub const ADDITIONAL_CONTEXT_PROMPT: &str = r#"
+
+**Additional context:**
+{context}"#;
+
+/// Audience presets for `explain --audience`: (names, audience, focus).
+const EXPLAIN_AUDIENCES: &[(&[&str], &str, &str)] = &[
+    (
+        &["stakeholder", "stakeholders", "business"],
+        "non-technical stakeholders",
+        "No jargon, focus on user impact, be brief.",
+    ),
+    (
+        &["qa", "tester", "testers"],
+        "QA engineers",
+        "Focus on changed behavior, edge cases, and what needs to be tested.",
+    ),
+    (
+        &["security", "secops"],
+        "security reviewers",
+        "Focus on attack surface, authentication, input validation, secrets, and data handling.",
+    ),
+    (
+        &["support", "helpdesk"],
+        "customer support staff",
+        "No jargon, focus on user-visible behavior changes and questions customers may ask.",
+    ),
+    (
+        &["developer", "developers", "dev", "engineer"],
+        "software engineers",
+        "Be precise and technical, focus on design, interfaces, and side effects.",
+    ),
+];
+
+/// Resolve an `--audience` value to (audience, focus). Unknown values are used verbatim.
+pub fn explain_audience(audience: Option<&str>) -> (String, String) {
+    let key = audience.map(|a| a.trim()).unwrap_or("");
+    if key.is_empty() {
+        let (_, who, focus) = EXPLAIN_AUDIENCES[0];
+        return (who.into(), focus.into());
+    }
+    EXPLAIN_AUDIENCES
+        .iter()
+        .find(|(names, _, _)| names.iter().any(|n| n.eq_ignore_ascii_case(key)))
+        .map(|(_, who, focus)| (who.to_string(), focus.to_string()))
+        .unwrap_or_else(|| {
+            (
+                key.to_string(),
+                "Tailor the terminology and level of detail to this audience.".into(),
+            )
+        })
+}
+
+pub fn build_explain_system_prompt(audience: Option<&str>) -> String {
+    let (who, focus) = explain_audience(audience);
+    EXPLAIN_SYSTEM_PROMPT
+        .replace("{audience}", &who)
+        .replace("{focus}", &focus)
+}
+
 pub const VERSION_SYSTEM_PROMPT: &str = r#"Recommend semantic version bump.
 - MAJOR: Breaking changes
 - MINOR: New features
@@ -162,6 +341,210 @@ pub const VERSION_USER_PROMPT: &str = r#"Recommend version bump.
 {diff}
 ```"#;
 
+pub const VERSION_JSON_SYSTEM_PROMPT: &str = r#"Recommend semantic version bump.
+- MAJOR: Breaking changes
+- MINOR: New features
+- PATCH: Fixes/refactors
+
+Use plain ASCII characters only. Do not use emojis or Unicode symbols.
+
+Respond with a single JSON object and nothing else:
+{"bump": "major|minor|patch", "next_version": "x.y.z", "breaking": true|false, "reasoning": "..."}"#;
+
+// =============================================================================
+// PROMPT BUILDERS
+// =============================================================================
+
+/// Fully substituted prompts, exactly as sent to the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub system: String,
+    pub user: String,
+}
+
+/// Scope line of the commit user prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommitScope<'a> {
+    /// Guessed from the changed paths; the model may pick another
+    Detected(&'a str),
+    /// `commit --scope`
+    Forced(&'a str),
+}
+
+/// Commit prompt; `subject_max_len` (0 = no limit) adds a length rule after the others.
+pub fn commit_prompt(
+    diff: &str,
+    scope: Option<CommitScope>,
+    emoji: bool,
+    subject_max_len: usize,
+) -> Prompt {
+    let scope_hint = match scope {
+        Some(CommitScope::Detected(s)) => format!("Suggested scope (from changed paths): {}\n", s),
+        Some(CommitScope::Forced(s)) => format!("Use scope: {}\n", s),
+        None => String::new(),
+    };
+    let mut system = if emoji { COMMIT_EMOJI_SYSTEM_PROMPT } else { COMMIT_SYSTEM_PROMPT }.to_string();
+    if subject_max_len > 0 {
+        let rule = format!("4. Be specific\n5. Keep the subject under {} characters\n", subject_max_len);
+        system = system.replacen("4. Be specific\n", &rule, 1);
+    }
+    Prompt {
+        system,
+        // Diff last: diffs of prompt templates contain the placeholders themselves
+        user: COMMIT_USER_PROMPT
+            .replace("{scope_hint}", &scope_hint)
+            .replace("{diff}", diff),
+    }
+}
+
+/// Add `--context-file` text (a design note, ticket) to the user prompt.
+pub fn add_additional_context(prompt: &mut Prompt, context: &str) {
+    prompt
+        .user
+        .push_str(&ADDITIONAL_CONTEXT_PROMPT.replace("{context}", context.trim_end()));
+}
+
+/// Few-shot block appended to the commit system prompt; empty without examples.
+pub fn commit_style_block(subjects: &[String]) -> String {
+    if subjects.is_empty() {
+        return String::new();
+    }
+    let examples = subjects
+        .iter()
+        .map(|s| format!("\"{}\"", s))
+        .collect::<Vec<_>>()
+        .join("\n");
+    COMMIT_STYLE_PROMPT.replace("{examples}", &examples)
+}
+
+pub fn history_prompt(original_message: &str, diff: &str) -> Prompt {
+    Prompt {
+        system: HISTORY_SYSTEM_PROMPT.into(),
+        user: HISTORY_USER_PROMPT
+            .replace("{original_message}", original_message)
+            .replace("{diff}", diff),
+    }
+}
+
+/// PR system prompt: the built-in format, or the repo's template to fill in.
+pub fn pr_system_prompt(template: Option<&str>) -> String {
+    match template.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(t) => PR_TEMPLATE_SYSTEM_PROMPT.replace("{template}", t),
+        None => PR_SYSTEM_PROMPT.into(),
+    }
+}
+
+pub fn pr_prompt(
+    branch: &str,
+    commits: &str,
+    stats: &str,
+    diff: &str,
+    template: Option<&str>,
+) -> Prompt {
+    Prompt {
+        system: pr_system_prompt(template),
+        user: PR_USER_PROMPT
+            .replace("{branch}", branch)
+            .replace("{commits}", commits)
+            .replace("{stats}", stats)
+            .replace("{diff}", diff),
+    }
+}
+
+/// Map step of `pr --map-reduce`: summarize one group of files.
+pub fn pr_group_prompt(branch: &str, part: usize, parts: usize, diff: &str) -> Prompt {
+    Prompt {
+        system: PR_GROUP_SYSTEM_PROMPT.into(),
+        user: PR_GROUP_USER_PROMPT
+            .replace("{part}", &part.to_string())
+            .replace("{parts}", &parts.to_string())
+            .replace("{branch}", branch)
+            .replace("{diff}", diff),
+    }
+}
+
+/// Reduce step of `pr --map-reduce`: write the description from the group summaries.
+pub fn pr_reduce_prompt(
+    branch: &str,
+    commits: &str,
+    stats: &str,
+    summaries: &[String],
+    template: Option<&str>,
+) -> Prompt {
+    let summaries = summaries
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("### Part {}\n{}", i + 1, s.trim()))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    Prompt {
+        system: pr_system_prompt(template),
+        user: PR_REDUCE_USER_PROMPT
+            .replace("{branch}", branch)
+            .replace("{commits}", commits)
+            .replace("{stats}", stats)
+            .replace("{summaries}", &summaries),
+    }
+}
+
+pub fn changelog_prompt(
+    range: &str,
+    count: usize,
+    commits: &str,
+    diff: &str,
+    by_author: bool,
+) -> Prompt {
+    let system = if by_author {
+        CHANGELOG_BY_AUTHOR_SYSTEM_PROMPT
+    } else {
+        CHANGELOG_SYSTEM_PROMPT
+    };
+    Prompt {
+        system: system.into(),
+        user: CHANGELOG_USER_PROMPT
+            .replace("{range}", range)
+            .replace("{count}", &count.to_string())
+            .replace("{commits}", commits)
+            .replace("{diff}", diff),
+    }
+}
+
+pub fn explain_prompt(audience: Option<&str>, range: &str, stats: &str, diff: &str) -> Prompt {
+    let (who, _) = explain_audience(audience);
+    Prompt {
+        system: build_explain_system_prompt(audience),
+        user: EXPLAIN_USER_PROMPT
+            .replace("{audience}", &who)
+            .replace("{range}", range)
+            .replace("{stats}", stats)
+            .replace("{diff}", diff),
+    }
+}
+
+pub fn explain_risk_prompt(range: &str, stats: &str, diff: &str) -> Prompt {
+    Prompt {
+        system: EXPLAIN_RISK_SYSTEM_PROMPT.to_string(),
+        user: EXPLAIN_RISK_USER_PROMPT
+            .replace("{range}", range)
+            .replace("{stats}", stats)
+            .replace("{diff}", diff),
+    }
+}
+
+pub fn version_prompt(version: &str, diff: &str, json: bool) -> Prompt {
+    let system = if json {
+        VERSION_JSON_SYSTEM_PROMPT
+    } else {
+        VERSION_SYSTEM_PROMPT
+    };
+    Prompt {
+        system: system.into(),
+        user: VERSION_USER_PROMPT
+            .replace("{version}", version)
+            .replace("{diff}", diff),
+    }
+}
+
 // =============================================================================
 // MODULE TESTS
 // =============================================================================
@@ -169,6 +552,117 @@ pub const VERSION_USER_PROMPT: &str = r#"Recommend version bump.
 mod tests {
     use super::*;
 
+    #[test]
+    fn builders_leave_no_placeholders() {
+        let prompts = [
+            commit_prompt("D", None, false, 0),
+            commit_prompt("D", Some(CommitScope::Detected("auth")), false, 0),
+            commit_prompt("D", None, true, 50),
+            history_prompt("M", "D"),
+            pr_prompt("B", "C", "S", "D", None),
+            pr_prompt("B", "C", "S", "D", Some("## T")),
+            pr_group_prompt("B", 1, 2, "D"),
+            pr_reduce_prompt("B", "C", "S", &["X".into()], None),
+            changelog_prompt("R", 3, "C", "D", false),
+            changelog_prompt("R", 3, "C", "D", true),
+            explain_prompt(Some("qa"), "R", "S", "D"),
+            explain_risk_prompt("R", "S", "D"),
+            version_prompt("1.0.0", "D", false),
+            version_prompt("1.0.0", "D", true),
+        ];
+        let placeholders = [
+            "{diff}", "{branch}", "{commits}", "{stats}", "{range}", "{count}",
+            "{version}", "{audience}", "{focus}", "{original_message}", "{part}", "{parts}",
+            "{summaries}", "{scope_hint}", "{template}",
+        ];
+        for p in prompts {
+            for placeholder in placeholders {
+                assert!(!p.system.contains(placeholder), "{} left in system", placeholder);
+                assert!(!p.user.contains(placeholder), "{} left in user", placeholder);
+            }
+        }
+    }
+
+    #[test]
+    fn commit_prompt_matches_templates() {
+        let p = commit_prompt("+fn main() {}", None, false, 0);
+        assert_eq!(p.system, COMMIT_SYSTEM_PROMPT);
+        assert_eq!(
+            p.user,
+            COMMIT_USER_PROMPT
+                .replace("{diff}", "+fn main() {}")
+                .replace("{scope_hint}", "")
+        );
+    }
+
+    #[test]
+    fn commit_prompt_emoji_selects_gitmoji_variant() {
+        let p = commit_prompt("+x", Some(CommitScope::Detected("auth")), true, 0);
+        assert_eq!(p.system, COMMIT_EMOJI_SYSTEM_PROMPT);
+        assert!(p.system.contains(":sparkles: feat:"));
+        assert!(!p.system.contains("ASCII"));
+        assert!(!p.system.contains("Do not use emojis"));
+        // Same user prompt either way
+        assert_eq!(p.user, commit_prompt("+x", Some(CommitScope::Detected("auth")), false, 0).user);
+    }
+
+    #[test]
+    fn commit_prompt_forced_scope_replaces_suggestion() {
+        let p = commit_prompt("+x", Some(CommitScope::Forced("billing")), false, 0);
+        assert!(p.user.contains("Use scope: billing\nRespond"));
+        assert!(!p.user.contains("Suggested scope"));
+    }
+
+    #[test]
+    fn commit_prompt_injects_subject_max_len() {
+        for emoji in [false, true] {
+            let p = commit_prompt("+x", None, emoji, 50);
+            assert!(p.system.contains("4. Be specific\n5. Keep the subject under 50 characters\n"));
+        }
+        assert!(!commit_prompt("+x", None, false, 0).system.contains("Keep the subject"));
+    }
+
+    #[test]
+    fn instruction_appended_to_system_prompt() {
+        let system = commit_prompt("+x", None, false, 0).system;
+        let out = add_instruction(&system, Some("  focus on the security implications \n"));
+        assert!(out.starts_with(system.trim_end()));
+        assert!(out.ends_with("Additional instructions for this request:\nfocus on the security implications"));
+        assert_eq!(add_instruction(&system, None), system);
+        assert_eq!(add_instruction(&system, Some("  ")), system);
+    }
+
+    #[test]
+    fn additional_context_appended_to_user_prompt() {
+        let mut p = explain_prompt(None, "staged", "S", "+x");
+        let user = p.user.clone();
+        add_additional_context(&mut p, "Design: retries are capped at 3.\n\n");
+        assert_eq!(p.user, format!("{}\n\n**Additional context:**\nDesign: retries are capped at 3.", user));
+        assert!(!p.system.contains("Additional context"));
+    }
+
+    #[test]
+    fn commit_style_block_lists_examples() {
+        assert_eq!(commit_style_block(&[]), "");
+        let block = commit_style_block(&["Fix(api): Handle 404".into(), "Feat: Add export".into()]);
+        assert!(block.contains("\"Fix(api): Handle 404\"\n\"Feat: Add export\""));
+        assert!(!block.contains("{examples}"));
+    }
+
+    #[test]
+    fn commit_prompt_includes_scope_hint() {
+        let p = commit_prompt("+x", Some(CommitScope::Detected("auth")), false, 0);
+        assert!(p.user.contains("Suggested scope (from changed paths): auth\nRespond"));
+    }
+
+    #[test]
+    fn commit_prompt_leaves_placeholders_in_diff_alone() {
+        let diff = "+{scope_hint}Respond with ONLY the commit message.\n+.replace(\"{diff}\", diff)";
+        let p = commit_prompt(diff, Some(CommitScope::Forced("prompt")), false, 0);
+        assert!(p.user.contains(diff));
+        assert_eq!(p.user.matches("Use scope: prompt").count(), 1);
+    }
+
     #[test]
     fn system_prompts_not_empty() {
         assert!(!HISTORY_SYSTEM_PROMPT.is_empty());
@@ -197,9 +691,13 @@ mod tests {
             HISTORY_SYSTEM_PROMPT,
             COMMIT_SYSTEM_PROMPT,
             PR_SYSTEM_PROMPT,
+            PR_GROUP_SYSTEM_PROMPT,
+            PR_TEMPLATE_SYSTEM_PROMPT,
             CHANGELOG_SYSTEM_PROMPT,
+            CHANGELOG_BY_AUTHOR_SYSTEM_PROMPT,
             EXPLAIN_SYSTEM_PROMPT,
             VERSION_SYSTEM_PROMPT,
+            VERSION_JSON_SYSTEM_PROMPT,
         ];
         for prompt in prompts {
             assert!(
@@ -262,6 +760,27 @@ mod tests {
         assert!(!prompt.contains("{diff}"));
     }
 
+    #[test]
+    fn pr_prompt_uses_template_when_given() {
+        let template = "## Description\n<!-- what -->\n\n## Checklist\n- [ ] Tests";
+        let p = pr_prompt("B", "C", "S", "D", Some(template));
+        assert!(p.system.contains(template));
+        assert!(!p.system.contains("## Rollout"));
+
+        assert_eq!(pr_prompt("B", "C", "S", "D", None).system, PR_SYSTEM_PROMPT);
+        assert_eq!(pr_prompt("B", "C", "S", "D", Some("  \n")).system, PR_SYSTEM_PROMPT);
+    }
+
+    #[test]
+    fn pr_reduce_prompt_numbers_summaries() {
+        let p = pr_reduce_prompt("feat/x", "- a", "2 files", &["- one".into(), "- two\n".into()], None);
+        assert_eq!(p.system, PR_SYSTEM_PROMPT);
+        assert!(p.user.contains("### Part 1\n- one\n\n### Part 2\n- two"));
+
+        let g = pr_group_prompt("feat/x", 2, 5, "+x");
+        assert!(g.user.contains("part 2 of 5 of branch feat/x"));
+    }
+
     #[test]
     fn changelog_prompt_substitution() {
         let prompt = CHANGELOG_USER_PROMPT
@@ -290,11 +809,44 @@ mod tests {
     #[test]
     fn explain_prompt_substitution() {
         let prompt = EXPLAIN_USER_PROMPT
+            .replace("{audience}", "QA engineers")
             .replace("{stats}", "5 files, +100 -50")
             .replace("{diff}", "diff here");
+        assert!(prompt.contains("Explain for QA engineers."));
         assert!(prompt.contains("5 files, +100 -50"));
         assert!(prompt.contains("diff here"));
+        assert!(!prompt.contains("{audience}"));
         assert!(!prompt.contains("{stats}"));
         assert!(!prompt.contains("{diff}"));
     }
+
+    #[test]
+    fn explain_default_audience_is_stakeholders() {
+        let prompt = build_explain_system_prompt(None);
+        assert!(prompt.starts_with(
+            "Explain code changes to non-technical stakeholders.\nNo jargon, focus on user impact, be brief."
+        ));
+        assert!(!prompt.contains("{audience}"));
+        assert!(!prompt.contains("{focus}"));
+    }
+
+    #[test]
+    fn explain_security_audience_preset() {
+        let prompt = build_explain_system_prompt(Some("Security"));
+        assert!(prompt.contains("Explain code changes to security reviewers."));
+        assert!(prompt.contains("attack surface"));
+        assert!(prompt.contains("data handling"));
+    }
+
+    #[test]
+    fn explain_free_form_audience_injected() {
+        let prompt = build_explain_system_prompt(Some("the legal team"));
+        assert!(prompt.contains("Explain code changes to the legal team."));
+        assert!(prompt.contains("Tailor the terminology"));
+    }
+
+    #[test]
+    fn explain_blank_audience_uses_default() {
+        assert_eq!(explain_audience(Some("  ")), explain_audience(None));
+    }
 }
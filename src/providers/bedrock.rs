@@ -0,0 +1,518 @@
+// src/providers/bedrock.rs
+//
+// Claude on AWS Bedrock: the Anthropic messages body sent to `invoke-model`, signed
+// with SigV4 using credentials from the standard AWS_* env vars.
+use anyhow::{bail, Context, Result};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use reqwest::{Client, Method};
+use ring::{digest, hmac};
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::claude::{thinking_block, thinking_budget};
+use super::{ChatRequest, Endpoint, Provider, ReasoningEffort, Usage};
+use crate::types::*;
+
+pub const BEDROCK_ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+/// Region from a `bedrock-runtime.<region>.amazonaws.com` (or VPC endpoint) URL.
+fn region_from_url(base_url: &str) -> Option<String> {
+    let host = host_of(base_url);
+    let mut labels = host.split('.');
+    while let Some(label) = labels.next() {
+        if label.starts_with("bedrock-runtime") || label == "bedrock" {
+            return labels.next().filter(|r| !r.is_empty()).map(str::to_string);
+        }
+    }
+    None
+}
+
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    rest.split(['/', '?']).next().unwrap_or(rest)
+}
+
+/// Bedrock's `invoke-model` body for Anthropic models: the messages API without
+/// `model` (it is in the URL) or `stream`. Thinking as in `claude::build_request`.
+pub fn build_invoke_body(
+    model: &str,
+    system: &str,
+    turns: &[ChatMessage],
+    max_tokens: u32,
+    temperature: f32,
+    reasoning_effort: Option<ReasoningEffort>,
+) -> BedrockClaudeRequest {
+    let budget = thinking_budget(model, reasoning_effort);
+    BedrockClaudeRequest {
+        anthropic_version: BEDROCK_ANTHROPIC_VERSION.to_string(),
+        messages: turns.to_vec(),
+        system: (!system.trim().is_empty()).then(|| system.to_string()),
+        max_tokens: max_tokens + budget.unwrap_or(0),
+        temperature: if budget.is_some() { None } else { Some(temperature) },
+        thinking: budget.map(thinking_block),
+    }
+}
+
+/// Request path for a model; ids like `...-v1:0` are percent-encoded.
+fn invoke_path(model: &str) -> String {
+    format!("/model/{}/invoke", uri_encode(model))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn chat(
+    http: &Client,
+    base_url: &str,
+    model: &str,
+    max_tokens: u32,
+    temperature: f32,
+    system: &str,
+    turns: &[ChatMessage],
+    stream: bool,
+    reasoning_effort: Option<ReasoningEffort>,
+    usage: &Usage,
+) -> Result<String> {
+    let region = region_from_url(base_url)
+        .with_context(|| format!("Cannot tell the AWS region from base URL: {}", base_url))?;
+    let creds = Credentials::from_env()?;
+
+    let body = build_invoke_body(model, system, turns, max_tokens, temperature, reasoning_effort);
+    let body = serde_json::to_vec(&body)?;
+    let path = invoke_path(model);
+    let response = send_signed(http, &creds, &region, Method::POST, base_url, &path, "", body).await?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .context("Failed to read response body")?;
+    if !status.is_success() {
+        return Err(ApiFailure::from_response(status, &body).into());
+    }
+
+    let resp: ClaudeResponse =
+        serde_json::from_str(&body).context("Failed to parse Bedrock response")?;
+    if let Some(u) = &resp.usage {
+        usage.record(u.input_tokens, u.output_tokens);
+    }
+    // With extended thinking the text block follows a thinking block
+    let text = response_text(
+        "Bedrock API",
+        resp.content.iter().find_map(|c| c.text.as_deref()),
+        resp.stop_reason.as_deref(),
+    )?;
+
+    // invoke-model does not stream; print the whole answer so --stream still shows it
+    if stream {
+        println!("{}", text);
+        io::stdout().flush()?;
+    }
+    Ok(text)
+}
+
+/// Claude inference profiles in the region (the ids `invoke-model` accepts on demand).
+pub async fn list_models(http: &Client, base_url: &str) -> Result<Vec<String>> {
+    let region = region_from_url(base_url)
+        .with_context(|| format!("Cannot tell the AWS region from base URL: {}", base_url))?;
+    let creds = Credentials::from_env()?;
+
+    let control = format!("https://bedrock.{}.amazonaws.com", region);
+    let response = send_signed(
+        http,
+        &creds,
+        &region,
+        Method::GET,
+        &control,
+        "/inference-profiles",
+        "maxResults=1000",
+        Vec::new(),
+    )
+    .await?;
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .context("Failed to read response body")?;
+    if !status.is_success() {
+        return Err(ApiFailure::from_response(status, &body).into());
+    }
+
+    let resp: BedrockProfilesResponse =
+        serde_json::from_str(&body).context("Failed to parse inference profiles response")?;
+    Ok(claude_profiles(resp))
+}
+
+fn claude_profiles(resp: BedrockProfilesResponse) -> Vec<String> {
+    resp.inference_profile_summaries
+        .into_iter()
+        .map(|p| p.inference_profile_id)
+        .filter(|id| id.contains("anthropic.claude"))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn send_signed(
+    http: &Client,
+    creds: &Credentials,
+    region: &str,
+    method: Method,
+    base_url: &str,
+    path: &str,
+    query: &str,
+    body: Vec<u8>,
+) -> Result<reqwest::Response> {
+    let base = base_url.trim_end_matches('/');
+    let url = if query.is_empty() {
+        format!("{}{}", base, path)
+    } else {
+        format!("{}{}?{}", base, path, query)
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let headers = sign(
+        creds,
+        region,
+        "bedrock",
+        method.as_str(),
+        host_of(base),
+        path,
+        query,
+        &body,
+        &amz_date(now),
+    );
+
+    let mut req = http
+        .request(method, &url)
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json");
+    for (name, value) in headers {
+        req = req.header(name, value);
+    }
+    req.body(body).send().await.context("Failed to send request")
+}
+
+// =============================================================================
+// SIGV4
+// =============================================================================
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    /// AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY (+ AWS_SESSION_TOKEN for temporary creds)
+    pub fn from_env() -> Result<Self> {
+        let var = |k: &str| std::env::var(k).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let (Some(access_key_id), Some(secret_access_key)) =
+            (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY"))
+        else {
+            bail!("Bedrock needs AWS credentials: set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY");
+        };
+        Ok(Self {
+            access_key_id,
+            secret_access_key,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+}
+
+/// RFC 3986 encoding as SigV4 wants it: everything but unreserved characters.
+fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data.as_bytes()).as_ref().to_vec()
+}
+
+/// `YYYYMMDDTHHMMSSZ` for a Unix timestamp (UTC)
+fn amz_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Headers to add to the request (`x-amz-date`, optional `x-amz-security-token`,
+/// `authorization`). `path` is the already-encoded request path; SigV4 encodes it
+/// once more for the canonical request. `query` must be in canonical order.
+#[allow(clippy::too_many_arguments)]
+fn sign(
+    creds: &Credentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    host: &str,
+    path: &str,
+    query: &str,
+    body: &[u8],
+    amz_date: &str,
+) -> Vec<(&'static str, String)> {
+    let date = &amz_date[..8];
+
+    let mut headers = vec![("host", host.to_string()), ("x-amz-date", amz_date.to_string())];
+    if let Some(token) = &creds.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+    let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+
+    let canonical_uri = path.split('/').map(uri_encode).collect::<Vec<_>>().join("/");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        canonical_uri,
+        query,
+        canonical_headers,
+        signed_headers,
+        sha256_hex(body)
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let mut key = hmac_sha256(format!("AWS4{}", creds.secret_access_key).as_bytes(), date);
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part);
+    }
+    let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+    headers.remove(0); // reqwest sets host itself
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            creds.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    headers
+}
+
+// =============================================================================
+// PROVIDER
+// =============================================================================
+pub struct Bedrock {
+    endpoint: Endpoint,
+}
+
+impl Bedrock {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl Provider for Bedrock {
+    fn name(&self) -> &'static str {
+        "bedrock"
+    }
+
+    fn chat<'a>(&'a self, req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>> {
+        let e = &self.endpoint;
+        chat(
+            &e.http,
+            &e.base_url,
+            &e.model,
+            e.max_tokens,
+            e.temperature,
+            req.system,
+            req.turns,
+            req.stream,
+            req.reasoning_effort,
+            req.usage,
+        )
+        .boxed()
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        let e = &self.endpoint;
+        list_models(&e.http, &e.base_url).boxed()
+    }
+}
+
+// =============================================================================
+// MODULE TESTS
+// =============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn example_creds(token: Option<&str>) -> Credentials {
+        Credentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: token.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn invoke_body_maps_anthropic_request() {
+        let turns = [ChatMessage::user("diff"), ChatMessage::assistant("Fix bug"), ChatMessage::user("shorter")];
+        let body = build_invoke_body("us.anthropic.claude-3-5-haiku-v1:0", "Be terse.", &turns, 800, 0.3, None);
+        let v: Value = serde_json::to_value(body).unwrap();
+
+        assert_eq!(v["anthropic_version"], "bedrock-2023-05-31");
+        assert_eq!(v["system"], "Be terse.");
+        assert_eq!(v["max_tokens"], 800);
+        assert!((v["temperature"].as_f64().unwrap() - 0.3).abs() < 1e-6);
+        assert_eq!(v["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(v["messages"][1]["role"], "assistant");
+        assert!(v.get("model").is_none());
+        assert!(v.get("stream").is_none());
+        assert!(v.get("thinking").is_none());
+    }
+
+    #[test]
+    fn invoke_body_sends_thinking_for_reasoning_effort() {
+        let model = "us.anthropic.claude-sonnet-4-5-20250929-v1:0";
+        let turns = [ChatMessage::user("diff")];
+        let body = build_invoke_body(model, "", &turns, 800, 0.3, Some(ReasoningEffort::Medium));
+        let v: Value = serde_json::to_value(body).unwrap();
+        assert_eq!(v["thinking"]["type"], "enabled");
+        assert_eq!(v["thinking"]["budget_tokens"], 4096);
+        assert_eq!(v["max_tokens"], 800 + 4096);
+        assert!(v.get("temperature").is_none());
+
+        // Models without thinking ignore the effort
+        let model = "anthropic.claude-3-haiku-20240307-v1:0";
+        let body = build_invoke_body(model, "", &turns, 800, 0.3, Some(ReasoningEffort::High));
+        assert!(serde_json::to_value(body).unwrap().get("thinking").is_none());
+    }
+
+    #[test]
+    fn invoke_body_omits_empty_system() {
+        let body = build_invoke_body("m", " ", &[ChatMessage::user("hi")], 10, 0.0, None);
+        let v: Value = serde_json::to_value(body).unwrap();
+        assert!(v.get("system").is_none());
+    }
+
+    #[test]
+    fn invoke_response_parses_as_claude() {
+        let json = r#"{"id":"msg_1","type":"message","role":"assistant",
+            "content":[{"type":"text","text":"Add login"}],"stop_reason":"end_turn"}"#;
+        let resp: ClaudeResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.content[0].text.as_deref(), Some("Add login"));
+    }
+
+    #[test]
+    fn invoke_path_encodes_model_id() {
+        assert_eq!(
+            invoke_path("us.anthropic.claude-sonnet-4-5-20250929-v1:0"),
+            "/model/us.anthropic.claude-sonnet-4-5-20250929-v1%3A0/invoke"
+        );
+    }
+
+    #[test]
+    fn region_from_runtime_url() {
+        assert_eq!(region_from_url(&crate::config::bedrock_url("eu-west-1")).as_deref(), Some("eu-west-1"));
+        assert_eq!(
+            region_from_url("https://bedrock-runtime-fips.us-gov-west-1.amazonaws.com/").as_deref(),
+            Some("us-gov-west-1")
+        );
+        assert_eq!(region_from_url("https://api.anthropic.com/v1"), None);
+    }
+
+    #[test]
+    fn claude_profiles_keeps_anthropic_ids() {
+        let json = r#"{"inferenceProfileSummaries":[
+            {"inferenceProfileId":"us.anthropic.claude-sonnet-4-5-20250929-v1:0"},
+            {"inferenceProfileId":"us.meta.llama3-2-90b-instruct-v1:0"}]}"#;
+        let resp: BedrockProfilesResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(claude_profiles(resp), ["us.anthropic.claude-sonnet-4-5-20250929-v1:0"]);
+    }
+
+    #[test]
+    fn amz_date_formats_utc() {
+        assert_eq!(amz_date(0), "19700101T000000Z");
+        assert_eq!(amz_date(1_440_938_160), "20150830T123600Z");
+        assert_eq!(amz_date(1_709_210_096), "20240229T123456Z");
+    }
+
+    #[test]
+    fn uri_encode_keeps_unreserved() {
+        assert_eq!(uri_encode("a-b_c.d~e"), "a-b_c.d~e");
+        assert_eq!(uri_encode("v1:0 /x"), "v1%3A0%20%2Fx");
+    }
+
+    #[test]
+    fn sign_matches_aws_get_vanilla_vector() {
+        // aws-sig-v4-test-suite "get-vanilla"
+        let headers = sign(
+            &example_creds(None),
+            "us-east-1",
+            "service",
+            "GET",
+            "example.amazonaws.com",
+            "/",
+            "",
+            b"",
+            "20150830T123600Z",
+        );
+        assert_eq!(headers[0], ("x-amz-date", "20150830T123600Z".to_string()));
+        assert_eq!(
+            headers[1].1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn sign_includes_session_token() {
+        let headers = sign(
+            &example_creds(Some("tok")),
+            "us-east-1",
+            "bedrock",
+            "POST",
+            "bedrock-runtime.us-east-1.amazonaws.com",
+            "/model/x%3A0/invoke",
+            "",
+            b"{}",
+            "20250101T000000Z",
+        );
+        assert!(headers.contains(&("x-amz-security-token", "tok".to_string())));
+        assert!(headers[2].1.contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+    }
+}
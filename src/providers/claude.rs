@@ -1,11 +1,14 @@
 // src/claude.rs
-use anyhow::{bail, Context, Result};
-use futures_util::StreamExt;
-use reqwest::Client;
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use futures_util::{FutureExt, StreamExt};
+use reqwest::{Client, StatusCode};
 use std::io::{self, Write};
 
+use super::{ChatRequest, Endpoint, Provider, ReasoningEffort, Usage};
 use crate::types::*;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn chat(
     http: &Client,
     base_url: &str,
@@ -14,22 +17,14 @@ pub async fn chat(
     max_tokens: u32,
     temperature: f32,
     system: &str,
-    user: &str,
+    turns: &[ChatMessage],
     stream: bool,
+    reasoning_effort: Option<ReasoningEffort>,
+    usage: &Usage,
 ) -> Result<String> {
     let url = format!("{}/messages", base_url);
 
-    let request = ClaudeRequest {
-        model: model.to_string(),
-        messages: vec![ChatMessage {
-            role: "user".to_string(),
-            content: user.to_string(),
-        }],
-        system: system.to_string(),
-        max_tokens,
-        temperature: Some(temperature),
-        stream: Some(stream),
-    };
+    let request = build_request(model, max_tokens, temperature, system, turns, stream, reasoning_effort);
 
     let mut req_builder = http
         .post(&url)
@@ -49,11 +44,12 @@ pub async fn chat(
     let status = response.status();
     if !status.is_success() {
         let body = response.text().await.context("Failed to read error body")?;
-        bail!("API error ({}): {}", status, body);
+        return Err(ApiFailure::from_response(status, &body).into());
     }
 
     if stream {
         let mut full_text = String::new();
+        let mut stop_reason: Option<String> = None;
         let mut s = response.bytes_stream();
 
         while let Some(item) = s.next().await {
@@ -70,7 +66,11 @@ pub async fn chat(
 
                     // We are looking for 'content_block_delta' types
                     if let Ok(delta) = serde_json::from_str::<ClaudeStreamDelta>(data) {
-                        if let Some(t) = delta.delta.and_then(|d| d.text) {
+                        let Some(d) = delta.delta else { continue };
+                        if d.stop_reason.is_some() {
+                            stop_reason = d.stop_reason;
+                        }
+                        if let Some(t) = d.text {
                             print!("{}", t);
                             io::stdout().flush()?;
                             full_text.push_str(&t);
@@ -81,6 +81,9 @@ pub async fn chat(
         }
 
         println!(); // New line after stream ends
+        if full_text.trim().is_empty() {
+            return Err(empty_response_error("Claude API", stop_reason.as_deref()));
+        }
         return Ok(full_text);
     }
 
@@ -91,16 +94,75 @@ pub async fn chat(
         .context("Failed to read response body")?;
     let resp: ClaudeResponse =
         serde_json::from_str(&body).context("Failed to parse Claude response")?;
+    if let Some(u) = &resp.usage {
+        usage.record(u.input_tokens, u.output_tokens);
+    }
+
+    // With extended thinking the text block follows a thinking block
+    response_text(
+        "Claude API",
+        resp.content.iter().find_map(|c| c.text.as_deref()),
+        resp.stop_reason.as_deref(),
+    )
+}
 
-    resp.content
-        .first()
-        .and_then(|c| c.text.as_ref())
-        .map(|s| s.trim().to_string())
-        .context("No response content from Claude API")
+/// Models with extended thinking, also under Bedrock-style ids (`us.anthropic.claude-...`)
+fn supports_thinking(model: &str) -> bool {
+    ["claude-3-7-sonnet", "claude-sonnet-4", "claude-opus-4", "claude-haiku-4-5"]
+        .iter()
+        .any(|p| model.contains(p))
 }
 
+/// Thinking budget for `reasoning_effort`, None when off or the model has no thinking.
+/// Shared with Bedrock, which takes the same body.
+pub(crate) fn thinking_budget(model: &str, reasoning_effort: Option<ReasoningEffort>) -> Option<u32> {
+    reasoning_effort
+        .filter(|_| supports_thinking(model))
+        .map(|e| e.thinking_budget())
+}
+
+/// The `thinking` block that turns extended thinking on.
+pub(crate) fn thinking_block(budget: u32) -> serde_json::Value {
+    serde_json::json!({ "type": "enabled", "budget_tokens": budget })
+}
+
+/// With thinking on, the budget comes on top of `max_tokens` (which covers both) and
+/// temperature is left out: the API only accepts the default with thinking.
+#[allow(clippy::too_many_arguments)]
+fn build_request(
+    model: &str,
+    max_tokens: u32,
+    temperature: f32,
+    system: &str,
+    turns: &[ChatMessage],
+    stream: bool,
+    reasoning_effort: Option<ReasoningEffort>,
+) -> ClaudeRequest {
+    let budget = thinking_budget(model, reasoning_effort);
+
+    ClaudeRequest {
+        model: model.to_string(),
+        messages: turns.to_vec(),
+        system: system.to_string(),
+        max_tokens: max_tokens + budget.unwrap_or(0),
+        temperature: if budget.is_some() { None } else { Some(temperature) },
+        stream: Some(stream),
+        thinking: budget.map(thinking_block),
+    }
+}
+
+/// Known model ids, returned when the models endpoint is not available (404)
+pub const FALLBACK_MODELS: &[&str] = &[
+    "claude-opus-4-5-20251101",
+    "claude-sonnet-4-5-20250929",
+    "claude-haiku-4-5-20251001",
+    "claude-opus-4-1-20250805",
+    "claude-sonnet-4-20250514",
+    "claude-opus-4-20250514",
+];
+
 pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -> Result<Vec<String>> {
-    let url = format!("{}/models", base_url);
+    let url = format!("{}/models?limit=1000", base_url);
 
     let mut req_builder = http
         .get(&url)
@@ -119,27 +181,67 @@ pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -
         .await
         .context("Failed to read response body")?;
 
+    parse_models_response(status, &body)
+}
+
+/// `data[].id` from a models response; the fallback list if the endpoint 404s.
+fn parse_models_response(status: StatusCode, body: &str) -> Result<Vec<String>> {
+    if status == StatusCode::NOT_FOUND {
+        return Ok(FALLBACK_MODELS.iter().map(|m| m.to_string()).collect());
+    }
+
     if !status.is_success() {
-        if let Ok(err) = serde_json::from_str::<ApiError>(&body) {
-            if let Some(detail) = err.error {
-                if let Some(msg) = detail.message {
-                    bail!("API error ({}): {}", status, msg);
-                }
-            }
-        }
-        bail!(
-            "API error ({}): {}",
-            status,
-            &body[..body.len().min(500)]
-        );
+        return Err(ApiFailure::from_response(status, body).into());
     }
 
     let resp: ModelsResponse =
-        serde_json::from_str(&body).context("Failed to parse models response")?;
+        serde_json::from_str(body).context("Failed to parse models response")?;
 
     Ok(resp.data.into_iter().map(|m| m.id).collect())
 }
 
+// =============================================================================
+// PROVIDER
+// =============================================================================
+pub struct Claude {
+    endpoint: Endpoint,
+}
+
+impl Claude {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl Provider for Claude {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn chat<'a>(&'a self, req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>> {
+        let e = &self.endpoint;
+        chat(
+            &e.http,
+            &e.base_url,
+            e.api_key.as_deref(),
+            &e.model,
+            e.max_tokens,
+            e.temperature,
+            req.system,
+            req.turns,
+            req.stream,
+            req.reasoning_effort,
+            req.usage,
+        )
+        .boxed()
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        let e = &self.endpoint;
+        list_models(&e.http, &e.base_url, e.api_key.as_deref()).boxed()
+    }
+}
+
 // =============================================================================
 // MODULE TESTS
 // =============================================================================
@@ -172,6 +274,7 @@ mod tests {
             max_tokens: 1024,
             temperature: Some(0.7),
             stream: Some(false),
+            thinking: None,
         };
 
         let v: Value = serde_json::to_value(&request).unwrap();
@@ -201,6 +304,7 @@ mod tests {
             max_tokens: 500,
             temperature: Some(0.5),
             stream: Some(true),
+            thinking: None,
         };
 
         assert_eq!(request.messages.len(), 1);
@@ -208,18 +312,70 @@ mod tests {
         assert_eq!(request.messages[0].content, "Test message");
     }
 
+    #[test]
+    fn thinking_only_for_thinking_models() {
+        let turns = [ChatMessage::user("hi")];
+        let medium = Some(ReasoningEffort::Medium);
+        let request = |model: &str, effort: Option<ReasoningEffort>| {
+            serde_json::to_value(build_request(model, 1000, 0.3, "s", &turns, false, effort)).unwrap()
+        };
+
+        let v = request("claude-sonnet-4-5-20250929", medium);
+        assert_eq!(v["thinking"]["type"], "enabled");
+        assert_eq!(v["thinking"]["budget_tokens"], 4096);
+        assert_eq!(v["max_tokens"], 5096);
+        assert!(v.get("temperature").is_none());
+
+        let v = request("claude-3-5-haiku-20241022", medium);
+        assert!(v.get("thinking").is_none());
+        assert_eq!(v["max_tokens"], 1000);
+        assert!(v.get("temperature").is_some());
+
+        let v = request("claude-opus-4-5-20251101", None);
+        assert!(v.get("thinking").is_none());
+    }
+
+    #[test]
+    fn thinking_model_ids() {
+        assert!(supports_thinking("claude-3-7-sonnet-20250219"));
+        assert!(supports_thinking("us.anthropic.claude-sonnet-4-5-20250929-v1:0"));
+        assert!(supports_thinking("claude-haiku-4-5-20251001"));
+        assert!(!supports_thinking("claude-3-5-sonnet-20241022"));
+        assert!(!supports_thinking("claude-3-haiku-20240307"));
+    }
+
+    #[test]
+    fn parse_models_response_reads_ids() {
+        let body = r#"{
+            "data": [
+                {"type": "model", "id": "claude-sonnet-4-5-20250929", "display_name": "Claude Sonnet 4.5"},
+                {"type": "model", "id": "claude-haiku-4-5-20251001", "display_name": "Claude Haiku 4.5"}
+            ],
+            "has_more": false,
+            "first_id": "claude-sonnet-4-5-20250929",
+            "last_id": "claude-haiku-4-5-20251001"
+        }"#;
+        let models = parse_models_response(StatusCode::OK, body).unwrap();
+        assert_eq!(models, vec!["claude-sonnet-4-5-20250929", "claude-haiku-4-5-20251001"]);
+    }
+
+    #[test]
+    fn parse_models_response_falls_back_on_404() {
+        let models = parse_models_response(StatusCode::NOT_FOUND, "Not Found").unwrap();
+        assert_eq!(models.len(), FALLBACK_MODELS.len());
+        assert!(models.iter().all(|m| m.starts_with("claude-")));
+    }
+
+    #[test]
+    fn parse_models_response_other_errors_propagate() {
+        let body = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+        assert!(parse_models_response(StatusCode::UNAUTHORIZED, body).is_err());
+        assert!(parse_models_response(StatusCode::OK, "not json").is_err());
+    }
+
     #[test]
     fn claude_model_ids_valid_format() {
-        let valid_models = [
-            "claude-opus-4-5-20251101",
-            "claude-sonnet-4-5-20250929",
-            "claude-haiku-4-5-20251001",
-            "claude-opus-4-1-20250805",
-            "claude-sonnet-4-20250514",
-            "claude-opus-4-20250514",
-        ];
-
-        for model in valid_models {
+        for model in FALLBACK_MODELS {
             assert!(
                 model.starts_with("claude-"),
                 "Model should start with 'claude-': {}",
@@ -1,10 +1,12 @@
 // src/gemini.rs
 use anyhow::{bail, Context, Result};
-use futures_util::StreamExt;
+use futures_util::future::BoxFuture;
+use futures_util::{FutureExt, StreamExt};
 use reqwest::Client;
 use serde_json::Value;
 use std::io::{self, Write};
 
+use super::{ChatRequest, Endpoint, Provider, Usage};
 use crate::types::*;
 
 fn normalize_base_url(base_url: &str) -> String {
@@ -24,6 +26,16 @@ fn normalize_model_path(model: &str) -> String {
     }
 }
 
+/// Gemini calls the assistant role "model"
+fn gemini_role(role: &str) -> &str {
+    if role == "assistant" {
+        "model"
+    } else {
+        "user"
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn chat(
     http: &Client,
     base_url: &str,
@@ -32,8 +44,9 @@ pub async fn chat(
     _max_tokens: u32,
     _temperature: f32,
     system: &str,
-    user: &str,
+    turns: &[ChatMessage],
     stream: bool,
+    usage: &Usage,
 ) -> Result<String> {
     let base = normalize_base_url(base_url);
     let model_path = normalize_model_path(model);
@@ -49,16 +62,21 @@ pub async fn chat(
             None
         } else {
             Some(GeminiContent {
+                role: None,
                 parts: vec![GeminiPart {
                     text: system.to_string(),
                 }],
             })
         },
-        contents: vec![GeminiContent {
-            parts: vec![GeminiPart {
-                text: user.to_string(),
-            }],
-        }],
+        contents: turns
+            .iter()
+            .map(|t| GeminiContent {
+                role: Some(gemini_role(&t.role).to_string()),
+                parts: vec![GeminiPart {
+                    text: t.content.clone(),
+                }],
+            })
+            .collect(),
     };
 
     let mut req_builder = http
@@ -79,18 +97,12 @@ pub async fn chat(
     let status = response.status();
     if !status.is_success() {
         let body = response.text().await.context("Failed to read error body")?;
-        if let Ok(err) = serde_json::from_str::<ApiError>(&body) {
-            if let Some(detail) = err.error {
-                if let Some(msg) = detail.message {
-                    bail!("API error ({}): {}", status, msg);
-                }
-            }
-        }
-        bail!("API error ({}): {}", status, &body[..body.len().min(500)]);
+        return Err(ApiFailure::from_response(status, &body).into());
     }
 
     if stream {
         let mut full_text = String::new();
+        let mut finish_reason: Option<String> = None;
         let mut buf = String::new();
         let mut s = response.bytes_stream();
 
@@ -102,6 +114,9 @@ pub async fn chat(
                 drain_gemini_stream_values(&mut buf).context("Failed to parse Gemini stream")?;
 
             for v in vals {
+                if let Some(r) = extract_gemini_finish_reason(&v) {
+                    finish_reason = Some(r);
+                }
                 let t = extract_gemini_text_from_value(&v);
                 if !t.is_empty() {
                     print!("{}", t);
@@ -135,8 +150,8 @@ pub async fn chat(
         }
         
         println!();
-        if full_text.is_empty() {
-            bail!("No response content from Gemini API (stream ended without content)");
+        if full_text.trim().is_empty() {
+            return Err(empty_response_error("Gemini API", finish_reason.as_deref()));
         }
         return Ok(full_text);
     }
@@ -149,16 +164,21 @@ pub async fn chat(
 
     let resp: GeminiGenerateContentResponse =
         serde_json::from_str(&body).context("Failed to parse Gemini response")?;
+    if let Some(u) = &resp.usage_metadata {
+        usage.record(u.prompt_token_count, u.candidates_token_count);
+    }
 
-    let text = resp
-        .candidates
-        .as_ref()
-        .and_then(|c| c.first())
+    let candidate = resp.candidates.as_ref().and_then(|c| c.first());
+    let text = candidate
         .and_then(|c| c.content.as_ref())
         .and_then(|c| c.parts.first())
-        .map(|p| p.text.trim().to_string());
+        .map(|p| p.text.as_str());
+    // A blocked prompt has no candidates, only promptFeedback.blockReason
+    let reason = candidate
+        .and_then(|c| c.finish_reason.as_deref())
+        .or_else(|| resp.prompt_feedback.as_ref().and_then(|f| f.block_reason.as_deref()));
 
-    text.context("No response content from Gemini API")
+    response_text("Gemini API", text, reason)
 }
 
 pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -> Result<Vec<String>> {
@@ -177,14 +197,7 @@ pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -
     let body = response.text().await.context("Failed to read response body")?;
 
     if !status.is_success() {
-        if let Ok(err) = serde_json::from_str::<ApiError>(&body) {
-            if let Some(detail) = err.error {
-                if let Some(msg) = detail.message {
-                    bail!("API error ({}): {}", status, msg);
-                }
-            }
-        }
-        bail!("API error ({}): {}", status, &body[..body.len().min(500)]);
+        return Err(ApiFailure::from_response(status, &body).into());
     }
 
     let resp: GeminiModelsResponse =
@@ -197,10 +210,60 @@ pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -
         .collect())
 }
 
+// =============================================================================
+// PROVIDER
+// =============================================================================
+pub struct Gemini {
+    endpoint: Endpoint,
+}
+
+impl Gemini {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl Provider for Gemini {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn chat<'a>(&'a self, req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>> {
+        let e = &self.endpoint;
+        chat(
+            &e.http,
+            &e.base_url,
+            e.api_key.as_deref(),
+            &e.model,
+            e.max_tokens,
+            e.temperature,
+            req.system,
+            req.turns,
+            req.stream,
+            req.usage,
+        )
+        .boxed()
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        let e = &self.endpoint;
+        list_models(&e.http, &e.base_url, e.api_key.as_deref()).boxed()
+    }
+}
+
 // =============================================================================
 // Streaming helpers (Value-based, tolerant to metadata chunks)
 // =============================================================================
 
+/// candidates[0].finishReason, when this chunk carries it
+fn extract_gemini_finish_reason(v: &Value) -> Option<String> {
+    v.get("candidates")?
+        .get(0)?
+        .get("finishReason")?
+        .as_str()
+        .map(String::from)
+}
+
 fn extract_gemini_text_from_value(v: &Value) -> String {
     // candidates[0].content.parts[*].text
     let mut out = String::new();
@@ -247,21 +310,12 @@ fn drain_gemini_stream_values(buf: &mut String) -> Result<Vec<Value>> {
         }
 
         // Drop leading array separators: '[', ',', ']'
-        loop {
-            let Some(first) = buf.chars().next() else { break };
-            match first {
-                '[' | ',' | ']' => {
-                    buf.drain(..first.len_utf8());
-                    // trim again
-                    let t = buf.trim_start();
-                    if t.len() != buf.len() {
-                        buf.drain(..(buf.len() - t.len()));
-                    }
-                    if buf.is_empty() {
-                        break;
-                    }
-                }
-                _ => break,
+        while let Some(first @ ('[' | ',' | ']')) = buf.chars().next() {
+            buf.drain(..first.len_utf8());
+            // trim again
+            let t = buf.trim_start();
+            if t.len() != buf.len() {
+                buf.drain(..(buf.len() - t.len()));
             }
         }
         if buf.is_empty() {
@@ -327,6 +381,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn gemini_role_maps_assistant_to_model() {
+        assert_eq!(gemini_role("assistant"), "model");
+        assert_eq!(gemini_role("user"), "user");
+    }
+
     #[test]
     fn normalize_model_path_adds_prefix() {
         assert_eq!(normalize_model_path("gemini-2.5-flash"), "models/gemini-2.5-flash");
@@ -341,9 +401,11 @@ mod tests {
     fn gemini_request_with_system_instruction() {
         let request = GeminiGenerateContentRequest {
             system_instruction: Some(GeminiContent {
+                role: None,
                 parts: vec![GeminiPart { text: "You are helpful.".to_string() }],
             }),
             contents: vec![GeminiContent {
+                role: None,
                 parts: vec![GeminiPart { text: "Hello".to_string() }],
             }],
         };
@@ -358,6 +420,7 @@ mod tests {
         let request = GeminiGenerateContentRequest {
             system_instruction: None,
             contents: vec![GeminiContent {
+                role: None,
                 parts: vec![GeminiPart { text: "Hello".to_string() }],
             }],
         };
@@ -387,10 +450,20 @@ mod tests {
         assert_eq!(super::extract_gemini_text_from_value(&v), "");
     }
 
+    #[test]
+    fn extract_finish_reason_from_stream_chunk() {
+        let v: Value = serde_json::json!({
+          "candidates": [{ "content": { "role": "model" }, "finishReason": "MAX_TOKENS" }]
+        });
+        assert_eq!(super::extract_gemini_finish_reason(&v), Some("MAX_TOKENS".into()));
+        let v: Value = serde_json::json!({ "candidates": [{ "content": { "parts": [] } }] });
+        assert_eq!(super::extract_gemini_finish_reason(&v), None);
+    }
+
     #[test]
     fn drain_values_parses_array_across_chunks_and_ignores_metadata() {
         let mut buf = String::new();
-        buf.push_str("[");
+        buf.push('[');
         buf.push_str(r#"{"candidates":[{"content":{"parts":[{"text":"Hi"}]}}]},"#);
         // not complete second yet
         let v = super::drain_gemini_stream_values(&mut buf).unwrap();
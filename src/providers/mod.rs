@@ -1,3 +1,164 @@
+// src/providers/mod.rs
 pub mod claude;
 pub mod openai;
-pub mod gemini;
\ No newline at end of file
+pub mod gemini;
+#[cfg(feature = "bedrock")]
+pub mod bedrock;
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+#[cfg(not(feature = "bedrock"))]
+use futures_util::FutureExt;
+use reqwest::Client;
+use std::sync::Mutex;
+
+use crate::types::ChatMessage;
+
+/// Connection and sampling settings a provider is built from
+#[derive(Clone)]
+pub struct Endpoint {
+    pub http: Client,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub max_tokens: u32,
+    pub temperature: f32,
+}
+
+/// One chat call: alternating user/assistant `turns`, ending with a user turn.
+pub struct ChatRequest<'a> {
+    pub system: &'a str,
+    pub turns: &'a [ChatMessage],
+    pub stream: bool,
+    /// Ask for a JSON object; only OpenAI-compatible providers have a native mode
+    pub json_mode: bool,
+    /// Reproducible sampling; only OpenAI-compatible providers use it
+    pub seed: Option<u64>,
+    /// Only sent to reasoning models (OpenAI o-series/gpt-5, Claude extended thinking)
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// The client's running total; providers add the response's reported usage
+    pub usage: &'a Usage,
+}
+
+/// `--reasoning-effort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    /// OpenAI `reasoning_effort` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    /// Anthropic extended thinking `budget_tokens` (the API minimum is 1024)
+    pub fn thinking_budget(&self) -> u32 {
+        match self {
+            Self::Low => 1024,
+            Self::Medium => 4096,
+            Self::High => 16384,
+        }
+    }
+}
+
+/// (prompt, completion) tokens summed over a client's requests; None until a response
+/// reports usage
+#[derive(Debug, Default)]
+pub struct Usage(Mutex<Option<(u64, u64)>>);
+
+impl Usage {
+    /// Add one response's `usage` to the total.
+    pub fn record(&self, prompt: u64, completion: u64) {
+        let mut usage = self.0.lock().unwrap();
+        let (p, c) = usage.unwrap_or_default();
+        *usage = Some((p + prompt, c + completion));
+    }
+
+    /// `--show-usage` line for the requests so far
+    pub fn line(&self) -> String {
+        format_usage(*self.0.lock().unwrap())
+    }
+}
+
+fn format_usage(usage: Option<(u64, u64)>) -> String {
+    match usage {
+        Some((prompt, completion)) => format!("tokens: prompt={} completion={}", prompt, completion),
+        None => "tokens: not reported (streamed responses carry no usage)".into(),
+    }
+}
+
+pub trait Provider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn chat<'a>(&'a self, req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>>;
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>>;
+}
+
+/// Pick the API by provider name, or by a well-known base URL (e.g. a Claude URL
+/// configured under another provider name). Everything else speaks OpenAI.
+pub fn for_endpoint(provider: &str, endpoint: Endpoint) -> Box<dyn Provider> {
+    if provider == "bedrock" || endpoint.base_url.contains("bedrock-runtime") {
+        return bedrock_provider(endpoint);
+    }
+    if provider == "claude" || endpoint.base_url.contains("anthropic.com") {
+        return Box::new(claude::Claude::new(endpoint));
+    }
+    if provider == "gemini" || endpoint.base_url.contains("generativelanguage.googleapis.com") {
+        return Box::new(gemini::Gemini::new(endpoint));
+    }
+    Box::new(openai::OpenAi::new(endpoint))
+}
+
+#[cfg(feature = "bedrock")]
+fn bedrock_provider(endpoint: Endpoint) -> Box<dyn Provider> {
+    Box::new(bedrock::Bedrock::new(endpoint))
+}
+
+#[cfg(not(feature = "bedrock"))]
+fn bedrock_provider(_endpoint: Endpoint) -> Box<dyn Provider> {
+    Box::new(BedrockDisabled)
+}
+
+/// Stand-in when gitar is built without the `bedrock` feature.
+#[cfg(not(feature = "bedrock"))]
+struct BedrockDisabled;
+
+#[cfg(not(feature = "bedrock"))]
+impl BedrockDisabled {
+    fn error() -> anyhow::Error {
+        anyhow::anyhow!(
+            "This gitar was built without Bedrock support. Reinstall with:\n  \
+             cargo install gitar --features bedrock"
+        )
+    }
+}
+
+#[cfg(not(feature = "bedrock"))]
+impl Provider for BedrockDisabled {
+    fn name(&self) -> &'static str {
+        "bedrock"
+    }
+
+    fn chat<'a>(&'a self, _req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>> {
+        async { Err(Self::error()) }.boxed()
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        async { Err(Self::error()) }.boxed()
+    }
+}
@@ -1,16 +1,82 @@
 // src/openai.rs
-use anyhow::{bail, Context, Result};
-use futures_util::StreamExt;
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use futures_util::{FutureExt, StreamExt};
 use reqwest::Client;
 use std::collections::HashSet;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::{LazyLock, Mutex};
 
+use super::{ChatRequest, Endpoint, Provider, ReasoningEffort, Usage};
 use crate::types::*;
 
-pub static REASONING_MODELS: LazyLock<Mutex<HashSet<String>>> =
-    LazyLock::new(|| Mutex::new(HashSet::new()));
+/// Models learned to reject max_tokens/temperature, seeded from the cache file.
+pub static REASONING_MODELS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| {
+    Mutex::new(
+        reasoning_cache_path()
+            .map(|p| load_reasoning_models(&p))
+            .unwrap_or_default(),
+    )
+});
+
+pub const REASONING_CACHE_FILENAME: &str = ".gitar_reasoning_models";
+
+/// Next to the config file (`~/.gitar_reasoning_models` with the default `~/.gitar.toml`).
+#[cfg(not(test))]
+fn reasoning_cache_path() -> Option<PathBuf> {
+    crate::config::Config::sibling_path(REASONING_CACHE_FILENAME)
+}
+
+/// Tests never read or write the user's cache.
+#[cfg(test)]
+fn reasoning_cache_path() -> Option<PathBuf> {
+    Some(std::env::temp_dir().join(format!("{}_{}", REASONING_CACHE_FILENAME, std::process::id())))
+}
 
+/// One model name per line; a missing or unreadable file is an empty set.
+pub fn load_reasoning_models(path: &Path) -> HashSet<String> {
+    std::fs::read_to_string(path)
+        .map(|s| {
+            s.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Merge with what is already on disk (another process may have learned more), then
+/// replace the file atomically.
+pub fn save_reasoning_models(path: &Path, models: &HashSet<String>) -> Result<()> {
+    let mut all: Vec<String> = load_reasoning_models(path)
+        .into_iter()
+        .chain(models.iter().cloned())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    all.sort();
+
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    std::fs::write(&tmp, all.join("\n") + "\n").context("Failed to write reasoning model cache")?;
+    std::fs::rename(&tmp, path).context("Failed to replace reasoning model cache")?;
+    Ok(())
+}
+
+fn remember_reasoning_model(model: &str) {
+    let mut set = REASONING_MODELS.lock().unwrap();
+    if set.insert(model.to_string()) {
+        if let Some(path) = reasoning_cache_path() {
+            // Best effort: a cache write failure only costs a retry next time
+            let _ = save_reasoning_models(&path, &set);
+        }
+    }
+}
+
+/// With `json_mode`, asks for `response_format: json_object` and falls back to a plain
+/// request if the model rejects it.
+#[allow(clippy::too_many_arguments)]
 pub async fn chat(
     http: &Client,
     base_url: &str,
@@ -19,39 +85,117 @@ pub async fn chat(
     max_tokens: u32,
     temperature: f32,
     system: &str,
-    user: &str,
+    turns: &[ChatMessage],
     stream: bool,
+    seed: Option<u64>,
+    reasoning_effort: Option<ReasoningEffort>,
+    json_mode: bool,
+    usage: &Usage,
 ) -> Result<String> {
-    let url = format!("{}/chat/completions", base_url);
+    let result = send_chat(
+        http, base_url, api_key, model, max_tokens, temperature, system, turns, stream, seed,
+        reasoning_effort, json_mode, usage,
+    )
+    .await;
+
+    match result {
+        Err(e) if json_mode && is_response_format_error(&e) => {
+            send_chat(
+                http, base_url, api_key, model, max_tokens, temperature, system, turns, stream,
+                seed, reasoning_effort, false, usage,
+            )
+            .await
+        }
+        r => r,
+    }
+}
+
+/// App attribution OpenRouter asks integrations to send
+const OPENROUTER_HEADERS: &[(&str, &str)] = &[
+    ("HTTP-Referer", "https://github.com/sganis/gitar"),
+    ("X-Title", "gitar"),
+];
+
+/// Extra headers for requests to `url`: OpenRouter's attribution, nothing elsewhere.
+pub(crate) fn extra_headers(url: &str) -> &'static [(&'static str, &'static str)] {
+    if url.contains("openrouter.ai") {
+        OPENROUTER_HEADERS
+    } else {
+        &[]
+    }
+}
+
+const CHAT_PATH: &str = "/chat/completions";
+const MODELS_PATH: &str = "/models";
+
+/// `base_url` with `path` as the endpoint. Gateways that hand out a full endpoint URL
+/// (`.../v1/chat/completions`, or `.../models`) get it swapped rather than doubled;
+/// a trailing `/` and a query string (`?api-version=...`) are kept out of the way.
+pub(crate) fn endpoint_url(base_url: &str, path: &str) -> String {
+    let (base, query) = match base_url.split_once('?') {
+        Some((b, q)) => (b, Some(q)),
+        None => (base_url, None),
+    };
+    let base = base.trim_end_matches('/');
+    let root = base
+        .strip_suffix(CHAT_PATH)
+        .or_else(|| base.strip_suffix(MODELS_PATH))
+        .unwrap_or(base);
+    match query {
+        Some(q) => format!("{}{}?{}", root, path, q),
+        None => format!("{}{}", root, path),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn send_chat(
+    http: &Client,
+    base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    max_tokens: u32,
+    temperature: f32,
+    system: &str,
+    turns: &[ChatMessage],
+    stream: bool,
+    seed: Option<u64>,
+    reasoning_effort: Option<ReasoningEffort>,
+    json_mode: bool,
+    usage: &Usage,
+) -> Result<String> {
+    let url = endpoint_url(base_url, CHAT_PATH);
 
     let is_reasoning_model = REASONING_MODELS.lock().unwrap().contains(model);
 
-    let messages = vec![
-        ChatMessage {
-            role: "system".to_string(),
-            content: system.to_string(),
-        },
-        ChatMessage {
-            role: "user".to_string(),
-            content: user.to_string(),
-        },
-    ];
+    let mut messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: system.to_string(),
+    }];
+    messages.extend_from_slice(turns);
 
     if stream {
-        let request_json =
-            build_chat_request_json(model, &messages, is_reasoning_model, max_tokens, temperature, true);
+        let request_json = build_chat_request_json(
+            model,
+            &messages,
+            is_reasoning_model,
+            max_tokens,
+            temperature,
+            true,
+            seed,
+            reasoning_effort,
+            json_mode,
+        );
 
         let resp = send_chat_request_stream(http, &url, api_key, request_json).await;
 
         if let Err(e) = &resp {
-            let err_str = e.to_string();
-            if (err_str.contains("max_completion_tokens") || err_str.contains("temperature"))
-                && !is_reasoning_model
-            {
-                REASONING_MODELS.lock().unwrap().insert(model.to_string());
-
-                let retry_json =
-                    build_chat_request_json(model, &messages, true, max_tokens, temperature, true);
+            if is_reasoning_param_error(e) && !is_reasoning_model {
+                remember_reasoning_model(model);
+
+                let retry_json = build_chat_request_json(
+                    model, &messages, true, max_tokens, temperature, true, seed, reasoning_effort,
+                    json_mode,
+                );
                 return send_chat_request_stream(http, &url, api_key, retry_json).await;
             }
         }
@@ -66,16 +210,16 @@ pub async fn chat(
         max_tokens: if is_reasoning_model { None } else { Some(max_tokens) },
         max_completion_tokens: if is_reasoning_model { Some(max_tokens) } else { None },
         temperature: if is_reasoning_model { None } else { Some(temperature) },
+        seed,
+        response_format: response_format(json_mode),
+        reasoning_effort: reasoning_effort_for(model, is_reasoning_model, reasoning_effort),
     };
 
-    let response = send_chat_request(http, &url, api_key, &request).await;
+    let response = send_chat_request(http, &url, api_key, &request, usage).await;
 
     if let Err(e) = &response {
-        let err_str = e.to_string();
-        if (err_str.contains("max_completion_tokens") || err_str.contains("temperature"))
-            && !is_reasoning_model
-        {
-            REASONING_MODELS.lock().unwrap().insert(model.to_string());
+        if is_reasoning_param_error(e) && !is_reasoning_model {
+            remember_reasoning_model(model);
 
             let retry_request = ChatCompletionRequest {
                 model: model.to_string(),
@@ -83,9 +227,12 @@ pub async fn chat(
                 max_tokens: None,
                 max_completion_tokens: Some(max_tokens),
                 temperature: None,
+                seed,
+                response_format: response_format(json_mode),
+                reasoning_effort: reasoning_effort_for(model, true, reasoning_effort),
             };
 
-            return send_chat_request(http, &url, api_key, &retry_request).await;
+            return send_chat_request(http, &url, api_key, &retry_request, usage).await;
         }
     }
 
@@ -97,6 +244,7 @@ async fn send_chat_request(
     url: &str,
     api_key: Option<&str>,
     request: &ChatCompletionRequest,
+    usage: &Usage,
 ) -> Result<String> {
     let mut req_builder = http
         .post(url)
@@ -106,6 +254,9 @@ async fn send_chat_request(
     if let Some(key) = api_key {
         req_builder = req_builder.header("Authorization", format!("Bearer {}", key));
     }
+    for (name, value) in extra_headers(url) {
+        req_builder = req_builder.header(*name, *value);
+    }
 
     let response = req_builder
         .json(request)
@@ -117,24 +268,21 @@ async fn send_chat_request(
     let body = response.text().await.context("Failed to read response body")?;
 
     if !status.is_success() {
-        if let Ok(err) = serde_json::from_str::<ApiError>(&body) {
-            if let Some(detail) = err.error {
-                if let Some(msg) = detail.message {
-                    bail!("API error ({}): {}", status, msg);
-                }
-            }
-        }
-        bail!("API error ({}): {}", status, &body[..body.len().min(500)]);
+        return Err(ApiFailure::from_response(status, &body).into());
     }
 
     let resp: ChatCompletionResponse =
         serde_json::from_str(&body).context("Failed to parse response")?;
+    if let Some(u) = &resp.usage {
+        usage.record(u.prompt_tokens, u.completion_tokens);
+    }
 
-    resp.choices
-        .first()
-        .and_then(|c| c.message.content.as_ref())
-        .map(|s| s.trim().to_string())
-        .context("No response content from API")
+    let choice = resp.choices.first();
+    response_text(
+        "API",
+        choice.and_then(|c| c.message.content.as_deref()),
+        choice.and_then(|c| c.finish_reason.as_deref()),
+    )
 }
 
 async fn send_chat_request_stream(
@@ -151,6 +299,9 @@ async fn send_chat_request_stream(
     if let Some(key) = api_key {
         req_builder = req_builder.header("Authorization", format!("Bearer {}", key));
     }
+    for (name, value) in extra_headers(url) {
+        req_builder = req_builder.header(*name, *value);
+    }
 
     let response = req_builder
         .json(&request_json)
@@ -161,85 +312,110 @@ async fn send_chat_request_stream(
     let status = response.status();
     if !status.is_success() {
         let body = response.text().await.context("Failed to read error body")?;
-        // Keep consistent error parsing behavior
-        if let Ok(err) = serde_json::from_str::<ApiError>(&body) {
-            if let Some(detail) = err.error {
-                if let Some(msg) = detail.message {
-                    bail!("API error ({}): {}", status, msg);
-                }
-            }
-        }
-        bail!("API error ({}): {}", status, &body[..body.len().min(500)]);
+        return Err(ApiFailure::from_response(status, &body).into());
     }
 
     let mut full_text = String::new();
+    let mut finish_reason: Option<String> = None;
+    let mut buf = Vec::new();
     let mut s = response.bytes_stream();
 
-    while let Some(item) = s.next().await {
-        let chunk = item.context("Error while reading stream")?;
-        let text = String::from_utf8_lossy(&chunk);
+    loop {
+        let item = s.next().await;
+        let ended = item.is_none();
+        if let Some(item) = item {
+            buf.extend_from_slice(&item.context("Error while reading stream")?);
+        }
 
         // OpenAI-compatible SSE usually sends "data: {...}" lines and "data: [DONE]"
-        for line in text.lines() {
-            let data = line
-                .strip_prefix("data: ")
-                .or_else(|| line.strip_prefix("data:"))
-                .map(|x| x.trim());
-
-            let Some(data) = data else { continue };
-
-            if data == "[DONE]" {
+        for data in drain_sse_data(&mut buf, ended) {
+            if read_stream_data(&data, &mut full_text, &mut finish_reason)? {
                 // End of stream
                 println!();
-                return Ok(full_text);
-            }
-
-            // Primary format: choices[].delta.content
-            if let Ok(delta) = serde_json::from_str::<OpenAiStreamChunk>(data) {
-                if let Some(t) = delta
-                    .choices
-                    .first()
-                    .and_then(|c| c.delta.content.as_ref())
-                {
-                    print!("{}", t);
-                    io::stdout().flush()?;
-                    full_text.push_str(t);
-                    continue;
-                }
-            }
-
-            // Some providers may stream final content in `message.content` (rare). Best-effort:
-            if let Ok(fallback) = serde_json::from_str::<ChatCompletionResponse>(data) {
-                if let Some(t) = fallback
-                    .choices
-                    .first()
-                    .and_then(|c| c.message.content.as_ref())
-                {
-                    let t = t.as_str();
-                    print!("{}", t);
-                    io::stdout().flush()?;
-                    full_text.push_str(t);
+                if full_text.trim().is_empty() {
+                    return Err(empty_response_error("API", finish_reason.as_deref()));
                 }
+                return Ok(full_text);
             }
         }
+        if ended {
+            break;
+        }
     }
 
     // If stream ends without [DONE], still return what we have.
-    if full_text.is_empty() {
-        bail!("No response content from API (stream ended without content)");
+    if full_text.trim().is_empty() {
+        return Err(empty_response_error("API", finish_reason.as_deref()));
     }
     println!();
     Ok(full_text)
 }
 
+/// Take the `data:` payloads of the complete lines in `buf`. A partial line (an event cut
+/// at a chunk boundary) stays for the next chunk; `flush` takes it too, once the stream ends.
+fn drain_sse_data(buf: &mut Vec<u8>, flush: bool) -> Vec<String> {
+    let end = if flush {
+        buf.len()
+    } else {
+        buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1)
+    };
+    let lines: Vec<u8> = buf.drain(..end).collect();
+    String::from_utf8_lossy(&lines)
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.trim().to_string())
+        .collect()
+}
+
+/// Print and collect the text of one `data:` payload. True on `[DONE]`.
+fn read_stream_data(data: &str, full_text: &mut String, finish_reason: &mut Option<String>) -> Result<bool> {
+    if data == "[DONE]" {
+        return Ok(true);
+    }
+
+    // Primary format: choices[].delta.content
+    if let Ok(delta) = serde_json::from_str::<OpenAiStreamChunk>(data) {
+        if let Some(r) = delta.choices.first().and_then(|c| c.finish_reason.clone()) {
+            *finish_reason = Some(r);
+        }
+        if let Some(t) = delta
+            .choices
+            .first()
+            .and_then(|c| c.delta.content.as_ref())
+        {
+            print!("{}", t);
+            io::stdout().flush()?;
+            full_text.push_str(t);
+            return Ok(false);
+        }
+    }
+
+    // Some providers may stream final content in `message.content` (rare). Best-effort:
+    if let Ok(fallback) = serde_json::from_str::<ChatCompletionResponse>(data) {
+        if let Some(t) = fallback
+            .choices
+            .first()
+            .and_then(|c| c.message.content.as_ref())
+        {
+            print!("{}", t);
+            io::stdout().flush()?;
+            full_text.push_str(t);
+        }
+    }
+    Ok(false)
+}
+
 pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -> Result<Vec<String>> {
-    let url = format!("{}/models", base_url);
+    let url = endpoint_url(base_url, MODELS_PATH);
 
     let mut req_builder = http.get(&url).header("Accept", "application/json");
 
     if let Some(key) = api_key {
         req_builder = req_builder.header("Authorization", format!("Bearer {}", key));
     }
+    for (name, value) in extra_headers(&url) {
+        req_builder = req_builder.header(*name, *value);
+    }
 
     let response = req_builder.send().await.context("Failed to send request")?;
 
@@ -247,14 +423,7 @@ pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -
     let body = response.text().await.context("Failed to read response body")?;
 
     if !status.is_success() {
-        if let Ok(err) = serde_json::from_str::<ApiError>(&body) {
-            if let Some(detail) = err.error {
-                if let Some(msg) = detail.message {
-                    bail!("API error ({}): {}", status, msg);
-                }
-            }
-        }
-        bail!("API error ({}): {}", status, &body[..body.len().min(500)]);
+        return Err(ApiFailure::from_response(status, &body).into());
     }
 
     let resp: ModelsResponse =
@@ -263,10 +432,93 @@ pub async fn list_models(http: &Client, base_url: &str, api_key: Option<&str>) -
     Ok(resp.data.into_iter().map(|m| m.id).collect())
 }
 
+// =============================================================================
+// PROVIDER
+// =============================================================================
+pub struct OpenAi {
+    endpoint: Endpoint,
+}
+
+impl OpenAi {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl Provider for OpenAi {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn chat<'a>(&'a self, req: ChatRequest<'a>) -> BoxFuture<'a, Result<String>> {
+        let e = &self.endpoint;
+        chat(
+            &e.http,
+            &e.base_url,
+            e.api_key.as_deref(),
+            &e.model,
+            e.max_tokens,
+            e.temperature,
+            req.system,
+            req.turns,
+            req.stream,
+            req.seed,
+            req.reasoning_effort,
+            req.json_mode,
+            req.usage,
+        )
+        .boxed()
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        let e = &self.endpoint;
+        list_models(&e.http, &e.base_url, e.api_key.as_deref()).boxed()
+    }
+}
+
 // =============================================================================
 // Helpers / stream types (local to this module)
 // =============================================================================
 
+/// Reasoning models reject `max_tokens`/`temperature`; such a rejection means
+/// the request should be retried with reasoning-model parameters.
+fn is_reasoning_param_error(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<ApiFailure>(),
+        Some(ApiFailure::BadParam { param, .. })
+            if matches!(param.as_str(), "max_tokens" | "max_completion_tokens" | "temperature")
+    )
+}
+
+fn is_response_format_error(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<ApiFailure>() {
+        Some(ApiFailure::BadParam { param, .. }) => param == "response_format",
+        Some(f) => f.message().contains("response_format"),
+        None => false,
+    }
+}
+
+fn response_format(json_mode: bool) -> Option<serde_json::Value> {
+    json_mode.then(|| serde_json::json!({ "type": "json_object" }))
+}
+
+/// o-series and gpt-5 models, also behind a router prefix like `openai/o3-mini`
+fn is_known_reasoning_model(model: &str) -> bool {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    ["o1", "o3", "o4", "gpt-5"].iter().any(|p| name.starts_with(p))
+}
+
+/// `reasoning_effort` is only sent to reasoning models; others would reject it.
+fn reasoning_effort_for(
+    model: &str,
+    is_reasoning_model: bool,
+    effort: Option<ReasoningEffort>,
+) -> Option<String> {
+    let reasoning = is_reasoning_model || is_known_reasoning_model(model);
+    effort.filter(|_| reasoning).map(|e| e.as_str().to_string())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn build_chat_request_json(
     model: &str,
     messages: &[ChatMessage],
@@ -274,6 +526,9 @@ pub(crate) fn build_chat_request_json(
     max_tokens: u32,
     temperature: f32,
     stream: bool,
+    seed: Option<u64>,
+    reasoning_effort: Option<ReasoningEffort>,
+    json_mode: bool,
 ) -> serde_json::Value {
     let mut v = serde_json::json!({
         "model": model,
@@ -289,6 +544,16 @@ pub(crate) fn build_chat_request_json(
         v["temperature"] = serde_json::json!(temperature);
     }
 
+    if let Some(seed) = seed {
+        v["seed"] = serde_json::json!(seed);
+    }
+    if let Some(format) = response_format(json_mode) {
+        v["response_format"] = format;
+    }
+    if let Some(effort) = reasoning_effort_for(model, is_reasoning_model, reasoning_effort) {
+        v["reasoning_effort"] = serde_json::json!(effort);
+    }
+
     v
 }
 
@@ -300,6 +565,8 @@ struct OpenAiStreamChunk {
 #[derive(Debug, serde::Deserialize)]
 struct OpenAiStreamChoice {
     delta: OpenAiStreamDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -328,6 +595,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn endpoint_url_from_api_root() {
+        for base in ["https://api.openai.com/v1", "https://api.openai.com/v1/"] {
+            assert_eq!(endpoint_url(base, CHAT_PATH), "https://api.openai.com/v1/chat/completions");
+            assert_eq!(endpoint_url(base, MODELS_PATH), "https://api.openai.com/v1/models");
+        }
+        assert_eq!(endpoint_url("http://localhost:8080", CHAT_PATH), "http://localhost:8080/chat/completions");
+    }
+
+    #[test]
+    fn endpoint_url_from_full_endpoint() {
+        let chat = "https://gw.example.com/openai/v1/chat/completions";
+        assert_eq!(endpoint_url(chat, CHAT_PATH), chat);
+        assert_eq!(endpoint_url(chat, MODELS_PATH), "https://gw.example.com/openai/v1/models");
+        let models = "https://gw.example.com/v1/models/";
+        assert_eq!(endpoint_url(models, CHAT_PATH), "https://gw.example.com/v1/chat/completions");
+    }
+
+    #[test]
+    fn extra_headers_only_for_openrouter() {
+        let headers = extra_headers("https://openrouter.ai/api/v1/chat/completions");
+        assert!(headers.contains(&("X-Title", "gitar")));
+        assert!(headers.iter().any(|(name, _)| *name == "HTTP-Referer"));
+        assert!(extra_headers("https://api.openai.com/v1/chat/completions").is_empty());
+        assert!(extra_headers("http://localhost:11434/v1/models").is_empty());
+    }
+
+    #[test]
+    fn endpoint_url_keeps_query_string() {
+        let base = "https://x.openai.azure.com/openai/deployments/gpt4/chat/completions?api-version=2024-06-01";
+        assert_eq!(endpoint_url(base, CHAT_PATH), base);
+        assert_eq!(
+            endpoint_url("https://x.openai.azure.com/openai/deployments/gpt4?api-version=2024-06-01", MODELS_PATH),
+            "https://x.openai.azure.com/openai/deployments/gpt4/models?api-version=2024-06-01"
+        );
+    }
+
+    #[test]
+    fn sse_event_split_across_chunks() {
+        let event = "data: {\"choices\":[{\"delta\":{\"content\":\"Fix parser\"}}]}\n\n";
+        let (first, second) = event.as_bytes().split_at(25);
+        let mut buf = Vec::new();
+
+        buf.extend_from_slice(first);
+        assert!(drain_sse_data(&mut buf, false).is_empty());
+        buf.extend_from_slice(second);
+        buf.extend_from_slice(b"data: [DONE]\n");
+        let data = drain_sse_data(&mut buf, false);
+        assert_eq!(data.len(), 2);
+        assert!(buf.is_empty());
+
+        let (mut text, mut reason) = (String::new(), None);
+        assert!(!read_stream_data(&data[0], &mut text, &mut reason).unwrap());
+        assert!(read_stream_data(&data[1], &mut text, &mut reason).unwrap());
+        assert_eq!(text, "Fix parser");
+    }
+
+    #[test]
+    fn sse_split_inside_multibyte_char() {
+        let event = "data: {\"choices\":[{\"delta\":{\"content\":\"caf\u{e9}\"}}]}\n";
+        let cut = event.find('\u{e9}').unwrap() + 1;
+        let mut buf = event.as_bytes()[..cut].to_vec();
+        assert!(drain_sse_data(&mut buf, false).is_empty());
+        buf.extend_from_slice(&event.as_bytes()[cut..]);
+
+        let (mut text, mut reason) = (String::new(), None);
+        for data in drain_sse_data(&mut buf, false) {
+            read_stream_data(&data, &mut text, &mut reason).unwrap();
+        }
+        assert_eq!(text, "caf\u{e9}");
+    }
+
+    #[test]
+    fn sse_flush_takes_unterminated_last_line() {
+        let mut buf = b"data:{\"choices\":[]}\r\ndata: [DONE]".to_vec();
+        assert_eq!(drain_sse_data(&mut buf, false), ["{\"choices\":[]}"]);
+        assert_eq!(drain_sse_data(&mut buf, true), ["[DONE]"]);
+        assert!(buf.is_empty());
+    }
+
     #[test]
     fn reasoning_models_starts_empty() {
         REASONING_MODELS.lock().unwrap().clear();
@@ -343,6 +690,60 @@ mod tests {
         REASONING_MODELS.lock().unwrap().clear();
     }
 
+    #[test]
+    fn reasoning_models_save_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("gitar_reasoning_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        assert!(load_reasoning_models(&path).is_empty());
+
+        let set: HashSet<String> = ["o1".to_string(), "gpt-5".to_string()].into();
+        save_reasoning_models(&path, &set).unwrap();
+        assert_eq!(load_reasoning_models(&path), set);
+
+        // Saving merges with models already on disk
+        let more: HashSet<String> = ["o3-mini".to_string()].into();
+        save_reasoning_models(&path, &more).unwrap();
+        let loaded = load_reasoning_models(&path);
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded.contains("o1") && loaded.contains("o3-mini"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn api_failure(status: u16, body: &str) -> anyhow::Error {
+        ApiFailure::from_response(reqwest::StatusCode::from_u16(status).unwrap(), body).into()
+    }
+
+    #[test]
+    fn reasoning_retry_on_rejected_token_param() {
+        let e = api_failure(
+            400,
+            r#"{"error": {"message": "Use 'max_completion_tokens' instead.", "param": "max_tokens"}}"#,
+        );
+        assert!(is_reasoning_param_error(&e));
+    }
+
+    #[test]
+    fn reasoning_retry_on_rejected_temperature() {
+        let e = api_failure(
+            400,
+            r#"{"error": {"message": "Unsupported value", "param": "temperature"}}"#,
+        );
+        assert!(is_reasoning_param_error(&e));
+    }
+
+    #[test]
+    fn no_reasoning_retry_for_other_failures() {
+        let e = api_failure(400, r#"{"error": {"message": "Bad", "param": "messages"}}"#);
+        assert!(!is_reasoning_param_error(&e));
+        // Wording alone no longer triggers a retry
+        let e = api_failure(429, r#"{"error": {"message": "max_completion_tokens rate limit"}}"#);
+        assert!(!is_reasoning_param_error(&e));
+        let e = anyhow::anyhow!("temperature too high");
+        assert!(!is_reasoning_param_error(&e));
+    }
+
     #[test]
     fn chat_completion_request_for_normal_model() {
         let request = ChatCompletionRequest {
@@ -354,6 +755,9 @@ mod tests {
             max_tokens: Some(500),
             max_completion_tokens: None,
             temperature: Some(0.5),
+            seed: None,
+            response_format: None,
+            reasoning_effort: None,
         };
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains("\"max_tokens\":500"));
@@ -372,6 +776,9 @@ mod tests {
             max_tokens: None,
             max_completion_tokens: Some(500),
             temperature: None,
+            seed: None,
+            response_format: None,
+            reasoning_effort: None,
         };
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains("\"max_completion_tokens\":500"));
@@ -386,7 +793,7 @@ mod tests {
             ChatMessage { role: "user".to_string(), content: "hi".to_string() },
         ];
 
-        let v = build_chat_request_json("gpt-4o", &messages, false, 123, 0.7, true);
+        let v = build_chat_request_json("gpt-4o", &messages, false, 123, 0.7, true, None, None, false);
         let vv: Value = serde_json::from_value(v).unwrap();
 
         assert_eq!(vv["model"], "gpt-4o");
@@ -397,6 +804,103 @@ mod tests {
         assert_f64_approx(temp, 0.7, 1e-6);
 
         assert!(vv.get("max_completion_tokens").is_none());
+        assert!(vv.get("seed").is_none());
+    }
+
+    #[test]
+    fn openai_request_json_response_format_only_in_json_mode() {
+        let messages = vec![ChatMessage { role: "user".to_string(), content: "hi".to_string() }];
+
+        let v = build_chat_request_json("gpt-4o", &messages, false, 100, 0.5, false, None, None, true);
+        assert_eq!(v["response_format"]["type"], "json_object");
+
+        let v = build_chat_request_json("gpt-4o", &messages, false, 100, 0.5, false, None, None, false);
+        assert!(v.get("response_format").is_none());
+
+        let request = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages,
+            max_tokens: Some(100),
+            max_completion_tokens: None,
+            temperature: Some(0.5),
+            seed: None,
+            response_format: response_format(false),
+            reasoning_effort: None,
+        };
+        assert!(!serde_json::to_string(&request).unwrap().contains("response_format"));
+    }
+
+    #[test]
+    fn response_format_error_detection() {
+        let e = api_failure(
+            400,
+            r#"{"error": {"message": "Invalid parameter: 'response_format' of type 'json_object' is not supported with this model.", "param": "response_format"}}"#,
+        );
+        assert!(is_response_format_error(&e));
+        let e = api_failure(400, r#"{"error": {"message": "bad", "param": "temperature"}}"#);
+        assert!(!is_response_format_error(&e));
+        assert!(!is_response_format_error(&anyhow::anyhow!("response_format")));
+    }
+
+    #[test]
+    fn openai_request_json_includes_seed_when_set() {
+        let messages = vec![ChatMessage { role: "user".to_string(), content: "hi".to_string() }];
+
+        let v = build_chat_request_json("gpt-4o", &messages, false, 100, 0.0, false, Some(42), None, false);
+        assert_eq!(v["seed"], 42);
+
+        let request = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages,
+            max_tokens: Some(100),
+            max_completion_tokens: None,
+            temperature: Some(0.0),
+            seed: Some(42),
+            response_format: None,
+            reasoning_effort: None,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains("\"seed\":42"));
+    }
+
+    #[test]
+    fn reasoning_effort_only_for_reasoning_models() {
+        let messages = vec![ChatMessage { role: "user".to_string(), content: "hi".to_string() }];
+        let high = Some(ReasoningEffort::High);
+
+        let v = build_chat_request_json("gpt-4o", &messages, false, 100, 0.5, true, None, high, false);
+        assert!(v.get("reasoning_effort").is_none());
+
+        let v = build_chat_request_json("o3-mini", &messages, false, 100, 0.5, true, None, high, false);
+        assert_eq!(v["reasoning_effort"], "high");
+
+        // Learned from an earlier rejection
+        let v = build_chat_request_json("my-reasoner", &messages, true, 100, 0.5, true, None, high, false);
+        assert_eq!(v["reasoning_effort"], "high");
+
+        let v = build_chat_request_json("o3-mini", &messages, true, 100, 0.5, true, None, None, false);
+        assert!(v.get("reasoning_effort").is_none());
+    }
+
+    #[test]
+    fn reasoning_effort_for_non_streaming_request() {
+        let low = Some(ReasoningEffort::Low);
+        assert_eq!(reasoning_effort_for("openai/gpt-5-mini", false, low).as_deref(), Some("low"));
+        assert_eq!(reasoning_effort_for("o1", false, low).as_deref(), Some("low"));
+        assert_eq!(reasoning_effort_for("gpt-4.1", false, low), None);
+        assert_eq!(reasoning_effort_for("llama3.2:latest", false, low), None);
+
+        let request = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![],
+            max_tokens: Some(100),
+            max_completion_tokens: None,
+            temperature: Some(0.5),
+            seed: None,
+            response_format: None,
+            reasoning_effort: reasoning_effort_for("gpt-4o", false, low),
+        };
+        assert!(!serde_json::to_string(&request).unwrap().contains("reasoning_effort"));
     }
 
     #[test]
@@ -406,7 +910,7 @@ mod tests {
             ChatMessage { role: "user".to_string(), content: "hi".to_string() },
         ];
 
-        let v = build_chat_request_json("o1-preview", &messages, true, 999, 0.2, true);
+        let v = build_chat_request_json("o1-preview", &messages, true, 999, 0.2, true, None, None, false);
         let vv: Value = serde_json::from_value(v).unwrap();
 
         assert_eq!(vv["model"], "o1-preview");
@@ -1,15 +1,32 @@
 // src/types.rs
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
 // =============================================================================
 // OPENAI API TYPES
 // =============================================================================
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ChatMessage {
     pub role: String,
     pub content: String,
 }
 
+impl ChatMessage {
+    pub fn user(content: &str) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    pub fn assistant(content: &str) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct ChatCompletionRequest {
     pub model: String,
@@ -20,16 +37,35 @@ pub struct ChatCompletionRequest {
     pub max_completion_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ChatCompletionResponse {
     pub choices: Vec<ChatChoice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// Token counts for one request (`--show-usage`)
+#[derive(Debug, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ChatChoice {
     pub message: ChatMessageResponse,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,6 +94,132 @@ pub struct ApiError {
 #[derive(Debug, Deserialize)]
 pub struct ApiErrorDetail {
     pub message: Option<String>,
+    #[serde(default)]
+    pub param: Option<String>,
+}
+
+/// Classified API failure, parsed from the HTTP status and error body.
+/// Retry decisions match on the variant instead of the error wording.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiFailure {
+    RateLimited { message: String },
+    BadParam { param: String, message: String },
+    Unauthorized { message: String },
+    ServerError { message: String },
+    Other { message: String },
+}
+
+impl ApiFailure {
+    pub fn from_response(status: StatusCode, body: &str) -> Self {
+        let detail = serde_json::from_str::<ApiError>(body)
+            .ok()
+            .and_then(|e| e.error);
+
+        let (msg, param) = match detail {
+            Some(d) => {
+                let param = d
+                    .param
+                    .clone()
+                    .or_else(|| d.message.as_deref().and_then(quoted_param));
+                (d.message, param)
+            }
+            None => (None, None),
+        };
+
+        let message = format!(
+            "API error ({}): {}",
+            status,
+            msg.unwrap_or_else(|| body.chars().take(500).collect())
+        );
+
+        match status.as_u16() {
+            429 => Self::RateLimited { message },
+            401 | 403 => Self::Unauthorized { message },
+            400 | 422 if param.is_some() => Self::BadParam {
+                param: param.unwrap_or_default(),
+                message,
+            },
+            s if s >= 500 => Self::ServerError { message },
+            _ => Self::Other { message },
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::RateLimited { message }
+            | Self::BadParam { message, .. }
+            | Self::Unauthorized { message }
+            | Self::ServerError { message }
+            | Self::Other { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for ApiFailure {}
+
+/// Pull a parameter name out of messages like "Unsupported parameter: 'max_tokens'"
+/// for providers that don't fill the structured `param` field.
+fn quoted_param(message: &str) -> Option<String> {
+    let start = message.find('\'')? + 1;
+    let len = message[start..].find('\'')?;
+    let name = &message[start..start + len];
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+        Some(name.to_string())
+    } else {
+        None
+    }
+}
+
+// =============================================================================
+// EMPTY RESPONSES
+// =============================================================================
+/// Finish reasons meaning the token limit cut the answer off (OpenAI, Claude, Gemini)
+const LENGTH_REASONS: &[&str] = &["length", "max_tokens", "MAX_TOKENS"];
+
+/// Finish reasons meaning a safety or content filter stopped the answer
+const FILTER_REASONS: &[&str] = &[
+    "content_filter",
+    "refusal",
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "OTHER",
+];
+
+/// The trimmed response text, or an error explaining an empty answer.
+pub fn response_text(api: &str, text: Option<&str>, finish_reason: Option<&str>) -> anyhow::Result<String> {
+    match text.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(t) => Ok(t.to_string()),
+        None => Err(empty_response_error(api, finish_reason)),
+    }
+}
+
+/// Error for an empty answer, with a hint chosen from the finish reason.
+pub fn empty_response_error(api: &str, finish_reason: Option<&str>) -> anyhow::Error {
+    let reason = finish_reason.map(str::trim).filter(|r| !r.is_empty());
+    let hint = match reason {
+        Some(r) if LENGTH_REASONS.contains(&r) => {
+            "The model hit the token limit before answering; increase --max-tokens \
+             (reasoning models spend tokens before any output)."
+        }
+        Some(r) if FILTER_REASONS.contains(&r) => {
+            "The answer was blocked by a content filter; check the provider's safety settings \
+             or the diff for sensitive content."
+        }
+        _ => "Try increasing --max-tokens, or check the provider's content filters.",
+    };
+    match reason {
+        Some(r) => anyhow::anyhow!("Empty response from {} (finish reason: {}). {}", api, r, hint),
+        None => anyhow::anyhow!("Empty response from {}. {}", api, hint),
+    }
 }
 
 // =============================================================================
@@ -73,6 +235,9 @@ pub struct ClaudeRequest {
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    /// Extended thinking: `{"type": "enabled", "budget_tokens": N}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<serde_json::Value>,
 }
 
 // Streaming event chunks (delta-style)
@@ -85,11 +250,26 @@ pub struct ClaudeStreamDelta {
 #[derive(Debug, Deserialize)]
 pub struct ClaudeTextDelta {
     pub text: Option<String>,
+    /// Set on the final `message_delta` event
+    #[serde(default)]
+    pub stop_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ClaudeResponse {
     pub content: Vec<ClaudeContent>,
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    #[serde(default)]
+    pub usage: Option<ClaudeUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaudeUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,6 +277,39 @@ pub struct ClaudeContent {
     pub text: Option<String>,
 }
 
+// =============================================================================
+// BEDROCK API TYPES
+// =============================================================================
+/// Anthropic messages body for Bedrock `invoke-model` (the response is a `ClaudeResponse`)
+#[cfg(feature = "bedrock")]
+#[derive(Debug, Serialize)]
+pub struct BedrockClaudeRequest {
+    pub anthropic_version: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<serde_json::Value>,
+}
+
+#[cfg(feature = "bedrock")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BedrockProfilesResponse {
+    #[serde(default)]
+    pub inference_profile_summaries: Vec<BedrockProfile>,
+}
+
+#[cfg(feature = "bedrock")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BedrockProfile {
+    pub inference_profile_id: String,
+}
+
 // =============================================================================
 // GEMINI API TYPES
 // =============================================================================
@@ -109,6 +322,9 @@ pub struct GeminiGenerateContentRequest {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GeminiContent {
+    /// "user" or "model"; omitted for system instructions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
     pub parts: Vec<GeminiPart>,
 }
 
@@ -120,11 +336,32 @@ pub struct GeminiPart {
 #[derive(Debug, Deserialize)]
 pub struct GeminiGenerateContentResponse {
     pub candidates: Option<Vec<GeminiCandidate>>,
+    /// Present when the prompt itself was blocked
+    #[serde(default, rename = "promptFeedback")]
+    pub prompt_feedback: Option<GeminiPromptFeedback>,
+    #[serde(default, rename = "usageMetadata")]
+    pub usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeminiUsage {
+    #[serde(default, rename = "promptTokenCount")]
+    pub prompt_token_count: u64,
+    #[serde(default, rename = "candidatesTokenCount")]
+    pub candidates_token_count: u64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GeminiCandidate {
     pub content: Option<GeminiContent>,
+    #[serde(default, rename = "finishReason")]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeminiPromptFeedback {
+    #[serde(default, rename = "blockReason")]
+    pub block_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -172,6 +409,9 @@ mod tests {
             max_tokens: Some(1024),
             max_completion_tokens: None,
             temperature: Some(0.7),
+            seed: None,
+            response_format: None,
+            reasoning_effort: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("\"model\":\"gpt-4o\""));
@@ -245,6 +485,75 @@ mod tests {
         assert!(err.error.is_none());
     }
 
+    #[test]
+    fn api_error_deserializes_param() {
+        let json = r#"{"error": {"message": "Unsupported value", "param": "temperature"}}"#;
+        let err: ApiError = serde_json::from_str(json).unwrap();
+        assert_eq!(err.error.unwrap().param, Some("temperature".to_string()));
+    }
+
+    fn failure(status: u16, body: &str) -> ApiFailure {
+        ApiFailure::from_response(StatusCode::from_u16(status).unwrap(), body)
+    }
+
+    #[test]
+    fn api_failure_rate_limited() {
+        let f = failure(429, r#"{"error": {"message": "Rate limit reached"}}"#);
+        assert!(matches!(f, ApiFailure::RateLimited { .. }));
+        assert_eq!(f.message(), "API error (429 Too Many Requests): Rate limit reached");
+    }
+
+    #[test]
+    fn api_failure_unauthorized() {
+        let f = failure(401, r#"{"error": {"message": "Invalid API key"}}"#);
+        assert!(matches!(f, ApiFailure::Unauthorized { .. }));
+        let f = failure(403, "forbidden");
+        assert!(matches!(f, ApiFailure::Unauthorized { .. }));
+    }
+
+    #[test]
+    fn api_failure_bad_param_from_field() {
+        let body = r#"{"error": {"message": "Unsupported value: 'temperature' does not support 0.5", "type": "invalid_request_error", "param": "temperature", "code": "unsupported_value"}}"#;
+        let f = failure(400, body);
+        assert_eq!(
+            f,
+            ApiFailure::BadParam {
+                param: "temperature".into(),
+                message: "API error (400 Bad Request): Unsupported value: 'temperature' does not support 0.5".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn api_failure_bad_param_from_message() {
+        let body = r#"{"error": {"message": "Unsupported parameter: 'max_tokens' is not supported with this model."}}"#;
+        let f = failure(400, body);
+        assert!(matches!(f, ApiFailure::BadParam { ref param, .. } if param == "max_tokens"));
+    }
+
+    #[test]
+    fn api_failure_bad_request_without_param_is_other() {
+        let f = failure(400, r#"{"error": {"message": "model 'gpt-x' not found"}}"#);
+        assert!(matches!(f, ApiFailure::Other { .. }));
+    }
+
+    #[test]
+    fn api_failure_server_error() {
+        let f = failure(503, "upstream unavailable");
+        assert!(matches!(f, ApiFailure::ServerError { .. }));
+        assert_eq!(f.to_string(), "API error (503 Service Unavailable): upstream unavailable");
+        let f = failure(529, r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#);
+        assert!(matches!(f, ApiFailure::ServerError { .. }));
+    }
+
+    #[test]
+    fn api_failure_unparseable_body_truncated() {
+        let body = "x".repeat(1000);
+        let f = failure(404, &body);
+        assert!(matches!(f, ApiFailure::Other { .. }));
+        assert!(f.message().len() < 600);
+    }
+
     #[test]
     fn claude_request_serializes() {
         let req = ClaudeRequest {
@@ -257,6 +566,7 @@ mod tests {
             max_tokens: 1024,
             temperature: Some(0.7),
             stream: None,
+            thinking: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("\"model\":\"claude-sonnet-4-5-20250929\""));
@@ -276,6 +586,7 @@ mod tests {
             max_tokens: 500,
             temperature: None,
             stream: None,
+            thinking: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(!json.contains("temperature"));
@@ -294,6 +605,7 @@ mod tests {
             max_tokens: 10,
             temperature: None,
             stream: Some(true),
+            thinking: None,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("\"stream\":true"));
@@ -310,6 +622,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn response_text_trims_and_rejects_blank() {
+        assert_eq!(response_text("API", Some("  Fix bug \n"), Some("stop")).unwrap(), "Fix bug");
+        assert!(response_text("API", Some(" \n "), Some("stop")).is_err());
+        assert!(response_text("API", None, None).is_err());
+    }
+
+    #[test]
+    fn empty_response_error_suggests_max_tokens_on_length() {
+        for reason in ["length", "max_tokens", "MAX_TOKENS"] {
+            let msg = empty_response_error("API", Some(reason)).to_string();
+            assert!(msg.contains(&format!("finish reason: {}", reason)), "{}", msg);
+            assert!(msg.contains("--max-tokens"), "{}", msg);
+        }
+    }
+
+    #[test]
+    fn empty_response_error_mentions_content_filter() {
+        for reason in ["content_filter", "refusal", "SAFETY"] {
+            let msg = empty_response_error("Gemini API", Some(reason)).to_string();
+            assert!(msg.starts_with("Empty response from Gemini API"), "{}", msg);
+            assert!(msg.contains("content filter"), "{}", msg);
+        }
+    }
+
+    #[test]
+    fn empty_response_error_without_reason() {
+        let msg = empty_response_error("Claude API", None).to_string();
+        assert!(!msg.contains("finish reason"));
+        assert!(msg.contains("--max-tokens") && msg.contains("content filters"));
+        let blank = empty_response_error("Claude API", Some(" ")).to_string();
+        assert_eq!(blank, msg);
+    }
+
+    #[test]
+    fn usage_blocks_deserialize() {
+        let openai = r#"{"choices":[],"usage":{"prompt_tokens":120,"completion_tokens":15,"total_tokens":135}}"#;
+        let usage = serde_json::from_str::<ChatCompletionResponse>(openai).unwrap().usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (120, 15));
+
+        let claude = r#"{"content":[],"usage":{"input_tokens":80,"output_tokens":9}}"#;
+        let usage = serde_json::from_str::<ClaudeResponse>(claude).unwrap().usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (80, 9));
+
+        let gemini = r#"{"candidates":[],"usageMetadata":{"promptTokenCount":42,"candidatesTokenCount":7}}"#;
+        let usage = serde_json::from_str::<GeminiGenerateContentResponse>(gemini)
+            .unwrap()
+            .usage_metadata
+            .unwrap();
+        assert_eq!((usage.prompt_token_count, usage.candidates_token_count), (42, 7));
+    }
+
+    #[test]
+    fn usage_block_is_optional() {
+        let resp: ChatCompletionResponse = serde_json::from_str(r#"{"choices":[]}"#).unwrap();
+        assert!(resp.usage.is_none());
+        let resp: ClaudeResponse = serde_json::from_str(r#"{"content":[]}"#).unwrap();
+        assert!(resp.usage.is_none());
+    }
+
+    #[test]
+    fn finish_reasons_deserialize() {
+        let openai = r#"{"choices":[{"message":{"content":""},"finish_reason":"length"}]}"#;
+        let resp: ChatCompletionResponse = serde_json::from_str(openai).unwrap();
+        assert_eq!(resp.choices[0].finish_reason.as_deref(), Some("length"));
+
+        let claude = r#"{"content":[],"stop_reason":"max_tokens"}"#;
+        let resp: ClaudeResponse = serde_json::from_str(claude).unwrap();
+        assert_eq!(resp.stop_reason.as_deref(), Some("max_tokens"));
+
+        let gemini = r#"{"candidates":[{"finishReason":"SAFETY"}],"promptFeedback":{"blockReason":"OTHER"}}"#;
+        let resp: GeminiGenerateContentResponse = serde_json::from_str(gemini).unwrap();
+        assert_eq!(resp.candidates.unwrap()[0].finish_reason.as_deref(), Some("SAFETY"));
+        assert_eq!(resp.prompt_feedback.unwrap().block_reason.as_deref(), Some("OTHER"));
+    }
+
     #[test]
     fn claude_response_handles_null_text() {
         let json = r#"{"content": [{"type": "text", "text": null}]}"#;
@@ -337,11 +725,13 @@ mod tests {
     fn gemini_request_serializes() {
         let req = GeminiGenerateContentRequest {
             system_instruction: Some(GeminiContent {
+                role: None,
                 parts: vec![GeminiPart {
                     text: "You are helpful.".to_string(),
                 }],
             }),
             contents: vec![GeminiContent {
+                role: None,
                 parts: vec![GeminiPart {
                     text: "Hello".to_string(),
                 }],
@@ -359,6 +749,7 @@ mod tests {
         let req = GeminiGenerateContentRequest {
             system_instruction: None,
             contents: vec![GeminiContent {
+                role: None,
                 parts: vec![GeminiPart {
                     text: "Hello".to_string(),
                 }],